@@ -0,0 +1,91 @@
+//! This module represents the character sheet of *Chroniques Oubliées Fantasy*: a
+//! [`Character`] of a given [`Race`] and [`Profile`] is described by its six
+//! [`Characteristics`] (FOR, DEX, CON, INT, SAG and CHA), each [`Characteristic`] value
+//! giving a modifier through [`modifier`].
+//!
+//! Most of the sheet is derived from these values and from the level of the character:
+//! its DEF, Initiative, attack bonuses ([`Attack`]), *points de vie*, *points de chance* and
+//! *dés de récupération*. What is rolled for the character (tests, attacks, hit points gained
+//! when levelling up, recoveries) is built out of the dice model, as [`AbilityTest`]s or
+//! [`DiceSet`]s, so that it can be rolled like any other dice.
+//!
+//! A [`Profile`] also gives the [`Voie`]s of five ranked [`Capacity`]s the character gains
+//! when levelling up, the rank of a capacity requiring the previous one in the same voie and
+//! a minimum level ([`min_level`]). The profiles are gathered in a [`Catalogue`] that can be
+//! loaded from TOML or JSON documents, the crate bundling the profiles of the core rules.
+//!
+//! [`AbilityTest`]: crate::model::dice::AbilityTest
+//! [`DiceSet`]: crate::model::dice::DiceSet
+
+mod characteristic;
+pub use characteristic::*;
+
+mod profile;
+pub use profile::*;
+
+mod sheet;
+pub use sheet::*;
+
+mod voie;
+pub use voie::*;
+
+mod catalogue;
+pub use catalogue::*;
+
+use thiserror::Error;
+
+use crate::model::dice::Error as DiceError;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Characteristic {0} does not exist")]
+    CharacteristicUnknown(String),
+
+    #[error("The {0} cannot be worth {1}")]
+    InvalidCharacteristic(Characteristic, u32),
+
+    #[error("Race {0} does not exist")]
+    RaceUnknown(String),
+
+    #[error("Invalid profile: {0}")]
+    InvalidProfile(String),
+
+    #[error("Invalid voie: {0}")]
+    InvalidVoie(String),
+
+    #[error("Invalid catalogue: {0}")]
+    InvalidCatalogue(String),
+
+    #[error("Invalid character name: {0}")]
+    InvalidName(String),
+
+    #[error("A character cannot be of level {0}")]
+    InvalidLevel(u32),
+
+    #[error("The profile of the character has no {0}")]
+    VoieUnknown(String),
+
+    #[error("A voie has no capacity of rank {0}")]
+    InvalidRank(u32),
+
+    #[error("The capacity {0} has already been gained")]
+    CapacityAlreadyGained(String),
+
+    #[error("The capacity of rank {rank} of the {voie} must be gained first")]
+    MissingPrerequisite { voie: String, rank: u32 },
+
+    #[error("A capacity of rank {rank} cannot be gained before level {level}")]
+    RankLocked { rank: u32, level: u32 },
+
+    #[error("Gaining the capacity costs {0} points de capacité")]
+    NotEnoughCapacityPoints(u32),
+
+    #[error("The character has no point de chance left")]
+    NoLuckPointLeft,
+
+    #[error("The character has no dé de récupération left")]
+    NoRecoveryDieLeft,
+
+    #[error(transparent)]
+    FromDice(#[from] DiceError),
+}
@@ -0,0 +1,349 @@
+#[cfg(feature = "catalogue")]
+use super::{Capacity, Characteristic, Voie};
+use super::{Error, Profile};
+#[cfg(feature = "catalogue")]
+use crate::model::dice::Dice;
+
+/// The profiles bundled with the crate, one catalogue TOML document each.
+#[cfg(feature = "catalogue")]
+const BUNDLED_PROFILES: [&str; 5] = [
+    include_str!("../../../data/profiles/guerrier.toml"),
+    include_str!("../../../data/profiles/magicien.toml"),
+    include_str!("../../../data/profiles/pretre.toml"),
+    include_str!("../../../data/profiles/rodeur.toml"),
+    include_str!("../../../data/profiles/voleur.toml"),
+];
+
+/// A `Catalogue` gathers the [`Profile`]s characters can be created from.
+///
+/// Along with the `catalogue` feature, it can be loaded from TOML or JSON documents holding a
+/// `profiles` array, each profile giving its `name`, `hit_dice` (e.g. `"d10"`),
+/// `magic_characteristic` (e.g. `"INT"`), `equipment` and `voies`, each voie giving its
+/// `name` and its five `capacities` from rank 1, each capacity giving its `name` and an
+/// optional `description`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalogue {
+    profiles: Vec<Profile>,
+}
+
+impl Catalogue {
+    /// Creates the catalogue of the given profiles.
+    ///
+    /// # Errors
+    /// [`Error::InvalidCatalogue`] is returned when two profiles have the same name.
+    pub fn new(profiles: Vec<Profile>) -> Result<Self, Error> {
+        for (i, profile) in profiles.iter().enumerate() {
+            if profiles[..i]
+                .iter()
+                .any(|p| p.name().eq_ignore_ascii_case(profile.name()))
+            {
+                return Err(Error::InvalidCatalogue(format!(
+                    "the profile {} appears twice",
+                    profile.name()
+                )));
+            }
+        }
+        Ok(Self { profiles })
+    }
+
+    /// Loads the profiles bundled with the crate: *Guerrier*, *Magicien*, *Prêtre*, *Rôdeur*
+    /// and *Voleur*.
+    ///
+    /// # Errors
+    /// [`Error::InvalidCatalogue`] is returned when the bundled data cannot be loaded.
+    #[cfg(feature = "catalogue")]
+    pub fn bundled() -> Result<Self, Error> {
+        let mut profiles = Vec::new();
+        for document in BUNDLED_PROFILES {
+            profiles.extend(Self::from_toml(document)?.profiles);
+        }
+        Self::new(profiles)
+    }
+
+    /// Loads the catalogue described by a TOML document.
+    ///
+    /// # Errors
+    /// [`Error::InvalidCatalogue`] is returned when the document cannot be parsed, other
+    /// errors being returned when one of its profiles is invalid.
+    #[cfg(feature = "catalogue")]
+    pub fn from_toml(document: &str) -> Result<Self, Error> {
+        toml::from_str::<data::Catalogue>(document)
+            .map_err(|e| Error::InvalidCatalogue(e.to_string()))?
+            .try_into()
+    }
+
+    /// Loads the catalogue described by a JSON document.
+    ///
+    /// # Errors
+    /// [`Error::InvalidCatalogue`] is returned when the document cannot be parsed, other
+    /// errors being returned when one of its profiles is invalid.
+    #[cfg(feature = "catalogue")]
+    pub fn from_json(document: &str) -> Result<Self, Error> {
+        serde_json::from_str::<data::Catalogue>(document)
+            .map_err(|e| Error::InvalidCatalogue(e.to_string()))?
+            .try_into()
+    }
+
+    /// `profile` returns the profile with the given name, ignoring the ASCII case.
+    #[must_use]
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles
+            .iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /// `profiles` returns all the profiles of the catalogue.
+    pub fn profiles(&self) -> impl Iterator<Item = &Profile> {
+        self.profiles.iter()
+    }
+}
+
+/// The layout of the documents the catalogue is loaded from, validated when turned into the
+/// structures of the model.
+#[cfg(feature = "catalogue")]
+mod data {
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub(super) struct Catalogue {
+        pub(super) profiles: Vec<Profile>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub(super) struct Profile {
+        pub(super) name: String,
+        pub(super) hit_dice: String,
+        pub(super) magic_characteristic: String,
+        #[serde(default)]
+        pub(super) equipment: Vec<String>,
+        #[serde(default)]
+        pub(super) voies: Vec<Voie>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub(super) struct Voie {
+        pub(super) name: String,
+        pub(super) capacities: Vec<Capacity>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub(super) struct Capacity {
+        pub(super) name: String,
+        pub(super) description: Option<String>,
+    }
+}
+
+#[cfg(feature = "catalogue")]
+impl TryFrom<data::Catalogue> for Catalogue {
+    type Error = Error;
+
+    fn try_from(value: data::Catalogue) -> Result<Self, Self::Error> {
+        Self::new(
+            value
+                .profiles
+                .into_iter()
+                .map(Profile::try_from)
+                .collect::<Result<_, _>>()?,
+        )
+    }
+}
+
+#[cfg(feature = "catalogue")]
+impl TryFrom<data::Profile> for Profile {
+    type Error = Error;
+
+    fn try_from(value: data::Profile) -> Result<Self, Self::Error> {
+        let hit_dice = Dice::try_from(value.hit_dice.as_str())?;
+        let magic_characteristic = Characteristic::try_from(value.magic_characteristic.as_str())?;
+        value.voies.into_iter().try_fold(
+            Profile::new(value.name, hit_dice, magic_characteristic)?
+                .with_equipment(value.equipment),
+            |profile, voie| profile.with_voie(voie.try_into()?),
+        )
+    }
+}
+
+#[cfg(feature = "catalogue")]
+impl TryFrom<data::Voie> for Voie {
+    type Error = Error;
+
+    fn try_from(value: data::Voie) -> Result<Self, Self::Error> {
+        let capacities = value
+            .capacities
+            .into_iter()
+            .map(|c| {
+                let capacity = Capacity::new(c.name)?;
+                Ok(match c.description {
+                    Some(description) => capacity.with_description(description),
+                    None => capacity,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Voie::new(value.name, capacities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "catalogue")]
+    use crate::model::character::{Character, Characteristics, Race, VOIE_LEN};
+    use crate::model::{character::Characteristic, dice::Dice};
+
+    #[test]
+    fn cannot_build_catalogues_with_duplicate_profiles() {
+        let profile = || Profile::new("Guerrier", Dice::D10, Characteristic::Int).unwrap();
+        assert!(Catalogue::new(vec![profile()]).is_ok());
+        assert!(matches!(
+            Catalogue::new(vec![profile(), profile()]),
+            Err(Error::InvalidCatalogue(_))
+        ));
+    }
+
+    #[cfg(feature = "catalogue")]
+    #[test]
+    fn can_load_the_bundled_catalogue() {
+        let catalogue = Catalogue::bundled().unwrap();
+        let names = catalogue.profiles().map(Profile::name).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["Guerrier", "Magicien", "Prêtre", "Rôdeur", "Voleur"]
+        );
+        for profile in catalogue.profiles() {
+            assert_eq!(profile.voies().count(), 5, "{}", profile.name());
+            assert!(!profile.equipment().is_empty(), "{}", profile.name());
+            for voie in profile.voies() {
+                assert_eq!(voie.capacities().count(), VOIE_LEN);
+            }
+        }
+
+        let guerrier = catalogue.profile("guerrier").unwrap();
+        assert_eq!(guerrier.hit_dice(), Dice::D10);
+        let magicien = catalogue.profile("Magicien").unwrap();
+        assert_eq!(magicien.hit_dice(), Dice::D4);
+        assert_eq!(magicien.magic_characteristic(), Characteristic::Int);
+        assert_eq!(
+            catalogue.profile("Prêtre").unwrap().magic_characteristic(),
+            Characteristic::Sag
+        );
+        assert!(catalogue.profile("Barbare").is_none());
+
+        let mut character = Character::new(
+            "Grimdal",
+            Race::Nain,
+            guerrier.clone(),
+            Characteristics::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            character.gain_capacity("Voie du combat", 1).unwrap().name(),
+            "Vivacité"
+        );
+    }
+
+    #[cfg(feature = "catalogue")]
+    #[test]
+    fn can_load_each_bundled_profile_as_a_catalogue() {
+        for document in BUNDLED_PROFILES {
+            let catalogue = Catalogue::from_toml(document).unwrap();
+            assert_eq!(catalogue.profiles().count(), 1);
+        }
+    }
+
+    #[cfg(feature = "catalogue")]
+    #[test]
+    fn can_load_catalogues_from_toml_and_json() {
+        let toml = r#"
+            [[profiles]]
+            name = "Barde"
+            hit_dice = "d6"
+            magic_characteristic = "CHA"
+            equipment = ["Rapière", "Luth"]
+
+            [[profiles.voies]]
+            name = "Voie du musicien"
+            capacities = [
+              { name = "Chant des héros", description = "+1 en attaque pour les alliés" },
+              { name = "Attaque sonore" },
+              { name = "Zone de silence" },
+              { name = "Danse irrésistible" },
+              { name = "Musique fascinante" },
+            ]
+        "#;
+        let json = r#"{
+            "profiles": [{
+                "name": "Barde",
+                "hit_dice": "d6",
+                "magic_characteristic": "CHA",
+                "equipment": ["Rapière", "Luth"],
+                "voies": [{
+                    "name": "Voie du musicien",
+                    "capacities": [
+                        {"name": "Chant des héros", "description": "+1 en attaque pour les alliés"},
+                        {"name": "Attaque sonore"},
+                        {"name": "Zone de silence"},
+                        {"name": "Danse irrésistible"},
+                        {"name": "Musique fascinante"}
+                    ]
+                }]
+            }]
+        }"#;
+
+        let catalogue = Catalogue::from_toml(toml).unwrap();
+        assert_eq!(catalogue, Catalogue::from_json(json).unwrap());
+
+        let barde = catalogue.profile("Barde").unwrap();
+        assert_eq!(barde.hit_dice(), Dice::D6);
+        assert_eq!(barde.magic_characteristic(), Characteristic::Cha);
+        assert_eq!(barde.equipment(), ["Rapière", "Luth"]);
+        let capacity = barde.voie("Voie du musicien").unwrap().capacity(1).unwrap();
+        assert_eq!(capacity.name(), "Chant des héros");
+        assert_eq!(
+            capacity.description(),
+            Some("+1 en attaque pour les alliés")
+        );
+    }
+
+    #[cfg(feature = "catalogue")]
+    #[test]
+    fn cannot_load_invalid_catalogues() {
+        let profile = |hit_dice: &str, magic: &str, capacities: &str| {
+            format!(
+                r#"{{"profiles": [{{"name": "Barde", "hit_dice": "{hit_dice}",
+                "magic_characteristic": "{magic}",
+                "voies": [{{"name": "Voie du musicien", "capacities": [{capacities}]}}]}}]}}"#
+            )
+        };
+        let five = r#"{"name": "a"}, {"name": "b"}, {"name": "c"}, {"name": "d"}, {"name": "e"}"#;
+
+        assert!(Catalogue::from_json(&profile("d6", "CHA", five)).is_ok());
+        assert!(matches!(
+            Catalogue::from_json(&profile("d7x", "CHA", five)),
+            Err(Error::FromDice(_))
+        ));
+        assert!(matches!(
+            Catalogue::from_json(&profile("d20", "CHA", five)),
+            Err(Error::InvalidProfile(_))
+        ));
+        assert!(matches!(
+            Catalogue::from_json(&profile("d6", "CHR", five)),
+            Err(Error::CharacteristicUnknown(_))
+        ));
+        assert!(matches!(
+            Catalogue::from_json(&profile("d6", "CHA", r#"{"name": "a"}"#)),
+            Err(Error::InvalidVoie(_))
+        ));
+        assert!(matches!(
+            Catalogue::from_json(r#"{"profiles": [{"name": "Barde"}]}"#),
+            Err(Error::InvalidCatalogue(_))
+        ));
+        assert!(matches!(
+            Catalogue::from_toml("profiles = 3"),
+            Err(Error::InvalidCatalogue(_))
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt::Display;
+
+use super::Error;
+
+/// The lowest value a characteristic can be worth.
+pub const MIN_CHARACTERISTIC: u32 = 1;
+
+/// The highest value a characteristic can be worth.
+pub const MAX_CHARACTERISTIC: u32 = 30;
+
+/// The value a characteristic is worth by default, giving no modifier.
+pub const DEFAULT_CHARACTERISTIC: u32 = 10;
+
+/// The six characteristics of a character.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Characteristic {
+    /// *Force*, the physical strength.
+    For,
+    /// *Dextérité*, the agility and the reflexes.
+    Dex,
+    /// *Constitution*, the endurance and the health.
+    Con,
+    /// *Intelligence*, the memory and the reasoning.
+    Int,
+    /// *Sagesse*, the perception and the willpower.
+    Sag,
+    /// *Charisme*, the force of personality.
+    Cha,
+}
+
+impl Characteristic {
+    /// All the characteristics, in the order of the character sheet.
+    pub const ALL: [Characteristic; 6] = [
+        Characteristic::For,
+        Characteristic::Dex,
+        Characteristic::Con,
+        Characteristic::Int,
+        Characteristic::Sag,
+        Characteristic::Cha,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            Characteristic::For => 0,
+            Characteristic::Dex => 1,
+            Characteristic::Con => 2,
+            Characteristic::Int => 3,
+            Characteristic::Sag => 4,
+            Characteristic::Cha => 5,
+        }
+    }
+}
+
+impl TryFrom<&str> for Characteristic {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.to_string().eq_ignore_ascii_case(value))
+            .ok_or_else(|| Error::CharacteristicUnknown(value.to_string()))
+    }
+}
+
+impl Display for Characteristic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Characteristic::For => write!(f, "FOR"),
+            Characteristic::Dex => write!(f, "DEX"),
+            Characteristic::Con => write!(f, "CON"),
+            Characteristic::Int => write!(f, "INT"),
+            Characteristic::Sag => write!(f, "SAG"),
+            Characteristic::Cha => write!(f, "CHA"),
+        }
+    }
+}
+
+/// Returns the modifier given by a characteristic value: 0 for 10 and 11, then one more
+/// every two points above (e.g. +2 for 14 and 15) and one less every two points below
+/// (e.g. -1 for 8 and 9).
+#[must_use]
+pub fn modifier(value: u32) -> i64 {
+    (i64::from(value) - 10).div_euclid(2)
+}
+
+/// The values of the six [`Characteristic`]s of a character.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Characteristics([u32; 6]);
+
+impl Characteristics {
+    /// Creates the characteristics worth the given values, in the order of
+    /// [`Characteristic::ALL`].
+    ///
+    /// # Errors
+    /// [`Error::InvalidCharacteristic`] is returned when a value is not between
+    /// [`MIN_CHARACTERISTIC`] and [`MAX_CHARACTERISTIC`].
+    pub fn new(values: [u32; 6]) -> Result<Self, Error> {
+        Characteristic::ALL
+            .into_iter()
+            .try_fold(Self::default(), |characteristics, c| {
+                characteristics.with_value(c, values[c.index()])
+            })
+    }
+
+    /// Changes the value of a characteristic.
+    ///
+    /// # Errors
+    /// [`Error::InvalidCharacteristic`] is returned when the value is not between
+    /// [`MIN_CHARACTERISTIC`] and [`MAX_CHARACTERISTIC`].
+    pub fn with_value(self, characteristic: Characteristic, value: u32) -> Result<Self, Error> {
+        if !(MIN_CHARACTERISTIC..=MAX_CHARACTERISTIC).contains(&value) {
+            return Err(Error::InvalidCharacteristic(characteristic, value));
+        }
+        let mut values = self.0;
+        values[characteristic.index()] = value;
+        Ok(Self(values))
+    }
+
+    /// `value` returns what the characteristic is worth.
+    #[must_use]
+    pub fn value(&self, characteristic: Characteristic) -> u32 {
+        self.0[characteristic.index()]
+    }
+
+    /// `modifier` returns the modifier given by the characteristic.
+    #[must_use]
+    pub fn modifier(&self, characteristic: Characteristic) -> i64 {
+        modifier(self.value(characteristic))
+    }
+}
+
+impl Default for Characteristics {
+    fn default() -> Self {
+        Self([DEFAULT_CHARACTERISTIC; 6])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_compute_modifiers() {
+        let test_cases = &[
+            (1, -5),
+            (3, -4),
+            (4, -3),
+            (7, -2),
+            (9, -1),
+            (10, 0),
+            (11, 0),
+            (12, 1),
+            (15, 2),
+            (18, 4),
+            (21, 5),
+            (30, 10),
+        ];
+        for (value, expected) in test_cases {
+            assert_eq!(modifier(*value), *expected, "value {value}");
+        }
+    }
+
+    #[test]
+    fn can_parse_characteristics() {
+        for c in Characteristic::ALL {
+            assert_eq!(Characteristic::try_from(c.to_string().as_str()).unwrap(), c);
+        }
+        assert_eq!(
+            Characteristic::try_from("sag").unwrap(),
+            Characteristic::Sag
+        );
+        assert!(matches!(
+            Characteristic::try_from("STR"),
+            Err(Error::CharacteristicUnknown(_))
+        ));
+    }
+
+    #[test]
+    fn can_build_characteristics() {
+        let characteristics = Characteristics::new([16, 13, 14, 8, 10, 9]).unwrap();
+        assert_eq!(characteristics.value(Characteristic::For), 16);
+        assert_eq!(characteristics.modifier(Characteristic::For), 3);
+        assert_eq!(characteristics.modifier(Characteristic::Dex), 1);
+        assert_eq!(characteristics.modifier(Characteristic::Int), -1);
+        assert_eq!(characteristics.modifier(Characteristic::Cha), -1);
+
+        let characteristics = characteristics.with_value(Characteristic::Cha, 12).unwrap();
+        assert_eq!(characteristics.modifier(Characteristic::Cha), 1);
+
+        assert_eq!(Characteristics::default().modifier(Characteristic::Sag), 0);
+    }
+
+    #[test]
+    fn cannot_build_invalid_characteristics() {
+        assert!(matches!(
+            Characteristics::new([16, 0, 14, 8, 10, 9]),
+            Err(Error::InvalidCharacteristic(Characteristic::Dex, 0))
+        ));
+        assert!(matches!(
+            Characteristics::default().with_value(Characteristic::Con, 31),
+            Err(Error::InvalidCharacteristic(Characteristic::Con, 31))
+        ));
+    }
+}
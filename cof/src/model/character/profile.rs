@@ -0,0 +1,255 @@
+use std::fmt::Display;
+
+use super::{Characteristic, Error, Voie};
+use crate::model::dice::Dice;
+
+/// The maximum length of the name of a profile or of a character.
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Checks that a name is neither blank nor too long, returning why it is invalid otherwise.
+pub(super) fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("the name cannot be blank".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!(
+            "the name cannot be longer than {MAX_NAME_LEN} characters"
+        ));
+    }
+    Ok(())
+}
+
+/// The races a character of *Chroniques Oubliées Fantasy* can be of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Race {
+    Humain,
+    ElfeHaut,
+    ElfeSylvain,
+    DemiElfe,
+    DemiOrc,
+    Gnome,
+    Halfelin,
+    Nain,
+}
+
+impl Race {
+    /// All the races.
+    pub const ALL: [Race; 8] = [
+        Race::Humain,
+        Race::ElfeHaut,
+        Race::ElfeSylvain,
+        Race::DemiElfe,
+        Race::DemiOrc,
+        Race::Gnome,
+        Race::Halfelin,
+        Race::Nain,
+    ];
+}
+
+impl TryFrom<&str> for Race {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|r| r.to_string().eq_ignore_ascii_case(value))
+            .ok_or_else(|| Error::RaceUnknown(value.to_string()))
+    }
+}
+
+impl Display for Race {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Race::Humain => write!(f, "Humain"),
+            Race::ElfeHaut => write!(f, "Elfe haut"),
+            Race::ElfeSylvain => write!(f, "Elfe sylvain"),
+            Race::DemiElfe => write!(f, "Demi-elfe"),
+            Race::DemiOrc => write!(f, "Demi-orc"),
+            Race::Gnome => write!(f, "Gnome"),
+            Race::Halfelin => write!(f, "Halfelin"),
+            Race::Nain => write!(f, "Nain"),
+        }
+    }
+}
+
+/// A `Profile` is the class of a character (e.g. *Guerrier* or *Magicien*): it gives the
+/// *dé de vie* rolled for its hit points, the characteristic its magic attacks rely on, its
+/// starting equipment and the [`Voie`]s its capacities are gained from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    name: String,
+    hit_dice: Dice,
+    magic_characteristic: Characteristic,
+    equipment: Vec<String>,
+    voies: Vec<Voie>,
+}
+
+impl Profile {
+    /// Creates the profile of the given name.
+    ///
+    /// # Errors
+    /// [`Error::InvalidProfile`] is returned when the name is blank or longer than
+    /// [`MAX_NAME_LEN`], or when the *dé de vie* is not a numbered dice from d4 to d12.
+    pub fn new(
+        name: impl Into<String>,
+        hit_dice: Dice,
+        magic_characteristic: Characteristic,
+    ) -> Result<Self, Error> {
+        let name = name.into();
+        check_name(&name).map_err(Error::InvalidProfile)?;
+        if !hit_dice.has_numbered_faces() || !(4..=12).contains(&hit_dice.side_count()) {
+            return Err(Error::InvalidProfile(format!(
+                "the dé de vie cannot be a {hit_dice}"
+            )));
+        }
+        Ok(Self {
+            name,
+            hit_dice,
+            magic_characteristic,
+            equipment: Vec::new(),
+            voies: Vec::new(),
+        })
+    }
+
+    /// Sets the equipment a character of the profile starts with.
+    #[must_use]
+    pub fn with_equipment(self, equipment: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            equipment: equipment.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Adds a voie to the profile.
+    ///
+    /// # Errors
+    /// [`Error::InvalidProfile`] is returned when the profile already has a voie of the same
+    /// name.
+    pub fn with_voie(mut self, voie: Voie) -> Result<Self, Error> {
+        if self.voie(voie.name()).is_some() {
+            return Err(Error::InvalidProfile(format!(
+                "the {} appears twice in the {}",
+                voie.name(),
+                self.name
+            )));
+        }
+        self.voies.push(voie);
+        Ok(self)
+    }
+
+    /// `name` returns the name of the profile.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `hit_dice` returns the *dé de vie* of the profile.
+    #[must_use]
+    pub fn hit_dice(&self) -> Dice {
+        self.hit_dice
+    }
+
+    /// `magic_characteristic` returns the characteristic giving the modifier of the magic
+    /// attacks (e.g. INT for a *Magicien*, SAG for a *Prêtre*).
+    #[must_use]
+    pub fn magic_characteristic(&self) -> Characteristic {
+        self.magic_characteristic
+    }
+
+    /// `equipment` returns the equipment a character of the profile starts with.
+    #[must_use]
+    pub fn equipment(&self) -> &[String] {
+        &self.equipment
+    }
+
+    /// `voies` returns the voies of the profile.
+    pub fn voies(&self) -> impl Iterator<Item = &Voie> {
+        self.voies.iter()
+    }
+
+    /// `voie` returns the voie of the profile with the given name, ignoring the ASCII case.
+    #[must_use]
+    pub fn voie(&self, name: &str) -> Option<&Voie> {
+        self.voies
+            .iter()
+            .find(|v| v.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::character::Capacity;
+
+    fn voie(name: &str) -> Voie {
+        let capacities = (1..=5)
+            .map(|rank| Capacity::new(format!("{name} {rank}")).unwrap())
+            .collect();
+        Voie::new(name, capacities).unwrap()
+    }
+
+    #[test]
+    fn can_parse_races() {
+        for race in Race::ALL {
+            assert_eq!(Race::try_from(race.to_string().as_str()).unwrap(), race);
+        }
+        assert_eq!(Race::try_from("demi-orc").unwrap(), Race::DemiOrc);
+        assert!(matches!(Race::try_from("Orc"), Err(Error::RaceUnknown(_))));
+    }
+
+    #[test]
+    fn can_build_profiles() {
+        let profile = Profile::new("Magicien", Dice::D4, Characteristic::Int).unwrap();
+        assert_eq!(profile.name(), "Magicien");
+        assert_eq!(profile.hit_dice(), Dice::D4);
+        assert_eq!(profile.magic_characteristic(), Characteristic::Int);
+        assert!(profile.equipment().is_empty());
+        assert_eq!(profile.voies().count(), 0);
+
+        let profile = profile
+            .with_equipment(["Bâton", "Dague"])
+            .with_voie(voie("Voie de la magie des arcanes"))
+            .unwrap()
+            .with_voie(voie("Voie de la magie destructrice"))
+            .unwrap();
+        assert_eq!(profile.equipment(), ["Bâton", "Dague"]);
+        assert_eq!(profile.voies().count(), 2);
+        assert_eq!(
+            profile
+                .voie("voie de la magie destructrice")
+                .unwrap()
+                .name(),
+            "Voie de la magie destructrice"
+        );
+        assert!(profile.voie("Voie du bouclier").is_none());
+    }
+
+    #[test]
+    fn cannot_build_invalid_profiles() {
+        let test_cases = [
+            (" ", Dice::D10),
+            (&"a".repeat(MAX_NAME_LEN + 1), Dice::D10),
+            ("Guerrier", Dice::D20),
+            ("Guerrier", Dice::D3),
+            ("Guerrier", Dice::Fudge),
+        ];
+        for (name, hit_dice) in test_cases {
+            assert!(
+                matches!(
+                    Profile::new(name, hit_dice, Characteristic::Int),
+                    Err(Error::InvalidProfile(_))
+                ),
+                "{name} {hit_dice}"
+            );
+        }
+
+        let profile = Profile::new("Magicien", Dice::D4, Characteristic::Int)
+            .unwrap()
+            .with_voie(voie("Voie de la magie des arcanes"))
+            .unwrap();
+        assert!(matches!(
+            profile.with_voie(voie("Voie de la magie des arcanes")),
+            Err(Error::InvalidProfile(_))
+        ));
+    }
+}
@@ -0,0 +1,634 @@
+use std::collections::BTreeMap;
+
+use super::{
+    Capacity, Characteristic, Characteristics, Error, Profile, Race, capacity_cost, check_name,
+    min_level,
+};
+use crate::model::dice::{
+    AbilityTest, Dice, DiceSet, DiceTerm, Error as DiceError, Expression, RolledDiceSet,
+};
+
+/// The highest level a character can reach.
+pub const MAX_LEVEL: u32 = 20;
+
+/// The DEF of a character before its DEX modifier and its armour are added.
+pub const BASE_DEFENSE: i64 = 10;
+
+/// The *points de chance* of a character before its CHA modifier is added.
+pub const BASE_LUCK_POINTS: i64 = 2;
+
+/// The number of *dés de récupération* a character gets back when resting.
+pub const MAX_RECOVERY_DICE: u32 = 2;
+
+/// The *points de capacité* of a character of level 1.
+pub const STARTING_CAPACITY_POINTS: u32 = 2;
+
+/// The *points de capacité* a character gains at each level.
+pub const CAPACITY_POINTS_PER_LEVEL: u32 = 2;
+
+/// The attacks a character can make, each one with its own bonus.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Attack {
+    /// The *attaque au contact*, relying on FOR.
+    Contact,
+    /// The *attaque à distance*, relying on DEX.
+    Distance,
+    /// The *attaque magique*, relying on the magic characteristic of the profile.
+    Magic,
+}
+
+/// A `Character` is the character sheet of a player of *Chroniques Oubliées Fantasy*.
+///
+/// The *points de vie*, the *points de chance* and the *dés de récupération* are the only
+/// values that change along the adventure, everything else is derived from the level, the
+/// profile and the characteristics of the character.
+///
+/// The capacities of the character are gained rank after rank in the voies of its profile,
+/// by spending the *points de capacité* it earns when levelling up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Character {
+    name: String,
+    race: Race,
+    profile: Profile,
+    level: u32,
+    characteristics: Characteristics,
+    defense_bonus: i64,
+    max_hit_points: i64,
+    hit_points: i64,
+    luck_points: u32,
+    recovery_dice: u32,
+    capacity_points: u32,
+    ranks: BTreeMap<String, u32>,
+}
+
+impl Character {
+    /// Creates a character of level 1, whose maximum *points de vie* are the highest result of
+    /// its *dé de vie* plus its CON modifier.
+    ///
+    /// # Errors
+    /// [`Error::InvalidName`] is returned when the name is blank or longer than
+    /// [`super::MAX_NAME_LEN`].
+    pub fn new(
+        name: impl Into<String>,
+        race: Race,
+        profile: Profile,
+        characteristics: Characteristics,
+    ) -> Result<Self, Error> {
+        let name = name.into();
+        check_name(&name).map_err(Error::InvalidName)?;
+
+        let con = characteristics.modifier(Characteristic::Con);
+        let max_hit_points = (i64::from(profile.hit_dice().side_count()) + con).max(1);
+        let mut character = Self {
+            name,
+            race,
+            profile,
+            level: 1,
+            characteristics,
+            defense_bonus: 0,
+            max_hit_points,
+            hit_points: max_hit_points,
+            luck_points: 0,
+            recovery_dice: MAX_RECOVERY_DICE,
+            capacity_points: STARTING_CAPACITY_POINTS,
+            ranks: BTreeMap::new(),
+        };
+        character.luck_points = character.max_luck_points();
+        Ok(character)
+    }
+
+    /// Sets the DEF bonus given by the armour and the shield of the character.
+    #[must_use]
+    pub fn with_defense_bonus(self, defense_bonus: i64) -> Self {
+        Self {
+            defense_bonus,
+            ..self
+        }
+    }
+
+    /// `name` returns the name of the character.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `race` returns the race of the character.
+    #[must_use]
+    pub fn race(&self) -> Race {
+        self.race
+    }
+
+    /// `profile` returns the profile of the character.
+    #[must_use]
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// `level` returns the level of the character, from 1 to [`MAX_LEVEL`].
+    #[must_use]
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// `characteristics` returns the values of the characteristics of the character.
+    #[must_use]
+    pub fn characteristics(&self) -> &Characteristics {
+        &self.characteristics
+    }
+
+    /// `modifier` returns the modifier given by a characteristic of the character.
+    #[must_use]
+    pub fn modifier(&self, characteristic: Characteristic) -> i64 {
+        self.characteristics.modifier(characteristic)
+    }
+
+    /// `defense` returns the DEF of the character: 10 plus its DEX modifier and the bonus of
+    /// its armour.
+    #[must_use]
+    pub fn defense(&self) -> i64 {
+        BASE_DEFENSE
+            .saturating_add(self.modifier(Characteristic::Dex))
+            .saturating_add(self.defense_bonus)
+    }
+
+    /// `initiative` returns the Initiative of the character, that is its DEX value.
+    #[must_use]
+    pub fn initiative(&self) -> i64 {
+        i64::from(self.characteristics.value(Characteristic::Dex))
+    }
+
+    /// `attack_bonus` returns the bonus of an attack: the level of the character plus the
+    /// modifier of the characteristic the attack relies on.
+    #[must_use]
+    pub fn attack_bonus(&self, attack: Attack) -> i64 {
+        let characteristic = match attack {
+            Attack::Contact => Characteristic::For,
+            Attack::Distance => Characteristic::Dex,
+            Attack::Magic => self.profile.magic_characteristic(),
+        };
+        i64::from(self.level) + self.modifier(characteristic)
+    }
+
+    /// `max_hit_points` returns the maximum *points de vie* of the character.
+    #[must_use]
+    pub fn max_hit_points(&self) -> i64 {
+        self.max_hit_points
+    }
+
+    /// `hit_points` returns the current *points de vie* of the character, 0 meaning that it
+    /// is out of the fight.
+    #[must_use]
+    pub fn hit_points(&self) -> i64 {
+        self.hit_points
+    }
+
+    /// `max_luck_points` returns the *points de chance* of the character when it starts an
+    /// adventure: 2 plus its CHA modifier.
+    #[must_use]
+    pub fn max_luck_points(&self) -> u32 {
+        let luck_points = BASE_LUCK_POINTS + self.modifier(Characteristic::Cha);
+        u32::try_from(luck_points.max(0)).unwrap_or(u32::MAX)
+    }
+
+    /// `luck_points` returns the *points de chance* the character has left.
+    #[must_use]
+    pub fn luck_points(&self) -> u32 {
+        self.luck_points
+    }
+
+    /// `recovery_dice` returns the *dés de récupération* the character has left.
+    #[must_use]
+    pub fn recovery_dice(&self) -> u32 {
+        self.recovery_dice
+    }
+
+    /// `capacity_points` returns the *points de capacité* the character has left to gain
+    /// capacities.
+    #[must_use]
+    pub fn capacity_points(&self) -> u32 {
+        self.capacity_points
+    }
+
+    /// `rank` returns the rank the character has reached in a voie of its profile, 0 when it
+    /// has not gained any of its capacities.
+    #[must_use]
+    pub fn rank(&self, voie: &str) -> u32 {
+        self.profile
+            .voie(voie)
+            .and_then(|v| self.ranks.get(v.name()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// `capacities` returns the capacities gained by the character, voie after voie.
+    pub fn capacities(&self) -> impl Iterator<Item = &Capacity> {
+        self.profile.voies().flat_map(|v| {
+            let rank = self.ranks.get(v.name()).copied().unwrap_or(0);
+            v.capacities()
+                .take(usize::try_from(rank).unwrap_or(usize::MAX))
+        })
+    }
+
+    /// Gains the capacity of the given rank in a voie of the profile of the character,
+    /// spending its *points de capacité*. Returns the capacity gained.
+    ///
+    /// # Errors
+    /// - [`Error::VoieUnknown`] when the profile has no such voie.
+    /// - [`Error::InvalidRank`] when the rank is not between 1 and [`super::VOIE_LEN`].
+    /// - [`Error::CapacityAlreadyGained`] when the character has already reached the rank.
+    /// - [`Error::MissingPrerequisite`] when the capacity of the previous rank has not been
+    ///   gained.
+    /// - [`Error::RankLocked`] when the character has not reached the [`min_level`] of the
+    ///   rank.
+    /// - [`Error::NotEnoughCapacityPoints`] when the character cannot afford the
+    ///   [`capacity_cost`] of the rank.
+    pub fn gain_capacity(&mut self, voie: &str, rank: u32) -> Result<&Capacity, Error> {
+        let voie = self
+            .profile
+            .voie(voie)
+            .ok_or_else(|| Error::VoieUnknown(voie.to_string()))?;
+        let capacity = voie.capacity(rank).ok_or(Error::InvalidRank(rank))?;
+
+        let reached = self.ranks.get(voie.name()).copied().unwrap_or(0);
+        if rank <= reached {
+            return Err(Error::CapacityAlreadyGained(capacity.name().to_string()));
+        }
+        if rank > reached + 1 {
+            return Err(Error::MissingPrerequisite {
+                voie: voie.name().to_string(),
+                rank: reached + 1,
+            });
+        }
+        if self.level < min_level(rank) {
+            return Err(Error::RankLocked {
+                rank,
+                level: min_level(rank),
+            });
+        }
+        let cost = capacity_cost(rank);
+        self.capacity_points = self
+            .capacity_points
+            .checked_sub(cost)
+            .ok_or(Error::NotEnoughCapacityPoints(cost))?;
+
+        self.ranks.insert(voie.name().to_string(), rank);
+        Ok(capacity)
+    }
+
+    /// Builds the test of a characteristic of the character against a difficulty.
+    ///
+    /// # Errors
+    /// [`Error::FromDice`] is returned when the test cannot be built.
+    pub fn test(
+        &self,
+        characteristic: Characteristic,
+        difficulty: i64,
+    ) -> Result<AbilityTest, Error> {
+        Ok(AbilityTest::new(
+            d20(),
+            self.modifier(characteristic),
+            difficulty,
+        )?)
+    }
+
+    /// Builds the attack test of the character against the DEF of its opponent.
+    ///
+    /// # Errors
+    /// [`Error::FromDice`] is returned when the test cannot be built.
+    pub fn attack(&self, attack: Attack, defense: i64) -> Result<AbilityTest, Error> {
+        Ok(AbilityTest::new(d20(), self.attack_bonus(attack), defense)?)
+    }
+
+    /// `hit_dice_set` returns the dice set rolled for the *points de vie* gained when levelling
+    /// up or spending a *dé de récupération*: the *dé de vie* plus the CON modifier.
+    #[must_use]
+    pub fn hit_dice_set(&self) -> DiceSet {
+        let dices = Expression::Dices(DiceTerm::new(1, self.profile.hit_dice()));
+        let con = self.modifier(Characteristic::Con);
+        DiceSet::from(match con {
+            0 => dices,
+            c if c > 0 => Expression::Add(Box::new(dices), Box::new(Expression::Constant(c))),
+            c => Expression::Sub(Box::new(dices), Box::new(Expression::Constant(-c))),
+        })
+    }
+
+    /// Raises the character of one level, its *points de vie* increasing by the roll of its
+    /// [`Character::hit_dice_set`], with a minimum of 1, and its *points de capacité* by
+    /// [`CAPACITY_POINTS_PER_LEVEL`]. Returns the *points de vie* gained.
+    ///
+    /// # Errors
+    /// [`Error::InvalidLevel`] is returned when the character is already of [`MAX_LEVEL`], and
+    /// [`DiceError::RolledDicesMismatch`] when the dices have not been rolled from the
+    /// [`Character::hit_dice_set`].
+    pub fn level_up(&mut self, rolled_dice_set: &RolledDiceSet) -> Result<i64, Error> {
+        if self.level >= MAX_LEVEL {
+            return Err(Error::InvalidLevel(self.level + 1));
+        }
+        let gained = self.hit_points_rolled(rolled_dice_set)?;
+        self.level += 1;
+        self.capacity_points = self
+            .capacity_points
+            .saturating_add(CAPACITY_POINTS_PER_LEVEL);
+        self.max_hit_points = self.max_hit_points.saturating_add(gained);
+        self.hit_points = self.hit_points.saturating_add(gained);
+        Ok(gained)
+    }
+
+    /// Spends a *dé de récupération*, the character healing the roll of its
+    /// [`Character::hit_dice_set`], with a minimum of 1. Returns the *points de vie* healed.
+    ///
+    /// # Errors
+    /// [`Error::NoRecoveryDieLeft`] is returned when all the *dés de récupération* have been
+    /// spent, and [`DiceError::RolledDicesMismatch`] when the dices have not been rolled from
+    /// the [`Character::hit_dice_set`].
+    pub fn recover(&mut self, rolled_dice_set: &RolledDiceSet) -> Result<i64, Error> {
+        if self.recovery_dice == 0 {
+            return Err(Error::NoRecoveryDieLeft);
+        }
+        let rolled = self.hit_points_rolled(rolled_dice_set)?;
+        self.recovery_dice -= 1;
+        Ok(self.heal(rolled))
+    }
+
+    /// Gets all the *dés de récupération* back after a night of rest.
+    pub fn rest(&mut self) {
+        self.recovery_dice = MAX_RECOVERY_DICE;
+    }
+
+    /// Spends a *point de chance*.
+    ///
+    /// # Errors
+    /// [`Error::NoLuckPointLeft`] is returned when all the *points de chance* have been spent.
+    pub fn spend_luck_point(&mut self) -> Result<(), Error> {
+        self.luck_points = self
+            .luck_points
+            .checked_sub(1)
+            .ok_or(Error::NoLuckPointLeft)?;
+        Ok(())
+    }
+
+    /// Removes *points de vie* from the character, down to 0.
+    pub fn take_damage(&mut self, damage: u32) {
+        self.hit_points = self.hit_points.saturating_sub(damage.into()).max(0);
+    }
+
+    /// Gives *points de vie* back to the character, up to its maximum. Returns the *points de
+    /// vie* actually healed.
+    pub fn heal(&mut self, hit_points: i64) -> i64 {
+        let healed = hit_points.clamp(0, self.max_hit_points - self.hit_points);
+        self.hit_points += healed;
+        healed
+    }
+
+    fn hit_points_rolled(&self, rolled_dice_set: &RolledDiceSet) -> Result<i64, Error> {
+        if rolled_dice_set.dice_set() != self.hit_dice_set() {
+            return Err(DiceError::RolledDicesMismatch.into());
+        }
+        Ok(rolled_dice_set.total().max(1))
+    }
+}
+
+fn d20() -> DiceSet {
+    DiceSet::new(std::iter::once(Dice::D20))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{character::Voie, dice::ScriptedRoller};
+
+    fn guerrier() -> Profile {
+        ["Voie du bouclier", "Voie du combat"].into_iter().fold(
+            Profile::new("Guerrier", Dice::D10, Characteristic::Int).unwrap(),
+            |profile, name| {
+                let capacities = (1..=5)
+                    .map(|rank| Capacity::new(format!("{name} {rank}")).unwrap())
+                    .collect();
+                profile
+                    .with_voie(Voie::new(name, capacities).unwrap())
+                    .unwrap()
+            },
+        )
+    }
+
+    fn nain(values: [u32; 6]) -> Character {
+        Character::new(
+            "Grimdal",
+            Race::Nain,
+            guerrier(),
+            Characteristics::new(values).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn roll(dice_set: DiceSet, results: &[u32]) -> RolledDiceSet {
+        dice_set
+            .roll_with(&mut ScriptedRoller::new(results.iter().copied()))
+            .unwrap()
+    }
+
+    #[test]
+    fn can_derive_the_character_sheet() {
+        let character = nain([16, 13, 14, 8, 10, 12]).with_defense_bonus(4);
+        assert_eq!(character.name(), "Grimdal");
+        assert_eq!(character.race(), Race::Nain);
+        assert_eq!(character.profile().name(), "Guerrier");
+        assert_eq!(character.level(), 1);
+        assert_eq!(character.max_hit_points(), 12);
+        assert_eq!(character.hit_points(), 12);
+        assert_eq!(character.defense(), 15);
+        assert_eq!(character.initiative(), 13);
+        assert_eq!(character.attack_bonus(Attack::Contact), 4);
+        assert_eq!(character.attack_bonus(Attack::Distance), 2);
+        assert_eq!(character.attack_bonus(Attack::Magic), 0);
+        assert_eq!(character.max_luck_points(), 3);
+        assert_eq!(character.luck_points(), 3);
+        assert_eq!(character.recovery_dice(), MAX_RECOVERY_DICE);
+
+        let frail = nain([8, 8, 3, 8, 8, 3]);
+        assert_eq!(frail.max_hit_points(), 6);
+        assert_eq!(frail.max_luck_points(), 0);
+    }
+
+    #[test]
+    fn cannot_create_invalid_characters() {
+        for name in ["", "  ", &"a".repeat(65)] {
+            assert!(matches!(
+                Character::new(name, Race::Humain, guerrier(), Characteristics::default()),
+                Err(Error::InvalidName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn can_roll_tests_and_attacks() {
+        let character = nain([16, 13, 14, 8, 10, 12]);
+
+        let test = character.test(Characteristic::For, 15).unwrap();
+        assert_eq!(test.dice_set().to_string(), "d20");
+        assert_eq!(test.modifier(), 3);
+        assert_eq!(test.difficulty(), 15);
+        let (_, outcome) = test.roll_with(&mut ScriptedRoller::new([12])).unwrap();
+        assert!(outcome.is_success());
+
+        let attack = character.attack(Attack::Distance, 14).unwrap();
+        assert_eq!(attack.modifier(), 2);
+        assert_eq!(attack.difficulty(), 14);
+    }
+
+    #[test]
+    fn can_level_up() {
+        let mut character = nain([16, 13, 14, 8, 10, 12]);
+        assert_eq!(character.hit_dice_set().to_string(), "d10 + 2");
+
+        let gained = character
+            .level_up(&roll(character.hit_dice_set(), &[7]))
+            .unwrap();
+        assert_eq!(gained, 9);
+        assert_eq!(character.level(), 2);
+        assert_eq!(character.max_hit_points(), 21);
+        assert_eq!(character.hit_points(), 21);
+        assert_eq!(character.attack_bonus(Attack::Contact), 5);
+
+        let mut frail = nain([8, 8, 6, 8, 8, 8]);
+        assert_eq!(frail.hit_dice_set().to_string(), "d10 - 2");
+        assert_eq!(
+            frail.level_up(&roll(frail.hit_dice_set(), &[1])).unwrap(),
+            1
+        );
+
+        let other_roll = roll(DiceSet::new(std::iter::once(Dice::D10)), &[7]);
+        assert!(matches!(
+            character.level_up(&other_roll),
+            Err(Error::FromDice(DiceError::RolledDicesMismatch))
+        ));
+        assert_eq!(character.level(), 2);
+
+        for _ in 2..MAX_LEVEL {
+            character
+                .level_up(&roll(character.hit_dice_set(), &[1]))
+                .unwrap();
+        }
+        assert_eq!(character.level(), MAX_LEVEL);
+        assert!(matches!(
+            character.level_up(&roll(character.hit_dice_set(), &[1])),
+            Err(Error::InvalidLevel(21))
+        ));
+    }
+
+    #[test]
+    fn can_take_damage_and_recover() {
+        let mut character = nain([16, 13, 14, 8, 10, 12]);
+        character.take_damage(10);
+        assert_eq!(character.hit_points(), 2);
+        character.take_damage(10);
+        assert_eq!(character.hit_points(), 0);
+
+        assert_eq!(
+            character
+                .recover(&roll(character.hit_dice_set(), &[3]))
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            character
+                .recover(&roll(character.hit_dice_set(), &[10]))
+                .unwrap(),
+            7
+        );
+        assert_eq!(character.hit_points(), 12);
+        assert_eq!(character.recovery_dice(), 0);
+        assert!(matches!(
+            character.recover(&roll(character.hit_dice_set(), &[10])),
+            Err(Error::NoRecoveryDieLeft)
+        ));
+
+        character.rest();
+        assert_eq!(character.recovery_dice(), MAX_RECOVERY_DICE);
+        assert_eq!(character.heal(5), 0);
+    }
+
+    #[test]
+    fn can_spend_luck_points() {
+        let mut character = nain([16, 13, 14, 8, 10, 10]);
+        assert!(character.spend_luck_point().is_ok());
+        assert!(character.spend_luck_point().is_ok());
+        assert!(matches!(
+            character.spend_luck_point(),
+            Err(Error::NoLuckPointLeft)
+        ));
+        assert_eq!(character.luck_points(), 0);
+    }
+
+    #[test]
+    fn can_gain_capacities() {
+        let mut character = nain([16, 13, 14, 8, 10, 10]);
+        assert_eq!(character.capacity_points(), STARTING_CAPACITY_POINTS);
+
+        let capacity = character.gain_capacity("Voie du combat", 1).unwrap();
+        assert_eq!(capacity.name(), "Voie du combat 1");
+        character.gain_capacity("voie du bouclier", 1).unwrap();
+        assert_eq!(character.capacity_points(), 0);
+        assert_eq!(character.rank("Voie du combat"), 1);
+        assert_eq!(character.rank("Voie du bouclier"), 1);
+        assert!(matches!(
+            character.gain_capacity("Voie du combat", 2),
+            Err(Error::NotEnoughCapacityPoints(1))
+        ));
+
+        character
+            .level_up(&roll(character.hit_dice_set(), &[5]))
+            .unwrap();
+        assert_eq!(character.capacity_points(), CAPACITY_POINTS_PER_LEVEL);
+        assert!(matches!(
+            character.gain_capacity("Voie du combat", 3),
+            Err(Error::MissingPrerequisite { rank: 2, .. })
+        ));
+        character.gain_capacity("Voie du combat", 2).unwrap();
+        assert!(matches!(
+            character.gain_capacity("Voie du combat", 3),
+            Err(Error::RankLocked { rank: 3, level: 3 })
+        ));
+
+        character
+            .level_up(&roll(character.hit_dice_set(), &[5]))
+            .unwrap();
+        assert_eq!(character.capacity_points(), 3);
+        assert_eq!(
+            character.gain_capacity("Voie du combat", 3).unwrap().name(),
+            "Voie du combat 3"
+        );
+        assert_eq!(character.capacity_points(), 1);
+        assert!(matches!(
+            character.gain_capacity("Voie du combat", 4),
+            Err(Error::RankLocked { rank: 4, level: 5 })
+        ));
+
+        assert!(matches!(
+            character.gain_capacity("Voie du combat", 2),
+            Err(Error::CapacityAlreadyGained(_))
+        ));
+        assert!(matches!(
+            character.gain_capacity("Voie du combat", 6),
+            Err(Error::InvalidRank(6))
+        ));
+        assert!(matches!(
+            character.gain_capacity("Voie de la magie destructrice", 1),
+            Err(Error::VoieUnknown(_))
+        ));
+
+        let capacities = character
+            .capacities()
+            .map(Capacity::name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            capacities,
+            [
+                "Voie du bouclier 1",
+                "Voie du combat 1",
+                "Voie du combat 2",
+                "Voie du combat 3"
+            ]
+        );
+    }
+}
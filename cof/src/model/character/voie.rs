@@ -0,0 +1,167 @@
+use super::{Error, check_name};
+
+/// The number of capacities of a voie, ranked from 1 to 5.
+pub const VOIE_LEN: usize = 5;
+
+/// Returns the lowest level a character must have reached to gain a capacity of the given
+/// rank: ranks 1 and 2 are open from level 1, rank 3 from level 3, rank 4 from level 5 and
+/// rank 5 from level 7.
+#[must_use]
+pub fn min_level(rank: u32) -> u32 {
+    (2 * rank).saturating_sub(3).max(1)
+}
+
+/// Returns the *points de capacité* spent to gain a capacity of the given rank: 1 for ranks 1
+/// and 2, 2 from rank 3.
+#[must_use]
+pub fn capacity_cost(rank: u32) -> u32 {
+    if rank <= 2 { 1 } else { 2 }
+}
+
+/// A `Capacity` is what a character is able to do once it has reached its rank in a
+/// [`Voie`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capacity {
+    name: String,
+    description: Option<String>,
+}
+
+impl Capacity {
+    /// Creates the capacity of the given name.
+    ///
+    /// # Errors
+    /// [`Error::InvalidVoie`] is returned when the name is blank or longer than
+    /// [`super::MAX_NAME_LEN`].
+    pub fn new(name: impl Into<String>) -> Result<Self, Error> {
+        let name = name.into();
+        check_name(&name).map_err(Error::InvalidVoie)?;
+        Ok(Self {
+            name,
+            description: None,
+        })
+    }
+
+    /// Describes what the capacity does.
+    #[must_use]
+    pub fn with_description(self, description: impl Into<String>) -> Self {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    /// `name` returns the name of the capacity.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `description` returns what the capacity does, when it has been described.
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+/// A `Voie` is a path of [`VOIE_LEN`] capacities of a profile (e.g. the *Voie du bouclier*
+/// of the *Guerrier*), each capacity requiring the one of the previous rank.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Voie {
+    name: String,
+    capacities: Vec<Capacity>,
+}
+
+impl Voie {
+    /// Creates the voie of the given name out of its capacities, from rank 1 to rank 5.
+    ///
+    /// # Errors
+    /// [`Error::InvalidVoie`] is returned when the name is blank or longer than
+    /// [`super::MAX_NAME_LEN`], or when the voie does not have exactly [`VOIE_LEN`]
+    /// capacities.
+    pub fn new(name: impl Into<String>, capacities: Vec<Capacity>) -> Result<Self, Error> {
+        let name = name.into();
+        check_name(&name).map_err(Error::InvalidVoie)?;
+        if capacities.len() != VOIE_LEN {
+            return Err(Error::InvalidVoie(format!(
+                "the {name} must have {VOIE_LEN} capacities, not {}",
+                capacities.len()
+            )));
+        }
+        Ok(Self { name, capacities })
+    }
+
+    /// `name` returns the name of the voie.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `capacity` returns the capacity of the given rank, from 1 to [`VOIE_LEN`].
+    #[must_use]
+    pub fn capacity(&self, rank: u32) -> Option<&Capacity> {
+        let index = usize::try_from(rank).ok()?.checked_sub(1)?;
+        self.capacities.get(index)
+    }
+
+    /// `capacities` returns the capacities of the voie, from rank 1 to rank 5.
+    pub fn capacities(&self) -> impl Iterator<Item = &Capacity> {
+        self.capacities.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capacities(names: &[&str]) -> Vec<Capacity> {
+        names.iter().map(|n| Capacity::new(*n).unwrap()).collect()
+    }
+
+    #[test]
+    fn can_compute_rank_requirements() {
+        let test_cases = &[(1, 1, 1), (2, 1, 1), (3, 3, 2), (4, 5, 2), (5, 7, 2)];
+        for (rank, level, cost) in test_cases {
+            assert_eq!(min_level(*rank), *level, "rank {rank}");
+            assert_eq!(capacity_cost(*rank), *cost, "rank {rank}");
+        }
+    }
+
+    #[test]
+    fn can_build_voies() {
+        let voie = Voie::new(
+            "Voie du combat",
+            capacities(&[
+                "Vivacité",
+                "Désarmer",
+                "Double attaque",
+                "Attaque circulaire",
+                "Attaque parfaite",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(voie.name(), "Voie du combat");
+        assert_eq!(voie.capacity(1).unwrap().name(), "Vivacité");
+        assert_eq!(voie.capacity(5).unwrap().name(), "Attaque parfaite");
+        assert!(voie.capacity(0).is_none());
+        assert!(voie.capacity(6).is_none());
+        assert_eq!(voie.capacities().count(), VOIE_LEN);
+
+        let capacity = Capacity::new("Vivacité")
+            .unwrap()
+            .with_description("+3 en Initiative");
+        assert_eq!(capacity.description(), Some("+3 en Initiative"));
+    }
+
+    #[test]
+    fn cannot_build_invalid_voies() {
+        assert!(matches!(Capacity::new(" "), Err(Error::InvalidVoie(_))));
+        assert!(matches!(
+            Voie::new("", capacities(&["a", "b", "c", "d", "e"])),
+            Err(Error::InvalidVoie(_))
+        ));
+        assert!(matches!(
+            Voie::new("Voie du combat", capacities(&["a", "b", "c", "d"])),
+            Err(Error::InvalidVoie(_))
+        ));
+    }
+}
@@ -1,11 +1,36 @@
 //! This module represents the traditional dices encountered in classical Table Top Role
 //! Playing Games: there are multiples types of [`Dice`]s: *d3*, *d4*, *d6*, *d8*, *d10*,
-//! *d12*, *d20* and *d100*.
+//! *d12*, *d20* and *d100*, along with dices of any number of sides (e.g. *d7*) and special
+//! dices such as the Fudge dice (*dF*) or the tens dice of percentile rolls (*d00*).
 //!
 //! These dices can grouped together in a [`DiceSet`] and, above everything else, they can
 //! be rolled. Both [`Dice`] and [`DiceSet`] implement the `roll()` method that generates a
 //! random value between 1 and the number of faces of the dice.
 //!
+//! A [`DiceSet`] is parsed from the usual dice notation (e.g. `1d20 + 3` or `(d8 + 2) * 2`)
+//! into an [`Expression`] whose leaves are [`DiceTerm`]s, so that flat modifiers and
+//! arithmetic are kept along with the dices. A [`DiceTerm`] may only keep a [`Selection`] of
+//! its rolled dices (e.g. `2d20kh1` or `4d6dl1`), the other ones being discarded. Its dices
+//! may also be rerolled ([`Reroll`], e.g. `d8r1`) or explode ([`Explosion`], e.g. `d6!`), each
+//! [`RolledDice`] recording its [`RollOrigin`] so that the history of the roll is kept.
+//!
+//! Rolls are made through a [`Roller`], the source of randomness, so that they can be
+//! seeded, scripted or made cryptographically secure, the [`RollSource`] allowing to audit
+//! them afterwards.
+//!
+//! A roll can also be made provably fair: the server commits to a seed before the roll, mixes
+//! it with a seed of the client and reveals it afterwards in a [`FairnessProof`], so that
+//! anyone can check the roll with [`verify_fair_roll`].
+//!
+//! The exact [`Distribution`] of the outcomes of a [`DiceSet`] can be computed beforehand to
+//! know the chances of a roll to reach a given target.
+//!
+//! An [`AbilityTest`] resolves the tests of *Chroniques Oubliées Fantasy*: a d20 roll plus a
+//! characteristic modifier against a difficulty, with critical successes and fumbles.
+//!
+//! The [`FaceCounts`] of a dice gathers its past rolls to check with a chi-squared test that
+//! its faces come up evenly.
+//!
 //! Once dices are rolled they are instances of the [`RolledDice`] structure that provides
 //! acces to the original dice and the outcome of the stochastic experience of rolling a dice
 //! through the `result()` method.
@@ -16,6 +41,30 @@ pub use dice_type::*;
 mod dice_set;
 pub use dice_set::*;
 
+mod dice_term;
+pub use dice_term::*;
+
+mod expression;
+pub use expression::*;
+
+mod roller;
+pub use roller::*;
+
+mod fairness;
+pub use fairness::*;
+
+mod distribution;
+pub use distribution::{Distribution, MAX_DISTRIBUTION_SIZE};
+
+mod ability_test;
+pub use ability_test::*;
+
+mod face_counts;
+pub use face_counts::*;
+
+mod parser;
+pub use parser::ParseErrorKind;
+
 #[cfg(feature = "protobuf")]
 mod protobuf;
 
@@ -27,6 +76,7 @@ mod protobuf;
 pub mod pb {
     pub mod common {
         pub mod dice {
+            #[allow(clippy::pedantic)]
             pub mod v1 {
                 tonic::include_proto!("cof.common.dice.v1");
             }
@@ -44,13 +94,53 @@ pub enum Error {
     #[error("How would a normal human put this amount of dices in a real table top game ?!?")]
     WayTooManyDices,
 
-    #[error("Cannot parse the diceset")]
-    DiceSetParseError,
+    #[error("Cannot parse the diceset at position {position}: {kind}")]
+    DiceSetParseError {
+        position: usize,
+        kind: ParseErrorKind,
+    },
+
+    #[error("Cannot keep or drop that many dices in {0}")]
+    InvalidSelection(String),
+
+    #[error("Roll origin {0} does not exist")]
+    RollOriginUnknown(String),
+
+    #[error("Cannot reroll or explode the dices of {0}")]
+    InvalidModifier(String),
+
+    #[error("Roll source {0} cannot be understood")]
+    RollSourceUnknown(String),
+
+    #[error("The client seed cannot be longer than {MAX_CLIENT_SEED_LEN} bytes")]
+    ClientSeedTooLong,
+
+    #[error("The revealed server seed does not match its commitment")]
+    CommitmentMismatch,
+
+    #[error("Cannot compute the exact distribution of {0}")]
+    NoExactDistribution(String),
+
+    #[error("The rolled dices do not match the diceset")]
+    RolledDicesMismatch,
+
+    #[error("{0} must keep exactly one non exploding d20 to be an ability test")]
+    InvalidAbilityTest(String),
+
+    #[error("The critical range cannot start at {0}")]
+    InvalidCriticalThreshold(u32),
+
+    #[error("The {0} has no face {1}")]
+    FaceUnknown(Dice, u32),
 
     #[cfg(feature = "protobuf")]
     #[error("Received an unspecifed Protobuf value")]
     UnspecifiedProtoEnum,
 
+    #[cfg(feature = "protobuf")]
+    #[error("Dice {0} is not a common Protobuf DiceType")]
+    NotAProtoDiceType(String),
+
     #[cfg(feature = "protobuf")]
     #[error(transparent)]
     ProstUnknownEnumValue(#[from] prost::UnknownEnumValue),
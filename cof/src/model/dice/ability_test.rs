@@ -0,0 +1,325 @@
+use super::{
+    Dice, DiceSet, DiceTerm, Error, Expression, RolledDiceSet, RolledDiceTerm, Roller, ThreadRoller,
+};
+
+/// The natural result of the d20 from which a test is a critical success by default.
+pub const DEFAULT_CRITICAL_THRESHOLD: u32 = 20;
+
+/// An `AbilityTest` is a test of *Chroniques Oubliées Fantasy*: a d20 expression (e.g. `d20`,
+/// or `2d20kh1` with a *dé bonus*) to which the modifier of a characteristic is added, the
+/// test being a success when the total reaches its difficulty.
+///
+/// The natural result of the d20, before any modifier, decides of critical successes and
+/// fumbles: a natural 1 always fails while a natural result from the critical threshold (20
+/// unless a capacity widens it, e.g. to 19-20) always succeeds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbilityTest {
+    dice_set: DiceSet,
+    modifier: i64,
+    difficulty: i64,
+    critical_threshold: u32,
+}
+
+impl AbilityTest {
+    /// Creates the test of rolling `dice_set` plus `modifier` against `difficulty`, which is
+    /// either the difficulty of the test or the DEF of the opponent.
+    ///
+    /// # Errors
+    /// [`Error::InvalidAbilityTest`] is returned when the dice set does not keep exactly one
+    /// non exploding d20 to read the natural result from, or when that d20 is not added to
+    /// the total (e.g. `-d20 + 25` or `2 * d20`).
+    pub fn new(dice_set: DiceSet, modifier: i64, difficulty: i64) -> Result<Self, Error> {
+        let d20_terms = dice_set
+            .expression()
+            .leaves()
+            .filter(|term| term.dice == Dice::D20)
+            .collect::<Vec<&DiceTerm>>();
+        match d20_terms[..] {
+            [term]
+                if term.kept_count() == 1
+                    && term.explosion.is_none()
+                    && adds_d20(dice_set.expression()) =>
+            {
+                Ok(Self {
+                    dice_set,
+                    modifier,
+                    difficulty,
+                    critical_threshold: DEFAULT_CRITICAL_THRESHOLD,
+                })
+            }
+            _ => Err(Error::InvalidAbilityTest(dice_set.to_string())),
+        }
+    }
+
+    /// Widens the critical range of the test, a natural result greater or equal to
+    /// `threshold` being a critical success.
+    ///
+    /// # Errors
+    /// [`Error::InvalidCriticalThreshold`] is returned when the threshold is not between 2
+    /// and 20, a natural 1 being always a fumble.
+    pub fn with_critical_threshold(self, threshold: u32) -> Result<Self, Error> {
+        if !(2..=DEFAULT_CRITICAL_THRESHOLD).contains(&threshold) {
+            return Err(Error::InvalidCriticalThreshold(threshold));
+        }
+        Ok(Self {
+            critical_threshold: threshold,
+            ..self
+        })
+    }
+
+    /// `dice_set` returns the d20 expression rolled for the test.
+    #[must_use]
+    pub fn dice_set(&self) -> &DiceSet {
+        &self.dice_set
+    }
+
+    /// `modifier` returns the characteristic modifier added to the roll.
+    #[must_use]
+    pub fn modifier(&self) -> i64 {
+        self.modifier
+    }
+
+    /// `difficulty` returns the total to reach for the test to succeed.
+    #[must_use]
+    pub fn difficulty(&self) -> i64 {
+        self.difficulty
+    }
+
+    /// `critical_threshold` returns the lowest natural result that is a critical success.
+    #[must_use]
+    pub fn critical_threshold(&self) -> u32 {
+        self.critical_threshold
+    }
+
+    /// Rolls the dices of the test and resolves it.
+    ///
+    /// # Errors
+    /// [`Error::WayTooManyDices`] is returned when the dice set cannot be rolled.
+    pub fn roll(&self) -> Result<(RolledDiceSet, TestOutcome), Error> {
+        self.roll_with(&mut ThreadRoller)
+    }
+
+    /// Rolls the dices of the test with the given [`Roller`] and resolves it.
+    ///
+    /// # Errors
+    /// [`Error::WayTooManyDices`] is returned when the dice set cannot be rolled.
+    pub fn roll_with(
+        &self,
+        roller: &mut (impl Roller + ?Sized),
+    ) -> Result<(RolledDiceSet, TestOutcome), Error> {
+        let rolled_dice_set = self.dice_set.clone().roll_with(roller)?;
+        let outcome = self.outcome(&rolled_dice_set)?;
+        Ok((rolled_dice_set, outcome))
+    }
+
+    /// Resolves the test out of the dices rolled for it.
+    ///
+    /// # Errors
+    /// [`Error::RolledDicesMismatch`] is returned when the dices have not been rolled from
+    /// the dice set of the test.
+    pub fn outcome(&self, rolled_dice_set: &RolledDiceSet) -> Result<TestOutcome, Error> {
+        if rolled_dice_set.dice_set() != self.dice_set {
+            return Err(Error::RolledDicesMismatch);
+        }
+        let natural = rolled_dice_set
+            .expression()
+            .leaves()
+            .filter(|term| term.term.dice == Dice::D20)
+            .flat_map(RolledDiceTerm::iter)
+            .find(|rd| !rd.is_discarded())
+            .ok_or(Error::RolledDicesMismatch)?
+            .result();
+
+        let total = rolled_dice_set.total().saturating_add(self.modifier);
+        let margin = total.saturating_sub(self.difficulty);
+        let critical = natural >= self.critical_threshold;
+        let fumble = natural == 1;
+
+        Ok(TestOutcome {
+            natural,
+            total,
+            margin,
+            success: !fumble && (critical || margin >= 0),
+            critical,
+            fumble,
+        })
+    }
+}
+
+/// Tells whether a d20 is added as is to the total of the expression, rather than being
+/// negated, subtracted or multiplied, so that a higher natural result is a better one.
+fn adds_d20(expression: &Expression<DiceTerm>) -> bool {
+    match expression {
+        Expression::Constant(_) | Expression::Neg(_) | Expression::Mul(_, _) => false,
+        Expression::Dices(term) => term.dice == Dice::D20,
+        Expression::Add(l, r) => adds_d20(l) || adds_d20(r),
+        Expression::Sub(l, _) => adds_d20(l),
+    }
+}
+
+/// The `TestOutcome` is the resolution of an [`AbilityTest`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TestOutcome {
+    natural: u32,
+    total: i64,
+    margin: i64,
+    success: bool,
+    critical: bool,
+    fumble: bool,
+}
+
+impl TestOutcome {
+    /// `natural` returns the result of the kept d20, before any modifier.
+    #[must_use]
+    pub fn natural(&self) -> u32 {
+        self.natural
+    }
+
+    /// `total` returns the total of the roll, characteristic modifier included.
+    #[must_use]
+    pub fn total(&self) -> i64 {
+        self.total
+    }
+
+    /// `margin` returns by how much the total exceeds the difficulty, being negative when it
+    /// does not reach it.
+    #[must_use]
+    pub fn margin(&self) -> i64 {
+        self.margin
+    }
+
+    /// `is_success` tells whether the test succeeds, criticals always succeeding and fumbles
+    /// always failing whatever the margin.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.success
+    }
+
+    /// `is_critical` tells whether the natural result is within the critical range.
+    #[must_use]
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    /// `is_fumble` tells whether the natural result is a 1.
+    #[must_use]
+    pub fn is_fumble(&self) -> bool {
+        self.fumble
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::model::dice::ScriptedRoller;
+
+    fn resolve(test: &AbilityTest, results: &[u32]) -> TestOutcome {
+        test.roll_with(&mut ScriptedRoller::new(results.iter().copied()))
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn can_resolve_ability_tests() {
+        let test = AbilityTest::new(DiceSet::from_str("d20").unwrap(), 3, 15).unwrap();
+
+        let test_cases = &[
+            (12, 15, 0, true, false, false),
+            (11, 14, -1, false, false, false),
+            (20, 23, 8, true, true, false),
+            (1, 4, -11, false, false, true),
+        ];
+        for tc in test_cases {
+            let outcome = resolve(&test, &[tc.0]);
+            assert_eq!(outcome.natural(), tc.0);
+            assert_eq!(outcome.total(), tc.1);
+            assert_eq!(outcome.margin(), tc.2);
+            assert_eq!(outcome.is_success(), tc.3, "natural {}", tc.0);
+            assert_eq!(outcome.is_critical(), tc.4);
+            assert_eq!(outcome.is_fumble(), tc.5);
+        }
+    }
+
+    #[test]
+    fn can_force_criticals_and_fumbles() {
+        let hard = AbilityTest::new(DiceSet::from_str("d20").unwrap(), 0, 30).unwrap();
+        assert!(resolve(&hard, &[20]).is_success());
+        assert!(!resolve(&hard, &[19]).is_success());
+
+        let widened = hard.with_critical_threshold(19).unwrap();
+        assert!(resolve(&widened, &[19]).is_critical());
+        assert!(resolve(&widened, &[19]).is_success());
+        assert!(!resolve(&widened, &[18]).is_critical());
+
+        let easy = AbilityTest::new(DiceSet::from_str("d20 + 2").unwrap(), 10, 5).unwrap();
+        let outcome = resolve(&easy, &[1]);
+        assert_eq!(outcome.total(), 13);
+        assert!(outcome.is_fumble());
+        assert!(!outcome.is_success());
+    }
+
+    #[test]
+    fn can_resolve_tests_with_bonus_dices() {
+        let bonus = AbilityTest::new(DiceSet::from_str("2d20kh1").unwrap(), 2, 10).unwrap();
+        let outcome = resolve(&bonus, &[1, 20]);
+        assert_eq!(outcome.natural(), 20);
+        assert!(outcome.is_critical());
+        assert!(!outcome.is_fumble());
+
+        let malus = AbilityTest::new(DiceSet::from_str("2d20kl1").unwrap(), 2, 10).unwrap();
+        let outcome = resolve(&malus, &[1, 20]);
+        assert_eq!(outcome.natural(), 1);
+        assert!(outcome.is_fumble());
+    }
+
+    #[test]
+    fn cannot_build_invalid_ability_tests() {
+        for notation in ["d6", "2d20", "d20 + d20", "d20!", "3d20kh2"] {
+            assert!(
+                matches!(
+                    AbilityTest::new(DiceSet::from_str(notation).unwrap(), 0, 10),
+                    Err(Error::InvalidAbilityTest(_))
+                ),
+                "{notation}"
+            );
+        }
+
+        let test = AbilityTest::new(DiceSet::from_str("d20 + d4").unwrap(), 0, 10).unwrap();
+        assert!(test.clone().with_critical_threshold(1).is_err());
+        assert!(test.clone().with_critical_threshold(21).is_err());
+
+        let other_roll = DiceSet::from_str("d20").unwrap().roll().unwrap();
+        assert!(matches!(
+            test.outcome(&other_roll),
+            Err(Error::RolledDicesMismatch)
+        ));
+    }
+
+    #[test]
+    fn cannot_build_ability_tests_without_adding_the_d20() {
+        for notation in [
+            "-d20 + 25",
+            "25 - d20",
+            "2 * d20",
+            "(d20 + 2) * 2",
+            "d4 - (d20 + 2)",
+        ] {
+            assert!(
+                matches!(
+                    AbilityTest::new(DiceSet::from_str(notation).unwrap(), 0, 10),
+                    Err(Error::InvalidAbilityTest(_))
+                ),
+                "{notation}"
+            );
+        }
+
+        for notation in ["d4 + d20", "(d20 + 2) - d4", "2d20kl1 - 1 + d6"] {
+            assert!(
+                AbilityTest::new(DiceSet::from_str(notation).unwrap(), 0, 10).is_ok(),
+                "{notation}"
+            );
+        }
+    }
+}
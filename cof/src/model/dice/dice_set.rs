@@ -1,53 +1,149 @@
-use regex::Regex;
 use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
-use super::{Dice, Error, RolledDice};
+use super::{
+    Dice, DiceTerm, Distribution, Error, Expression, RolledDice, RolledDiceTerm, Roller,
+    ThreadRoller, distribution, parser,
+};
 
-/// A `DiceSet` represents multiple dices to roll.
+/// The maximum number of dices that can be rolled at once in a [`DiceSet`].
+pub const MAX_DICES: u64 = 1000;
+
+/// The maximum number of rolls a [`DiceSet`] can take when every reroll and every explosion
+/// happens.
+pub const MAX_ROLLS: u64 = 10 * MAX_DICES;
+
+/// A `DiceSet` represents multiple dices to roll, combined with flat modifiers and
+/// arithmetic operations as described by its [`Expression`].
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct DiceSet(pub(super) Vec<Dice>);
+pub struct DiceSet(pub(super) Expression<DiceTerm>);
 
 impl DiceSet {
-    /// Creates a new `DiceSet` from multiple dices.
+    /// Creates a new `DiceSet` that sums multiple dices. Identical dices are grouped together
+    /// from the highest to the lowest one (e.g. `d100 + 2d20`).
     pub fn new(dices: impl Iterator<Item = Dice>) -> Self {
-        Self(dices.collect::<Vec<Dice>>())
+        let mut dice_counts = BTreeMap::new();
+        for d in dices {
+            dice_counts
+                .entry(d)
+                .and_modify(|e: &mut u32| *e = e.saturating_add(1))
+                .or_insert(1u32);
+        }
+        Self(Expression::sum(dice_counts.into_iter().rev().map(
+            |(dice, count)| Expression::Dices(DiceTerm::new(count, dice)),
+        )))
     }
 
-    /// Returns the lowest possible outcome for this [`DiceSet`] (i.e. all dice roll 1)
-    /// (i.e. the number of the [`Dice`] in the [`DiceSet`]).
+    /// `expression` returns the [`Expression`] describing this `DiceSet`.
+    #[must_use]
+    pub fn expression(&self) -> &Expression<DiceTerm> {
+        &self.0
+    }
+
+    /// Returns the lowest possible outcome for this [`DiceSet`], taking the flat modifiers
+    /// and arithmetic operations into account.
     ///
     /// # Errors
-    /// [`Error::WayTooManyDices`] is returned when the result cannot be casted in [`u32`].
-    pub fn lower_bound(&self) -> Result<u32, Error> {
-        u32::try_from(self.0.len()).map_err(|_| Error::WayTooManyDices)
+    /// [`Error::WayTooManyDices`] is returned when the result cannot be casted in [`i64`].
+    pub fn lower_bound(&self) -> Result<i64, Error> {
+        self.bounds().map(|(low, _)| low)
     }
 
-    /// Returns the highest possible outcome for this [`DiceSet`] (i.e. all dice roll their
-    /// maximum value) (i.e. the number of the [`Dice`] in the [`DiceSet`]).
+    /// Returns the highest possible outcome for this [`DiceSet`], taking the flat modifiers
+    /// and arithmetic operations into account.
     ///
     /// # Errors
-    /// [`Error::WayTooManyDices`] is returned when the result cannot be casted in [`u32`].
-    pub fn upper_bound(&self) -> Result<u32, Error> {
+    /// [`Error::WayTooManyDices`] is returned when the result cannot be casted in [`i64`].
+    pub fn upper_bound(&self) -> Result<i64, Error> {
+        self.bounds().map(|(_, high)| high)
+    }
+
+    fn bounds(&self) -> Result<(i64, i64), Error> {
         self.0
-            .iter()
-            .try_fold(0u32, |acc, dice| acc.checked_add(*dice as u32))
+            .bounds(&|t: &DiceTerm| t.bounds())
             .ok_or(Error::WayTooManyDices)
     }
 
+    /// `dice_count` returns the total number of dices to roll.
+    #[must_use]
+    pub fn dice_count(&self) -> u64 {
+        self.0.leaves().map(|t| u64::from(t.count)).sum()
+    }
+
+    /// `max_roll_count` returns the number of rolls in the worst case, when every reroll and
+    /// every explosion happens.
+    #[must_use]
+    pub fn max_roll_count(&self) -> u64 {
+        self.0.leaves().map(DiceTerm::max_roll_count).sum()
+    }
+
+    /// Checks that the `DiceSet` can be rolled, without rolling it.
+    ///
+    /// # Errors
+    /// [`Error::WayTooManyDices`] is returned when there are more than [`MAX_DICES`] dices
+    /// to roll, when rerolls and explosions may take more than [`MAX_ROLLS`] rolls or when
+    /// the result cannot be casted in [`i64`].
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.dice_count() > MAX_DICES
+            || self.max_roll_count() > MAX_ROLLS
+            || self.bounds().is_err()
+        {
+            return Err(Error::WayTooManyDices);
+        }
+        Ok(())
+    }
+
     /// Rolls all the dices in the `DiceSet` and returns a [`RolledDiceSet`].
     ///
+    /// Rerolls and explosions add at most [`super::MAX_CHAIN_LENGTH`] rolls each to every
+    /// dice, so that the number of rolls stays bounded.
+    ///
     /// # Errors
-    /// [`Error::WayTooManyDices`] is returned when the result cannot be casted in [`u32`].
+    /// [`Error::WayTooManyDices`] is returned when there are more than [`MAX_DICES`] dices
+    /// to roll, when rerolls and explosions may take more than [`MAX_ROLLS`] rolls or when
+    /// the result cannot be casted in [`i64`].
     pub fn roll(self) -> Result<RolledDiceSet, Error> {
-        if self.upper_bound().is_err() {
+        self.roll_with(&mut ThreadRoller)
+    }
+
+    /// Rolls all the dices in the `DiceSet` the same way [`DiceSet::roll`] does, the results
+    /// being given by the provided [`Roller`].
+    ///
+    /// # Errors
+    /// [`Error::WayTooManyDices`] is returned when there are more than [`MAX_DICES`] dices
+    /// to roll, when rerolls and explosions may take more than [`MAX_ROLLS`] rolls or when
+    /// the result cannot be casted in [`i64`].
+    pub fn roll_with(self, roller: &mut (impl Roller + ?Sized)) -> Result<RolledDiceSet, Error> {
+        self.validate()?;
+        Ok(RolledDiceSet(
+            self.0.map(&mut |term: &DiceTerm| term.roll_with(roller)),
+        ))
+    }
+
+    /// Computes the exact [`Distribution`] of the outcomes of rolling the `DiceSet`.
+    ///
+    /// # Errors
+    /// - [`Error::WayTooManyDices`] is returned when there are more than [`MAX_DICES`] dices
+    ///   or when the distribution is too large to be computed.
+    /// - [`Error::NoExactDistribution`] is returned when exploding dices are kept or dropped
+    ///   (e.g. `2d6!kh1`).
+    pub fn distribution(&self) -> Result<Distribution, Error> {
+        if self.dice_count() > MAX_DICES {
             return Err(Error::WayTooManyDices);
         }
-        Ok(RolledDiceSet(self.0.into_iter().map(Dice::roll).collect()))
+        distribution::of_expression(&self.0)
     }
 
     /// `iter()` returns an iterator of all the `Dice`s in the `DiceSet`.
     pub fn iter(&self) -> impl Iterator<Item = &Dice> {
-        self.0.iter()
+        self.0
+            .leaves()
+            .flat_map(|t| std::iter::repeat_n(&t.dice, t.count as usize))
+    }
+}
+
+impl From<Expression<DiceTerm>> for DiceSet {
+    fn from(value: Expression<DiceTerm>) -> Self {
+        Self(value)
     }
 }
 
@@ -55,84 +151,98 @@ impl FromStr for DiceSet {
     type Err = super::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"(?<number>[0-9]+)?(?<dice>d[0-9]+)").unwrap();
-        let dices: Vec<(u32, Dice)> = re
-            .captures_iter(s)
-            .map(|caps| {
-                let number = caps
-                    .name("number")
-                    .map_or(Ok(1u32), |m| m.as_str().parse::<u32>())
-                    .map_err(|_| super::Error::DiceSetParseError)?;
-
-                let dice = caps.name("dice").unwrap().as_str().try_into()?;
-
-                Ok((number, dice))
-            })
-            .collect::<Result<Vec<_>, Self::Err>>()?;
-
-        if dices.is_empty() {
-            return Err(super::Error::DiceSetParseError);
-        }
-
-        let dice_iter = dices
-            .into_iter()
-            .flat_map(|(number, dice)| (0..number).map(move |_| dice));
-
-        Ok(DiceSet::new(dice_iter))
+        parser::parse(s).map(Self)
     }
 }
 
 impl Display for DiceSet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut dice_counts = BTreeMap::new();
-        for d in &self.0 {
-            dice_counts
-                .entry(*d)
-                .and_modify(|e| *e += 1)
-                .or_insert(1u32);
-        }
-        let str_content = dice_counts
-            .iter()
-            .rev()
-            .map(|(dice, count)| {
-                if *count == 1 {
-                    format!("{dice}")
-                } else {
-                    format!("{count}{dice}")
-                }
-            })
-            .collect::<Vec<String>>()
-            .join(" + ");
-        write!(f, "{str_content}")
+        write!(f, "{}", self.0)
     }
 }
 
 /// A `RolledDiceSet` represents the outcome of rolling all dices in a [`DiceSet`].
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct RolledDiceSet(pub(super) Vec<RolledDice>);
+pub struct RolledDiceSet(pub(super) Expression<RolledDiceTerm>);
 
 impl RolledDiceSet {
-    /// Create a new `RolledDiceSet` out of the given `RolledDice`s.
+    /// Create a new `RolledDiceSet` summing the given `RolledDice`s. Identical dices are
+    /// grouped together the same way [`DiceSet::new`] does.
     pub fn new(rolled_dices: impl Iterator<Item = RolledDice>) -> Self {
-        Self(rolled_dices.collect::<Vec<RolledDice>>())
+        let mut grouped_dices: BTreeMap<Dice, Vec<RolledDice>> = BTreeMap::new();
+        for rd in rolled_dices {
+            grouped_dices.entry(rd.dice).or_default().push(rd);
+        }
+        Self(Expression::sum(grouped_dices.into_iter().rev().map(
+            |(dice, rolled_dices)| {
+                Expression::Dices(RolledDiceTerm {
+                    term: DiceTerm::new(
+                        u32::try_from(rolled_dices.len()).unwrap_or(u32::MAX),
+                        dice,
+                    ),
+                    rolled_dices,
+                })
+            },
+        )))
     }
 
-    /// `total` returns the sum of all the results of the rolls of each dices in the
-    /// `RolledDiceSet`.
+    /// Rebuilds the `RolledDiceSet` resulting of rolling `dice_set`, the `rolled_dices`
+    /// being given in the same order as [`RolledDiceSet::iter`] returns them.
+    ///
+    /// # Errors
+    /// [`Error::RolledDicesMismatch`] is returned when the rolled dices cannot be the outcome
+    /// of rolling the given [`DiceSet`].
+    pub fn try_from_parts(
+        dice_set: &DiceSet,
+        rolled_dices: impl IntoIterator<Item = RolledDice>,
+    ) -> Result<Self, Error> {
+        let rolled_dices = rolled_dices.into_iter().collect::<Vec<RolledDice>>();
+        let mut rolled_dices = rolled_dices.iter();
+        let expression = dice_set
+            .0
+            .try_map(&mut |term: &DiceTerm| term.replay(&mut rolled_dices))?;
+
+        if rolled_dices.next().is_some() {
+            return Err(Error::RolledDicesMismatch);
+        }
+        Ok(Self(expression))
+    }
+
+    /// `expression` returns the [`Expression`] of the rolled dice terms.
+    #[must_use]
+    pub fn expression(&self) -> &Expression<RolledDiceTerm> {
+        &self.0
+    }
+
+    /// `dice_set` returns the [`DiceSet`] that has been rolled.
     #[must_use]
-    pub fn total(&self) -> u32 {
-        self.0.iter().fold(0u32, |acc, e| acc + e.result)
+    pub fn dice_set(&self) -> DiceSet {
+        DiceSet(self.0.map(&mut RolledDiceTerm::term))
+    }
+
+    /// `total` returns the outcome of the roll: the results of the rolls of each dices in the
+    /// `RolledDiceSet` combined with the flat modifiers and arithmetic of the [`DiceSet`].
+    #[must_use]
+    pub fn total(&self) -> i64 {
+        self.0.evaluate(&RolledDiceTerm::total)
     }
 
     /// `iter` returns an iterator of all the `RolledDice` in the `RolledDiceSet`.
     pub fn iter(&self) -> impl Iterator<Item = &RolledDice> {
-        self.0.iter()
+        self.0.leaves().flat_map(RolledDiceTerm::iter)
+    }
+}
+
+impl Display for RolledDiceSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.0, self.total())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::dice::{ChaChaRoller, RollOrigin, ScriptedRoller};
 
     #[test]
     fn can_create_dice_set() {
@@ -142,13 +252,14 @@ mod tests {
                 .map(|e| Dice::try_from(e).unwrap()),
         );
 
-        assert_eq!(my_dice_set.0.len(), 6);
-        assert_eq!(my_dice_set.0.first(), Some(&Dice::D3));
-        assert_eq!(my_dice_set.0.get(1), Some(&Dice::D100));
-        assert_eq!(my_dice_set.0.get(2), Some(&Dice::D20));
-        assert_eq!(my_dice_set.0.get(3), Some(&Dice::D10));
-        assert_eq!(my_dice_set.0.get(4), Some(&Dice::D100));
-        assert_eq!(my_dice_set.0.get(5), Some(&Dice::D100));
+        let dices = my_dice_set.iter().collect::<Vec<_>>();
+        assert_eq!(dices.len(), 6);
+        assert_eq!(dices.first(), Some(&&Dice::D100));
+        assert_eq!(dices.get(1), Some(&&Dice::D100));
+        assert_eq!(dices.get(2), Some(&&Dice::D100));
+        assert_eq!(dices.get(3), Some(&&Dice::D20));
+        assert_eq!(dices.get(4), Some(&&Dice::D10));
+        assert_eq!(dices.get(5), Some(&&Dice::D3));
     }
 
     #[test]
@@ -187,7 +298,7 @@ mod tests {
                 .map(|e| Dice::try_from(e).unwrap()),
         );
         let result = my_dice_set.clone().roll().unwrap();
-        assert_eq!(result.0.len(), 6);
+        assert_eq!(result.iter().count(), 6);
         let total = result.total();
         assert!(
             total <= my_dice_set.upper_bound().unwrap()
@@ -196,7 +307,162 @@ mod tests {
 
         let my_empty_dice_set =
             DiceSet::new(vec![].into_iter().map(|e: &str| Dice::try_from(e).unwrap()));
-        assert_eq!(my_empty_dice_set.roll().unwrap().0.len(), 0);
+        assert_eq!(my_empty_dice_set.roll().unwrap().iter().count(), 0);
+    }
+
+    #[test]
+    fn can_roll_diceset_with_modifiers() {
+        let my_dice_set = DiceSet::from_str("(2d6 - 1) * 2 - d4").unwrap();
+        assert_eq!(my_dice_set.lower_bound().unwrap(), -2);
+        assert_eq!(my_dice_set.upper_bound().unwrap(), 21);
+
+        for _ in 0..100 {
+            let result = my_dice_set.clone().roll().unwrap();
+            let dices = result
+                .iter()
+                .map(|rd| i64::from(rd.result()))
+                .collect::<Vec<_>>();
+            assert_eq!(dices.len(), 3);
+            assert_eq!(result.total(), (dices[0] + dices[1] - 1) * 2 - dices[2]);
+            assert_eq!(result.dice_set(), my_dice_set);
+        }
+    }
+
+    #[test]
+    fn can_roll_diceset_keeping_and_dropping_dices() {
+        let my_dice_set = DiceSet::from_str("2d20kh1 + 4d6dl1").unwrap();
+        assert_eq!(my_dice_set.to_string(), "2d20kh1 + 4d6dl1");
+        assert_eq!(my_dice_set.lower_bound().unwrap(), 4);
+        assert_eq!(my_dice_set.upper_bound().unwrap(), 38);
+
+        for _ in 0..100 {
+            let result = my_dice_set.clone().roll().unwrap();
+            assert_eq!(result.iter().count(), 6);
+            assert_eq!(result.iter().filter(|rd| rd.is_discarded()).count(), 2);
+
+            let kept_total = result
+                .iter()
+                .filter(|rd| !rd.is_discarded())
+                .map(|rd| i64::from(rd.result()))
+                .sum::<i64>();
+            assert_eq!(result.total(), kept_total);
+
+            let rebuilt = RolledDiceSet::try_from_parts(&my_dice_set, result.iter().copied());
+            assert_eq!(rebuilt.unwrap(), result);
+        }
+    }
+
+    #[test]
+    fn can_roll_diceset_with_rerolls_and_explosions() {
+        let my_dice_set = DiceSet::from_str("d6! + 2d8r1 + 3d10!!kh2").unwrap();
+        assert_eq!(my_dice_set.to_string(), "d6! + 2d8r1 + 3d10!!kh2");
+        assert_eq!(my_dice_set.lower_bound().unwrap(), 5);
+        assert_eq!(my_dice_set.upper_bound().unwrap(), 66 + 16 + 220);
+
+        for _ in 0..100 {
+            let result = my_dice_set.clone().roll().unwrap();
+            let total = result.total();
+            assert!(
+                total <= my_dice_set.upper_bound().unwrap()
+                    && total >= my_dice_set.lower_bound().unwrap()
+            );
+            assert!(result.iter().count() >= 6);
+
+            let rebuilt = RolledDiceSet::try_from_parts(&my_dice_set, result.iter().copied());
+            assert_eq!(rebuilt.unwrap(), result);
+        }
+
+        let my_dice_set = DiceSet::from_str("d6! + 1").unwrap();
+        let rolled_dices = [
+            RolledDice::new(Dice::D6, 6),
+            RolledDice::new(Dice::D6, 6).with_origin(RollOrigin::Explosion),
+            RolledDice::new(Dice::D6, 3).with_origin(RollOrigin::Explosion),
+        ];
+        let rolled_dice_set = RolledDiceSet::try_from_parts(&my_dice_set, rolled_dices).unwrap();
+        assert_eq!(rolled_dice_set.to_string(), "d6![6, !6, !3] + 1 = 16");
+        assert!(matches!(
+            RolledDiceSet::try_from_parts(&my_dice_set, rolled_dices[..2].iter().copied()),
+            Err(Error::RolledDicesMismatch)
+        ));
+    }
+
+    #[test]
+    fn can_roll_diceset_with_a_roller() {
+        let my_dice_set = DiceSet::from_str("2d20kh1 + d6! + 3").unwrap();
+
+        let result = my_dice_set
+            .clone()
+            .roll_with(&mut ScriptedRoller::new([4, 17, 6, 2]))
+            .unwrap();
+        assert_eq!(result.to_string(), "2d20kh1[~4, 17] + d6![6, !2] + 3 = 28");
+
+        let seeded = my_dice_set
+            .clone()
+            .roll_with(&mut ChaChaRoller::from_seed([7; 32]))
+            .unwrap();
+        let reseeded = my_dice_set
+            .roll_with(&mut ChaChaRoller::from_seed([7; 32]))
+            .unwrap();
+        assert_eq!(seeded, reseeded);
+    }
+
+    #[test]
+    fn cannot_roll_way_too_many_dices() {
+        let my_dice_set = DiceSet::from_str("1001d6").unwrap();
+        assert!(matches!(
+            my_dice_set.validate(),
+            Err(Error::WayTooManyDices)
+        ));
+        assert!(matches!(my_dice_set.roll(), Err(Error::WayTooManyDices)));
+        assert!(DiceSet::from_str("1000d6").unwrap().validate().is_ok());
+
+        // every dice may be rerolled and explode up to the length of a chain
+        let my_dice_set = DiceSet::from_str("500d6rr1!").unwrap();
+        assert_eq!(my_dice_set.max_roll_count(), 10_500);
+        assert!(matches!(
+            my_dice_set.validate(),
+            Err(Error::WayTooManyDices)
+        ));
+        assert!(DiceSet::from_str("900d6!").unwrap().validate().is_ok());
+
+        let my_dice_set = DiceSet::from_str("9223372036854775807 + d6").unwrap();
+        assert!(matches!(my_dice_set.roll(), Err(Error::WayTooManyDices)));
+    }
+
+    #[test]
+    fn can_rebuild_rolled_diceset_from_parts() {
+        let my_dice_set = DiceSet::from_str("d20 + 2d6 + 3").unwrap();
+        let rolled_dices = [
+            RolledDice::new(Dice::D20, 12),
+            RolledDice::new(Dice::D6, 2),
+            RolledDice::new(Dice::D6, 5),
+        ];
+
+        let rolled_dice_set = RolledDiceSet::try_from_parts(&my_dice_set, rolled_dices).unwrap();
+        assert_eq!(rolled_dice_set.total(), 22);
+        assert_eq!(rolled_dice_set.to_string(), "d20[12] + 2d6[2, 5] + 3 = 22");
+
+        let error_cases: &[&[RolledDice]] = &[
+            &rolled_dices[..2],
+            &[
+                rolled_dices[0],
+                rolled_dices[1],
+                rolled_dices[2],
+                rolled_dices[2],
+            ],
+            &[rolled_dices[1], rolled_dices[0], rolled_dices[2]],
+            &[
+                rolled_dices[0],
+                rolled_dices[1],
+                RolledDice::new(Dice::D6, 7),
+            ],
+        ];
+        for tc in error_cases {
+            assert!(matches!(
+                RolledDiceSet::try_from_parts(&my_dice_set, tc.iter().copied()),
+                Err(Error::RolledDicesMismatch)
+            ));
+        }
     }
 
     #[test]
@@ -212,6 +478,20 @@ mod tests {
                 "d100 + 2d20",
                 DiceSet::new(vec![Dice::D100, Dice::D20, Dice::D20].into_iter()),
             ),
+            (
+                "1d20 + 3",
+                DiceSet(Expression::Add(
+                    Box::new(Expression::Dices(DiceTerm::new(1, Dice::D20))),
+                    Box::new(Expression::Constant(3)),
+                )),
+            ),
+            (
+                "1d20 - 2",
+                DiceSet(Expression::Sub(
+                    Box::new(Expression::Dices(DiceTerm::new(1, Dice::D20))),
+                    Box::new(Expression::Constant(2)),
+                )),
+            ),
         ];
         for tc in valid_test_cases {
             let ds = DiceSet::from_str(tc.0);
@@ -220,7 +500,7 @@ mod tests {
             assert_eq!(ds, tc.1);
         }
 
-        let error_cases = &["2d7", "D100"];
+        let error_cases = &["2d1", "D100", "1d20 + ", "1d20 + x"];
         for tc in error_cases {
             let ds = DiceSet::from_str(tc);
             assert!(ds.is_err());
@@ -262,6 +542,9 @@ mod tests {
                 ),
                 "2d100 + 3d20 + d8",
             ),
+            (DiceSet::from_str("1d20+3").unwrap(), "d20 + 3"),
+            (DiceSet::from_str("1d20 -2").unwrap(), "d20 - 2"),
+            (DiceSet::from_str("(1d8 + 2)*2").unwrap(), "(d8 + 2) * 2"),
         ];
 
         for tc in valid_test_cases {
@@ -0,0 +1,851 @@
+use std::fmt::Display;
+
+use super::{Dice, Error, RollOrigin, RolledDice, Roller, ThreadRoller};
+
+/// The maximum number of additional rolls (explosions or rerolls) a single dice can trigger,
+/// it keeps the outcome of exploding dices bounded.
+pub const MAX_CHAIN_LENGTH: u32 = 10;
+
+/// A `Selection` tells which of the rolled dices of a [`DiceTerm`] are kept to compute its
+/// total, the other ones being discarded. This is how *dé bonus* (`2d20kh1`), *dé malus*
+/// (`2d20kl1`) or characteristics generation (`4d6dl1`) are described.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Only keeps the given number of highest dices (`kh`).
+    KeepHighest(u32),
+    /// Only keeps the given number of lowest dices (`kl`).
+    KeepLowest(u32),
+    /// Discards the given number of highest dices (`dh`).
+    DropHighest(u32),
+    /// Discards the given number of lowest dices (`dl`).
+    DropLowest(u32),
+}
+
+impl Selection {
+    /// Returns how many of the lowest and of the highest dices are discarded out of `count`
+    /// rolled dices.
+    fn discarded_counts(self, count: u32) -> (u32, u32) {
+        match self {
+            Selection::KeepHighest(n) => (count.saturating_sub(n), 0),
+            Selection::KeepLowest(n) => (0, count.saturating_sub(n)),
+            Selection::DropHighest(n) => (0, n.min(count)),
+            Selection::DropLowest(n) => (n.min(count), 0),
+        }
+    }
+
+    /// Returns the number of dices that are kept out of `count` rolled dices.
+    #[must_use]
+    pub fn kept_count(self, count: u32) -> u32 {
+        let (lowest, highest) = self.discarded_counts(count);
+        count - lowest - highest
+    }
+
+    /// Tells whether the selection makes sense for `count` rolled dices: at least one dice
+    /// must be kept and the selection cannot be larger than the rolled dices.
+    #[must_use]
+    pub fn is_valid_for(self, count: u32) -> bool {
+        match self {
+            Selection::KeepHighest(n) | Selection::KeepLowest(n) => n >= 1 && n <= count,
+            Selection::DropHighest(n) | Selection::DropLowest(n) => n >= 1 && n < count,
+        }
+    }
+
+    /// Marks the rolled dices that are discarded by the selection. Among dices with the same
+    /// result, the first rolled ones are the first discarded.
+    ///
+    /// Rerolled dices are ignored and the compound explosions of a dice are kept or discarded
+    /// along with it.
+    fn apply(self, rolled_dices: &mut [RolledDice]) {
+        let mut units: Vec<(i64, Vec<usize>)> = Vec::new();
+        for (i, rd) in rolled_dices.iter().enumerate() {
+            match units.last_mut() {
+                _ if rd.discarded => {}
+                Some((value, indices)) if rd.origin == RollOrigin::Compound => {
+                    *value += rd.value();
+                    indices.push(i);
+                }
+                _ => units.push((rd.value(), vec![i])),
+            }
+        }
+
+        let (lowest, highest) =
+            self.discarded_counts(u32::try_from(units.len()).unwrap_or(u32::MAX));
+
+        let mut ranks = (0..units.len()).collect::<Vec<usize>>();
+        ranks.sort_by_key(|&u| units[u].0);
+        let mut discarded = ranks
+            .iter()
+            .take(lowest as usize)
+            .copied()
+            .collect::<Vec<_>>();
+        ranks.sort_by_key(|&u| std::cmp::Reverse(units[u].0));
+        discarded.extend(ranks.iter().take(highest as usize));
+
+        for u in discarded {
+            for &i in &units[u].1 {
+                rolled_dices[i].discarded = true;
+            }
+        }
+    }
+}
+
+impl Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Selection::KeepHighest(n) => write!(f, "kh{n}"),
+            Selection::KeepLowest(n) => write!(f, "kl{n}"),
+            Selection::DropHighest(n) => write!(f, "dh{n}"),
+            Selection::DropLowest(n) => write!(f, "dl{n}"),
+        }
+    }
+}
+
+/// A `Reroll` tells which results of a dice are rolled again, the previous result being
+/// discarded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reroll {
+    /// Rerolls once the results lower or equal to the given value (`r`).
+    Once(u32),
+    /// Rerolls the results lower or equal to the given value until they are higher (`rr`),
+    /// within the limit of [`MAX_CHAIN_LENGTH`] rerolls.
+    Until(u32),
+}
+
+impl Reroll {
+    fn threshold(self) -> u32 {
+        match self {
+            Reroll::Once(n) | Reroll::Until(n) => n,
+        }
+    }
+
+    fn max_rerolls(self) -> u32 {
+        match self {
+            Reroll::Once(_) => 1,
+            Reroll::Until(_) => MAX_CHAIN_LENGTH,
+        }
+    }
+
+    /// Tells whether the reroll makes sense for the given dice: at least one result must be
+    /// rerolled and at least one must not. Only dices numbered from 1 can be rerolled.
+    #[must_use]
+    pub fn is_valid_for(self, dice: Dice) -> bool {
+        dice.has_numbered_faces() && (1..dice.side_count()).contains(&self.threshold())
+    }
+}
+
+impl Display for Reroll {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reroll::Once(n) => write!(f, "r{n}"),
+            Reroll::Until(n) => write!(f, "rr{n}"),
+        }
+    }
+}
+
+/// An `Explosion` tells which results of a dice trigger an additional roll, within the limit
+/// of [`MAX_CHAIN_LENGTH`] explosions per dice.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Explosion {
+    /// Results greater or equal to the given value add another dice to the roll (`!`).
+    Explode(u32),
+    /// Results greater or equal to the given value add another roll to the same dice (`!!`).
+    Compound(u32),
+}
+
+impl Explosion {
+    fn threshold(self) -> u32 {
+        match self {
+            Explosion::Explode(n) | Explosion::Compound(n) => n,
+        }
+    }
+
+    fn origin(self) -> RollOrigin {
+        match self {
+            Explosion::Explode(_) => RollOrigin::Explosion,
+            Explosion::Compound(_) => RollOrigin::Compound,
+        }
+    }
+
+    /// Tells whether the explosion makes sense for the given dice: at least one result must
+    /// explode and at least one must not. Only dices numbered from 1 can explode.
+    #[must_use]
+    pub fn is_valid_for(self, dice: Dice) -> bool {
+        dice.has_numbered_faces() && (2..=dice.side_count()).contains(&self.threshold())
+    }
+}
+
+/// A `DiceTerm` is a group of dices of the same kind, such as `3d6`, whose dices can be
+/// rerolled ([`Reroll`]), can explode ([`Explosion`]) and can be restricted to a
+/// [`Selection`] of the rolled dices, such as `2d20kh1`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DiceTerm {
+    pub(super) count: u32,
+    pub(super) dice: Dice,
+    pub(super) reroll: Option<Reroll>,
+    pub(super) explosion: Option<Explosion>,
+    pub(super) selection: Option<Selection>,
+}
+
+impl DiceTerm {
+    /// Creates a new `DiceTerm` of `count` times the given [`Dice`].
+    #[must_use]
+    pub fn new(count: u32, dice: Dice) -> Self {
+        Self {
+            count,
+            dice,
+            reroll: None,
+            explosion: None,
+            selection: None,
+        }
+    }
+
+    /// Restricts the dices that count in the total of the term to the given [`Selection`].
+    ///
+    /// # Errors
+    /// [`Error::InvalidSelection`] is returned when the selection does not make sense for the
+    /// number of dices of the term (e.g. `2d20kh3`).
+    pub fn with_selection(self, selection: Selection) -> Result<Self, Error> {
+        if !selection.is_valid_for(self.count) {
+            return Err(Error::InvalidSelection(format!("{self}{selection}")));
+        }
+        Ok(Self {
+            selection: Some(selection),
+            ..self
+        })
+    }
+
+    /// Rerolls the dices of the term according to the given [`Reroll`].
+    ///
+    /// # Errors
+    /// [`Error::InvalidModifier`] is returned when the reroll does not make sense for the
+    /// dice of the term (e.g. `d6r6`).
+    pub fn with_reroll(self, reroll: Reroll) -> Result<Self, Error> {
+        if !reroll.is_valid_for(self.dice) {
+            return Err(Error::InvalidModifier(format!("{self}{reroll}")));
+        }
+        Ok(Self {
+            reroll: Some(reroll),
+            ..self
+        })
+    }
+
+    /// Makes the dices of the term explode according to the given [`Explosion`].
+    ///
+    /// # Errors
+    /// [`Error::InvalidModifier`] is returned when the explosion does not make sense for the
+    /// dice of the term (e.g. `d6!1`).
+    pub fn with_explosion(self, explosion: Explosion) -> Result<Self, Error> {
+        if !explosion.is_valid_for(self.dice) {
+            let term = Self {
+                explosion: Some(explosion),
+                ..self
+            };
+            return Err(Error::InvalidModifier(term.to_string()));
+        }
+        Ok(Self {
+            explosion: Some(explosion),
+            ..self
+        })
+    }
+
+    /// `count` returns the number of dices of the term.
+    #[must_use]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// `dice` returns the kind of [`Dice`] of the term.
+    #[must_use]
+    pub fn dice(&self) -> Dice {
+        self.dice
+    }
+
+    /// `selection` returns the [`Selection`] of the dices that are kept, if any.
+    #[must_use]
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    /// `reroll` returns the [`Reroll`] of the dices of the term, if any.
+    #[must_use]
+    pub fn reroll(&self) -> Option<Reroll> {
+        self.reroll
+    }
+
+    /// `explosion` returns the [`Explosion`] of the dices of the term, if any.
+    #[must_use]
+    pub fn explosion(&self) -> Option<Explosion> {
+        self.explosion
+    }
+
+    /// `kept_count` returns the number of dices that count in the total of the term when no
+    /// dice explodes.
+    #[must_use]
+    pub fn kept_count(&self) -> u32 {
+        self.selection
+            .map_or(self.count, |selection| selection.kept_count(self.count))
+    }
+
+    /// `max_roll_count` returns the number of times a dice is rolled in the worst case, when
+    /// every reroll and every explosion happens.
+    #[must_use]
+    pub fn max_roll_count(&self) -> u64 {
+        let per_dice = 1
+            + self.reroll.map_or(0, Reroll::max_rerolls)
+            + self.explosion.map_or(0, |_| MAX_CHAIN_LENGTH);
+        u64::from(self.count) * u64::from(per_dice)
+    }
+
+    /// Returns the lowest and highest possible outcomes of the term.
+    ///
+    /// Since rerolls are capped, even a dice rerolled until it is high enough may end on its
+    /// lowest result.
+    pub(super) fn bounds(self) -> Option<(i64, i64)> {
+        let lowest = self.dice.face_value(1);
+        let sides = self.dice.face_value(self.dice.side_count());
+        let (highest_result, max_units) = match self.explosion {
+            None => (sides, self.count),
+            Some(Explosion::Explode(_)) => (sides, self.count.checked_mul(MAX_CHAIN_LENGTH + 1)?),
+            Some(Explosion::Compound(_)) => (
+                sides.checked_mul(i64::from(MAX_CHAIN_LENGTH) + 1)?,
+                self.count,
+            ),
+        };
+        let kept = |units: u32| {
+            i64::from(
+                self.selection
+                    .map_or(units, |selection| selection.kept_count(units)),
+            )
+        };
+
+        Some((
+            kept(self.count).checked_mul(lowest)?,
+            kept(max_units).checked_mul(highest_result)?,
+        ))
+    }
+
+    /// Rolls all the dices of the term.
+    #[must_use]
+    pub fn roll(&self) -> RolledDiceTerm {
+        self.roll_with(&mut ThreadRoller)
+    }
+
+    /// Rolls all the dices of the term with the given [`Roller`].
+    #[must_use]
+    pub fn roll_with(&self, roller: &mut (impl Roller + ?Sized)) -> RolledDiceTerm {
+        self.roll_each(&mut |dice| roller.roll(dice))
+    }
+
+    /// Rolls all the dices of the term, the result of each individual roll being given by
+    /// `roll`. Rerolls, explosions and the selection are applied in that order.
+    fn roll_each(&self, roll: &mut impl FnMut(Dice) -> u32) -> RolledDiceTerm {
+        let mut rolled_dices = Vec::with_capacity(self.count as usize);
+
+        for _ in 0..self.count {
+            let mut rolled_dice = RolledDice::new(self.dice, roll(self.dice));
+
+            if let Some(reroll) = self.reroll {
+                let mut rerolls = 0;
+                while rolled_dice.result <= reroll.threshold() && rerolls < reroll.max_rerolls() {
+                    rolled_dice.discarded = true;
+                    rolled_dices.push(rolled_dice);
+                    rolled_dice =
+                        RolledDice::new(self.dice, roll(self.dice)).with_origin(RollOrigin::Reroll);
+                    rerolls += 1;
+                }
+            }
+
+            let mut last_result = rolled_dice.result;
+            rolled_dices.push(rolled_dice);
+
+            if let Some(explosion) = self.explosion {
+                let mut explosions = 0;
+                while last_result >= explosion.threshold() && explosions < MAX_CHAIN_LENGTH {
+                    let rolled_dice =
+                        RolledDice::new(self.dice, roll(self.dice)).with_origin(explosion.origin());
+                    last_result = rolled_dice.result;
+                    rolled_dices.push(rolled_dice);
+                    explosions += 1;
+                }
+            }
+        }
+
+        if let Some(selection) = self.selection {
+            selection.apply(&mut rolled_dices);
+        }
+
+        RolledDiceTerm {
+            term: *self,
+            rolled_dices,
+        }
+    }
+
+    /// Replays the rolls of the term out of the results of previously rolled dices, consuming
+    /// as many of them as rolling the term requires.
+    ///
+    /// # Errors
+    /// [`Error::RolledDicesMismatch`] is returned when the consumed dices are not the outcome
+    /// of rolling the term.
+    pub(super) fn replay<'a>(
+        &self,
+        rolled_dices: &mut impl Iterator<Item = &'a RolledDice>,
+    ) -> Result<RolledDiceTerm, Error> {
+        let mut consumed = Vec::new();
+        let mut is_consistent = true;
+
+        let replayed = self.roll_each(&mut |dice| match rolled_dices.next() {
+            Some(rd) if rd.dice == dice && (1..=dice.side_count()).contains(&rd.result) => {
+                consumed.push(*rd);
+                rd.result
+            }
+            _ => {
+                is_consistent = false;
+                1
+            }
+        });
+
+        if is_consistent && replayed.rolled_dices == consumed {
+            Ok(replayed)
+        } else {
+            Err(Error::RolledDicesMismatch)
+        }
+    }
+}
+
+impl Display for DiceTerm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.count != 1 {
+            write!(f, "{}", self.count)?;
+        }
+        write!(f, "{}", self.dice)?;
+        if let Some(reroll) = self.reroll {
+            write!(f, "{reroll}")?;
+        }
+        match self.explosion {
+            None => {}
+            Some(Explosion::Explode(n)) if n == self.dice.side_count() => write!(f, "!")?,
+            Some(Explosion::Explode(n)) => write!(f, "!{n}")?,
+            Some(Explosion::Compound(n)) if n == self.dice.side_count() => write!(f, "!!")?,
+            Some(Explosion::Compound(n)) => write!(f, "!!{n}")?,
+        }
+        if let Some(selection) = self.selection {
+            write!(f, "{selection}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A `RolledDiceTerm` is the outcome of rolling a [`DiceTerm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RolledDiceTerm {
+    pub(super) term: DiceTerm,
+    pub(super) rolled_dices: Vec<RolledDice>,
+}
+
+impl RolledDiceTerm {
+    /// Rebuilds the outcome of rolling the `term` out of all its rolled dices, rerolled and
+    /// discarded ones included.
+    ///
+    /// # Errors
+    /// [`Error::RolledDicesMismatch`] is returned when the rolled dices cannot be the outcome
+    /// of rolling the given [`DiceTerm`].
+    pub fn try_new(
+        term: DiceTerm,
+        rolled_dices: impl IntoIterator<Item = RolledDice>,
+    ) -> Result<Self, Error> {
+        let rolled_dices = rolled_dices.into_iter().collect::<Vec<RolledDice>>();
+        let mut rolled_dices = rolled_dices.iter();
+        let rolled_term = term.replay(&mut rolled_dices)?;
+
+        if rolled_dices.next().is_some() {
+            return Err(Error::RolledDicesMismatch);
+        }
+        Ok(rolled_term)
+    }
+
+    /// `term` returns the [`DiceTerm`] that has been rolled.
+    #[must_use]
+    pub fn term(&self) -> DiceTerm {
+        self.term
+    }
+
+    /// `iter` returns an iterator of all the [`RolledDice`] of the term, in the order they
+    /// have been rolled, rerolled and discarded ones included.
+    pub fn iter(&self) -> impl Iterator<Item = &RolledDice> {
+        self.rolled_dices.iter()
+    }
+
+    /// `total` returns the sum of the values of all the dices of the term that have not been
+    /// discarded.
+    #[must_use]
+    pub fn total(&self) -> i64 {
+        self.rolled_dices
+            .iter()
+            .filter(|rd| !rd.discarded)
+            .map(RolledDice::value)
+            .sum()
+    }
+}
+
+impl Display for RolledDiceTerm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let results = self
+            .rolled_dices
+            .iter()
+            .map(|rd| {
+                let origin = match rd.origin {
+                    RollOrigin::Initial => "",
+                    RollOrigin::Reroll => "r",
+                    RollOrigin::Explosion => "!",
+                    RollOrigin::Compound => "!!",
+                };
+                let discarded = if rd.discarded { "~" } else { "" };
+                format!("{discarded}{origin}{}", rd.value())
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{}[{results}]", self.term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::dice::ScriptedRoller;
+
+    fn rolled(dice: Dice, results: &[u32]) -> Vec<RolledDice> {
+        results.iter().map(|r| RolledDice::new(dice, *r)).collect()
+    }
+
+    /// Rolls the term using the given results instead of random ones.
+    fn scripted_roll(term: &DiceTerm, results: &[u32]) -> RolledDiceTerm {
+        term.roll_with(&mut ScriptedRoller::new(results.iter().copied()))
+    }
+
+    #[test]
+    fn can_validate_selections() {
+        let test_cases = &[
+            (Selection::KeepHighest(1), 2, true),
+            (Selection::KeepHighest(2), 2, true),
+            (Selection::KeepHighest(3), 2, false),
+            (Selection::KeepLowest(0), 2, false),
+            (Selection::DropLowest(1), 4, true),
+            (Selection::DropLowest(4), 4, false),
+            (Selection::DropHighest(0), 4, false),
+        ];
+
+        for tc in test_cases {
+            assert_eq!(tc.0.is_valid_for(tc.1), tc.2, "{:?} of {}", tc.0, tc.1);
+        }
+
+        let term = DiceTerm::new(2, Dice::D20);
+        assert!(term.with_selection(Selection::KeepHighest(1)).is_ok());
+        assert!(matches!(
+            term.with_selection(Selection::KeepHighest(3)),
+            Err(Error::InvalidSelection(_))
+        ));
+    }
+
+    #[test]
+    fn can_apply_selections() {
+        let test_cases = &[
+            (
+                Selection::KeepHighest(1),
+                vec![7, 15],
+                vec![true, false],
+                15,
+            ),
+            (Selection::KeepLowest(1), vec![7, 15], vec![false, true], 7),
+            (Selection::KeepHighest(1), vec![9, 9], vec![true, false], 9),
+            (
+                Selection::DropLowest(1),
+                vec![3, 1, 6, 1],
+                vec![false, true, false, false],
+                10,
+            ),
+            (
+                Selection::DropHighest(2),
+                vec![3, 1, 6, 5],
+                vec![false, false, true, true],
+                4,
+            ),
+        ];
+
+        for tc in test_cases {
+            let term = DiceTerm::new(u32::try_from(tc.1.len()).unwrap(), Dice::D20)
+                .with_selection(tc.0)
+                .unwrap();
+            let mut rolled_dices = rolled(Dice::D20, &tc.1);
+            tc.0.apply(&mut rolled_dices);
+
+            let discarded = rolled_dices
+                .iter()
+                .map(|rd| rd.discarded)
+                .collect::<Vec<_>>();
+            assert_eq!(discarded, tc.2, "{:?} applied to {:?}", tc.0, tc.1);
+
+            let rolled_term = RolledDiceTerm::try_new(term, rolled_dices).unwrap();
+            assert_eq!(rolled_term.total(), tc.3);
+        }
+    }
+
+    #[test]
+    fn can_roll_dice_term_with_selection() {
+        let term = DiceTerm::new(4, Dice::D6)
+            .with_selection(Selection::DropLowest(1))
+            .unwrap();
+        assert_eq!(term.bounds(), Some((3, 18)));
+
+        for _ in 0..100 {
+            let rolled_term = term.roll();
+            assert_eq!(rolled_term.iter().count(), 4);
+            assert_eq!(rolled_term.iter().filter(|rd| rd.discarded).count(), 1);
+
+            let lowest = rolled_term
+                .iter()
+                .map(|rd| i64::from(rd.result))
+                .min()
+                .unwrap();
+            let sum = rolled_term
+                .iter()
+                .map(|rd| i64::from(rd.result))
+                .sum::<i64>();
+            assert_eq!(rolled_term.total(), sum - lowest);
+
+            let rebuilt = RolledDiceTerm::try_new(term, rolled_term.rolled_dices.clone());
+            assert_eq!(rebuilt.unwrap(), rolled_term);
+        }
+    }
+
+    #[test]
+    fn can_reroll_dices() {
+        let once = DiceTerm::new(1, Dice::D8)
+            .with_reroll(Reroll::Once(1))
+            .unwrap();
+        let rolled_term = scripted_roll(&once, &[1, 1]);
+        assert_eq!(rolled_term.to_string(), "d8r1[~1, r1]");
+        assert_eq!(rolled_term.total(), 1);
+
+        let rolled_term = scripted_roll(&once, &[4]);
+        assert_eq!(rolled_term.to_string(), "d8r1[4]");
+
+        let until = DiceTerm::new(2, Dice::D6)
+            .with_reroll(Reroll::Until(2))
+            .unwrap();
+        let rolled_term = scripted_roll(&until, &[1, 2, 5, 3]);
+        assert_eq!(rolled_term.to_string(), "2d6rr2[~1, ~r2, r5, 3]");
+        assert_eq!(rolled_term.total(), 8);
+        assert_eq!(until.bounds(), Some((2, 12)));
+
+        let always_one = scripted_roll(&until, &[1; 30]);
+        assert_eq!(
+            always_one.iter().count(),
+            2 * (MAX_CHAIN_LENGTH as usize + 1)
+        );
+        assert_eq!(always_one.total(), 2);
+        assert_eq!(always_one.total(), until.bounds().unwrap().0);
+    }
+
+    #[test]
+    fn can_explode_dices() {
+        let explode = DiceTerm::new(2, Dice::D6)
+            .with_explosion(Explosion::Explode(6))
+            .unwrap();
+        let rolled_term = scripted_roll(&explode, &[6, 6, 2, 3]);
+        assert_eq!(rolled_term.to_string(), "2d6![6, !6, !2, 3]");
+        assert_eq!(rolled_term.total(), 17);
+
+        let compound = DiceTerm::new(2, Dice::D6)
+            .with_explosion(Explosion::Compound(5))
+            .unwrap()
+            .with_selection(Selection::KeepHighest(1))
+            .unwrap();
+        let rolled_term = scripted_roll(&compound, &[5, 1, 4]);
+        assert_eq!(rolled_term.to_string(), "2d6!!5kh1[5, !!1, ~4]");
+        assert_eq!(rolled_term.total(), 6);
+
+        let explode_and_keep = DiceTerm::new(2, Dice::D6)
+            .with_explosion(Explosion::Explode(6))
+            .unwrap()
+            .with_selection(Selection::KeepHighest(2))
+            .unwrap();
+        let rolled_term = scripted_roll(&explode_and_keep, &[6, 5, 1]);
+        assert_eq!(rolled_term.to_string(), "2d6!kh2[6, !5, ~1]");
+        assert_eq!(rolled_term.total(), 11);
+
+        let always_six = scripted_roll(&explode, &[6; 30]);
+        assert_eq!(
+            always_six.iter().count(),
+            2 * (MAX_CHAIN_LENGTH as usize + 1)
+        );
+        assert_eq!(
+            explode.bounds(),
+            Some((2, 2 * 6 * (i64::from(MAX_CHAIN_LENGTH) + 1)))
+        );
+        assert!(always_six.total() <= explode.bounds().unwrap().1);
+    }
+
+    #[test]
+    fn can_validate_rerolls_and_explosions() {
+        let d6 = DiceTerm::new(1, Dice::D6);
+        assert!(d6.with_reroll(Reroll::Once(5)).is_ok());
+        assert!(matches!(
+            d6.with_reroll(Reroll::Until(6)),
+            Err(Error::InvalidModifier(_))
+        ));
+        assert!(matches!(
+            d6.with_reroll(Reroll::Once(0)),
+            Err(Error::InvalidModifier(_))
+        ));
+        assert!(d6.with_explosion(Explosion::Explode(2)).is_ok());
+        assert!(matches!(
+            d6.with_explosion(Explosion::Compound(1)),
+            Err(Error::InvalidModifier(_))
+        ));
+        assert!(matches!(
+            d6.with_explosion(Explosion::Explode(7)),
+            Err(Error::InvalidModifier(_))
+        ));
+
+        // the thresholds are face values, which the faces of the special dices are not
+        for dice in [Dice::Fudge, Dice::PercentileTens] {
+            let term = DiceTerm::new(1, dice);
+            assert!(matches!(
+                term.with_reroll(Reroll::Once(1)),
+                Err(Error::InvalidModifier(_))
+            ));
+            assert!(matches!(
+                term.with_explosion(Explosion::Explode(2)),
+                Err(Error::InvalidModifier(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn can_replay_rolled_dice_terms() {
+        let term = DiceTerm::new(3, Dice::D6)
+            .with_reroll(Reroll::Once(1))
+            .unwrap()
+            .with_explosion(Explosion::Explode(6))
+            .unwrap()
+            .with_selection(Selection::DropLowest(1))
+            .unwrap();
+
+        for _ in 0..100 {
+            let rolled_term = term.roll();
+            let rebuilt = RolledDiceTerm::try_new(term, rolled_term.rolled_dices.clone());
+            assert_eq!(rebuilt.unwrap(), rolled_term);
+        }
+    }
+
+    #[test]
+    fn cannot_rebuild_inconsistent_rolled_dice_term() {
+        let term = DiceTerm::new(2, Dice::D20)
+            .with_selection(Selection::KeepHighest(1))
+            .unwrap();
+
+        let mut kept_lowest = rolled(Dice::D20, &[5, 12]);
+        kept_lowest[1].discarded = true;
+        let nothing_discarded = rolled(Dice::D20, &[5, 12]);
+        let mut too_many = rolled(Dice::D20, &[5, 12, 3]);
+        too_many[0].discarded = true;
+        let mut out_of_range = rolled(Dice::D20, &[5, 21]);
+        out_of_range[0].discarded = true;
+
+        for tc in [kept_lowest, nothing_discarded, too_many, out_of_range] {
+            assert!(matches!(
+                RolledDiceTerm::try_new(term, tc),
+                Err(Error::RolledDicesMismatch)
+            ));
+        }
+
+        let mut discarded_without_selection = rolled(Dice::D20, &[5, 12]);
+        discarded_without_selection[0].discarded = true;
+        assert!(matches!(
+            RolledDiceTerm::try_new(DiceTerm::new(2, Dice::D20), discarded_without_selection),
+            Err(Error::RolledDicesMismatch)
+        ));
+
+        let explode = DiceTerm::new(1, Dice::D6)
+            .with_explosion(Explosion::Explode(6))
+            .unwrap();
+        assert!(matches!(
+            RolledDiceTerm::try_new(explode, rolled(Dice::D6, &[6])),
+            Err(Error::RolledDicesMismatch)
+        ));
+        assert!(matches!(
+            RolledDiceTerm::try_new(explode, rolled(Dice::D6, &[6, 2])),
+            Err(Error::RolledDicesMismatch)
+        ));
+    }
+
+    #[test]
+    fn can_display_dice_terms() {
+        let test_cases = &[
+            (DiceTerm::new(1, Dice::D20), "d20"),
+            (DiceTerm::new(3, Dice::D6), "3d6"),
+            (
+                DiceTerm::new(2, Dice::D20)
+                    .with_selection(Selection::KeepLowest(1))
+                    .unwrap(),
+                "2d20kl1",
+            ),
+            (
+                DiceTerm::new(4, Dice::D6)
+                    .with_selection(Selection::DropLowest(1))
+                    .unwrap(),
+                "4d6dl1",
+            ),
+            (
+                DiceTerm::new(1, Dice::D6)
+                    .with_explosion(Explosion::Explode(6))
+                    .unwrap(),
+                "d6!",
+            ),
+            (
+                DiceTerm::new(1, Dice::D10)
+                    .with_explosion(Explosion::Compound(9))
+                    .unwrap(),
+                "d10!!9",
+            ),
+            (
+                DiceTerm::new(4, Dice::D6)
+                    .with_reroll(Reroll::Until(1))
+                    .unwrap()
+                    .with_selection(Selection::KeepHighest(3))
+                    .unwrap(),
+                "4d6rr1kh3",
+            ),
+        ];
+
+        for tc in test_cases {
+            assert_eq!(tc.0.to_string(), tc.1);
+        }
+    }
+
+    #[test]
+    fn can_roll_special_dices() {
+        let fudge = DiceTerm::new(4, Dice::Fudge)
+            .with_selection(Selection::DropLowest(1))
+            .unwrap();
+        let rolled = scripted_roll(&fudge, &[1, 3, 2, 3]);
+        assert_eq!(rolled.total(), 2);
+        assert_eq!(rolled.to_string(), "4dFdl1[~-1, 1, 0, 1]");
+        assert_eq!(fudge.bounds(), Some((-3, 3)));
+
+        let tens = DiceTerm::new(1, Dice::PercentileTens);
+        assert_eq!(scripted_roll(&tens, &[1]).total(), 0);
+        assert_eq!(scripted_roll(&tens, &[10]).to_string(), "d00[90]");
+        assert_eq!(tens.bounds(), Some((0, 90)));
+
+        assert!(matches!(
+            DiceTerm::new(4, Dice::Fudge).with_explosion(Explosion::Explode(3)),
+            Err(Error::InvalidModifier(_))
+        ));
+        assert!(
+            DiceTerm::new(2, Dice::with_sides(7).unwrap())
+                .with_explosion(Explosion::Explode(7))
+                .is_ok()
+        );
+    }
+}
@@ -1,24 +1,98 @@
-use rand::prelude::*;
 use std::fmt::Display;
 
+use super::{Roller, ThreadRoller};
+
+/// The maximum number of sides of a custom dice.
+pub const MAX_DICE_SIDES: u32 = 1000;
+
+/// The number of sides of a [`Dice::Custom`] dice, only built by [`Dice::with_sides`] so that
+/// it is always between 2 and [`MAX_DICE_SIDES`] and never the one of a common dice.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sides(u32);
+
 /// Dice represents the different kinds of Table Top Role Playing Games.
+///
+/// The common dices have their own variant, any other numbered dice being a
+/// [`Dice::Custom`] one. Special dices have faces that are not numbered from 1: their results
+/// are the index of the face rolled, [`Dice::face_value`] giving what the face is worth.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Dice {
-    D3 = 3,
-    D4 = 4,
-    D6 = 6,
-    D8 = 8,
-    D10 = 10,
-    D12 = 12,
-    D20 = 20,
-    D100 = 100,
+    D3,
+    D4,
+    D6,
+    D8,
+    D10,
+    D12,
+    D20,
+    D100,
+    /// A dice numbered from 1 to the given number of sides (e.g. `d7`), created with
+    /// [`Dice::with_sides`] so that common dices keep their own variant.
+    Custom(Sides),
+    /// The Fudge (or FATE) dice (`dF`) whose faces are worth -1, 0 and +1.
+    Fudge,
+    /// The tens dice of a percentile roll (`d00`) whose faces are worth 00, 10, ... up to 90.
+    PercentileTens,
 }
 
 impl Dice {
+    /// Returns the numbered dice with the given number of sides.
+    ///
+    /// # Errors
+    /// [`super::Error::DiceUnknown`] is returned when the dice has less than 2 sides or more
+    /// than [`MAX_DICE_SIDES`].
+    pub fn with_sides(sides: u32) -> Result<Self, super::Error> {
+        match sides {
+            3 => Ok(Self::D3),
+            4 => Ok(Self::D4),
+            6 => Ok(Self::D6),
+            8 => Ok(Self::D8),
+            10 => Ok(Self::D10),
+            12 => Ok(Self::D12),
+            20 => Ok(Self::D20),
+            100 => Ok(Self::D100),
+            2..=MAX_DICE_SIDES => Ok(Self::Custom(Sides(sides))),
+            _ => Err(super::Error::DiceUnknown(format!("d{sides}"))),
+        }
+    }
+
     /// returns the total number of side the dice has.
     #[must_use]
     pub fn side_count(&self) -> u32 {
-        *self as u32
+        match self {
+            Dice::D3 | Dice::Fudge => 3,
+            Dice::D4 => 4,
+            Dice::D6 => 6,
+            Dice::D8 => 8,
+            Dice::D10 | Dice::PercentileTens => 10,
+            Dice::D12 => 12,
+            Dice::D20 => 20,
+            Dice::D100 => 100,
+            Dice::Custom(Sides(sides)) => *sides,
+        }
+    }
+
+    /// `has_numbered_faces` tells whether the faces of the dice are numbered from 1 to its
+    /// number of sides, which is the case of all the dices but the special ones.
+    #[must_use]
+    pub fn has_numbered_faces(&self) -> bool {
+        !matches!(self, Dice::Fudge | Dice::PercentileTens)
+    }
+
+    /// Returns the scale and the offset turning the index of a face into its value.
+    pub(super) fn face_scale_offset(self) -> (i64, i64) {
+        match self {
+            Dice::Fudge => (1, -2),
+            Dice::PercentileTens => (10, -10),
+            _ => (1, 0),
+        }
+    }
+
+    /// `face_value` returns what the face of the given index is worth, the index being the
+    /// result of the roll between 1 and the number of sides.
+    #[must_use]
+    pub fn face_value(&self, result: u32) -> i64 {
+        let (scale, offset) = self.face_scale_offset();
+        scale * i64::from(result) + offset
     }
 
     /// rolls the dice and returns a [`RolledDice`] containing the actual dice and
@@ -26,10 +100,14 @@ impl Dice {
     /// side the dice has.
     #[must_use]
     pub fn roll(self) -> RolledDice {
-        RolledDice {
-            dice: self,
-            result: rand::rng().random_range(1..=self.side_count()),
-        }
+        self.roll_with(&mut ThreadRoller)
+    }
+
+    /// rolls the dice the same way [`Dice::roll`] does, the result being given by the
+    /// provided [`Roller`].
+    #[must_use]
+    pub fn roll_with(self, roller: &mut (impl Roller + ?Sized)) -> RolledDice {
+        RolledDice::new(self, roller.roll(self))
     }
 }
 
@@ -37,61 +115,109 @@ impl TryFrom<&str> for Dice {
     type Error = super::Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let unknown = || Self::Error::DiceUnknown(value.to_string());
         match value {
-            "d3" => Ok(Self::D3),
-            "d4" => Ok(Self::D4),
-            "d6" => Ok(Self::D6),
-            "d8" => Ok(Self::D8),
-            "d10" => Ok(Self::D10),
-            "d12" => Ok(Self::D12),
-            "d20" => Ok(Self::D20),
-            "d100" => Ok(Self::D100),
-            _ => Err(Self::Error::DiceUnknown(value.to_string())),
+            "dF" => Ok(Self::Fudge),
+            "d00" => Ok(Self::PercentileTens),
+            _ => {
+                let sides = value.strip_prefix('d').ok_or_else(unknown)?;
+                if sides.starts_with('0') || !sides.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(unknown());
+                }
+                let sides = sides.parse().map_err(|_| unknown())?;
+                Self::with_sides(sides).map_err(|_| unknown())
+            }
+        }
+    }
+}
+
+impl Display for Dice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dice::Fudge => write!(f, "dF"),
+            Dice::PercentileTens => write!(f, "d00"),
+            dice => write!(f, "d{}", dice.side_count()),
         }
     }
 }
 
-impl From<Dice> for &str {
-    fn from(value: Dice) -> Self {
+/// `RollOrigin` tells why a dice has been rolled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum RollOrigin {
+    /// The dice is one of the dices of its [`super::DiceTerm`].
+    #[default]
+    Initial,
+    /// The dice replaces the previous one that has been rerolled.
+    Reroll,
+    /// The dice has been added by the explosion of the previous one.
+    Explosion,
+    /// The dice has been added to the result of the previous one by a compound explosion.
+    Compound,
+}
+
+impl TryFrom<&str> for RollOrigin {
+    type Error = super::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
-            Dice::D3 => "d3",
-            Dice::D4 => "d4",
-            Dice::D6 => "d6",
-            Dice::D8 => "d8",
-            Dice::D10 => "d10",
-            Dice::D12 => "d12",
-            Dice::D20 => "d20",
-            Dice::D100 => "d100",
+            "initial" => Ok(Self::Initial),
+            "reroll" => Ok(Self::Reroll),
+            "explosion" => Ok(Self::Explosion),
+            "compound" => Ok(Self::Compound),
+            _ => Err(Self::Error::RollOriginUnknown(value.to_string())),
         }
     }
 }
 
-impl Display for Dice {
+impl Display for RollOrigin {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Dice::D3 => write!(f, "d3"),
-            Dice::D4 => write!(f, "d4"),
-            Dice::D6 => write!(f, "d6"),
-            Dice::D8 => write!(f, "d8"),
-            Dice::D10 => write!(f, "d10"),
-            Dice::D12 => write!(f, "d12"),
-            Dice::D20 => write!(f, "d20"),
-            Dice::D100 => write!(f, "d100"),
+            RollOrigin::Initial => write!(f, "initial"),
+            RollOrigin::Reroll => write!(f, "reroll"),
+            RollOrigin::Explosion => write!(f, "explosion"),
+            RollOrigin::Compound => write!(f, "compound"),
         }
     }
 }
 
 /// A `RolledDice` represents the outcome of rolling a dice.
+///
+/// A rolled dice can be discarded by the [`super::Selection`] of its [`super::DiceTerm`] or
+/// when it is rerolled: it stays visible in the roll but does not count in its total.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct RolledDice {
     pub(super) dice: Dice,
     pub(super) result: u32,
+    pub(super) discarded: bool,
+    pub(super) origin: RollOrigin,
 }
 
 impl RolledDice {
     #[must_use]
     pub fn new(dice: Dice, result: u32) -> Self {
-        Self { dice, result }
+        Self {
+            dice,
+            result,
+            discarded: false,
+            origin: RollOrigin::Initial,
+        }
+    }
+
+    /// Creates a `RolledDice` that has been discarded and does not count in the total.
+    #[must_use]
+    pub fn new_discarded(dice: Dice, result: u32) -> Self {
+        Self {
+            dice,
+            result,
+            discarded: true,
+            origin: RollOrigin::Initial,
+        }
+    }
+
+    /// Tells why the dice has been rolled.
+    #[must_use]
+    pub fn with_origin(self, origin: RollOrigin) -> Self {
+        Self { origin, ..self }
     }
 
     /// `dice` returns the [`Dice`] that has been rolled.
@@ -105,6 +231,25 @@ impl RolledDice {
     pub fn result(&self) -> u32 {
         self.result
     }
+
+    /// `value` returns what the rolled face is worth, which is the result itself for the
+    /// dices numbered from 1.
+    #[must_use]
+    pub fn value(&self) -> i64 {
+        self.dice.face_value(self.result)
+    }
+
+    /// `is_discarded` tells whether the dice has been discarded from the total of the roll.
+    #[must_use]
+    pub fn is_discarded(&self) -> bool {
+        self.discarded
+    }
+
+    /// `origin` returns why the dice has been rolled.
+    #[must_use]
+    pub fn origin(&self) -> RollOrigin {
+        self.origin
+    }
 }
 
 #[cfg(test)]
@@ -122,6 +267,9 @@ mod tests {
             (Dice::D12, 12),
             (Dice::D20, 20),
             (Dice::D100, 100),
+            (Dice::with_sides(7).unwrap(), 7),
+            (Dice::Fudge, 3),
+            (Dice::PercentileTens, 10),
         ];
 
         for tc in test_cases {
@@ -165,6 +313,11 @@ mod tests {
             ("d12", Dice::D12),
             ("d20", Dice::D20),
             ("d100", Dice::D100),
+            ("d2", Dice::Custom(Sides(2))),
+            ("d7", Dice::Custom(Sides(7))),
+            ("d1000", Dice::Custom(Sides(1000))),
+            ("dF", Dice::Fudge),
+            ("d00", Dice::PercentileTens),
         ];
         for tc in test_cases {
             let result = Dice::try_from(tc.0);
@@ -172,11 +325,10 @@ mod tests {
             let this_dice = result.unwrap();
             assert_eq!(this_dice, tc.1);
 
-            let notation: &str = tc.1.into();
-            assert_eq!(notation, tc.0);
+            assert_eq!(tc.1.to_string(), tc.0);
         }
 
-        let invalid_cases = &["d", "d2", "d13", "dd", "1d20"];
+        let invalid_cases = &["d", "d0", "d1", "d06", "d1001", "dd", "1d20", "df", "d-2"];
         for tc in invalid_cases {
             let result = Dice::try_from(*tc);
             assert!(result.is_err());
@@ -188,6 +340,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_build_dices_from_sides() {
+        assert_eq!(Dice::with_sides(20).unwrap(), Dice::D20);
+        assert_eq!(Dice::with_sides(13).unwrap(), Dice::Custom(Sides(13)));
+        assert_eq!(Dice::with_sides(13).unwrap().side_count(), 13);
+        assert_eq!(Dice::with_sides(6).unwrap(), Dice::D6);
+        assert!(Dice::with_sides(0).is_err());
+        assert!(Dice::with_sides(1).is_err());
+        assert!(Dice::with_sides(MAX_DICE_SIDES + 1).is_err());
+    }
+
+    #[test]
+    fn can_value_special_dices() {
+        let fudge = (1..=3)
+            .map(|r| Dice::Fudge.face_value(r))
+            .collect::<Vec<_>>();
+        assert_eq!(fudge, vec![-1, 0, 1]);
+        assert!(!Dice::Fudge.has_numbered_faces());
+
+        let tens = (1..=10)
+            .map(|r| Dice::PercentileTens.face_value(r))
+            .collect::<Vec<_>>();
+        assert_eq!(tens, vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 90]);
+
+        assert_eq!(Dice::with_sides(7).unwrap().face_value(7), 7);
+        assert_eq!(RolledDice::new(Dice::Fudge, 1).value(), -1);
+
+        for _ in 0..100 {
+            let rolled = Dice::Fudge.roll();
+            assert!((-1..=1).contains(&rolled.value()));
+        }
+    }
+
+    #[test]
+    fn can_understand_roll_origins() {
+        let test_cases = &[
+            ("initial", RollOrigin::Initial),
+            ("reroll", RollOrigin::Reroll),
+            ("explosion", RollOrigin::Explosion),
+            ("compound", RollOrigin::Compound),
+        ];
+        for tc in test_cases {
+            assert_eq!(RollOrigin::try_from(tc.0).unwrap(), tc.1);
+            assert_eq!(tc.1.to_string(), tc.0);
+        }
+        assert!(matches!(
+            RollOrigin::try_from("exploded"),
+            Err(crate::model::dice::Error::RollOriginUnknown(_))
+        ));
+    }
+
     #[test]
     fn can_print_dices() {
         let test_cases = &[
@@ -0,0 +1,554 @@
+use super::{DiceTerm, Error, Explosion, Expression, MAX_CHAIN_LENGTH, Reroll, Selection};
+
+/// The maximum number of distinct outcomes a [`Distribution`] can hold.
+pub const MAX_DISTRIBUTION_SIZE: u64 = 10_000;
+
+/// The maximum number of elementary operations allowed to compute a [`Distribution`], it
+/// protects against expressions whose outcomes are few but expensive to enumerate.
+const MAX_DISTRIBUTION_STEPS: u64 = 100_000_000;
+
+/// A `Distribution` is the exact probability of every possible outcome of a
+/// [`super::DiceSet`], from its lowest to its highest possible outcome.
+///
+/// It is computed by convolution of the distributions of each dice and not by sampling, so
+/// that it can be used to assess the difficulty of an encounter beforehand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    lowest: i64,
+    probabilities: Vec<f64>,
+}
+
+impl Distribution {
+    /// Creates the distribution of a value that is known for sure.
+    fn constant(value: i64) -> Self {
+        Self {
+            lowest: value,
+            probabilities: vec![1.0],
+        }
+    }
+
+    /// Creates a distribution of values starting at `lowest` from their probabilities,
+    /// trimming the impossible values at both ends.
+    fn new(lowest: i64, mut probabilities: Vec<f64>) -> Self {
+        let skipped = probabilities.iter().take_while(|p| **p <= 0.0).count();
+        probabilities.drain(..skipped.min(probabilities.len().saturating_sub(1)));
+        while probabilities.len() > 1 && probabilities.last().is_some_and(|p| *p <= 0.0) {
+            probabilities.pop();
+        }
+        Self {
+            lowest: lowest + i64::try_from(skipped).unwrap_or(i64::MAX),
+            probabilities,
+        }
+    }
+
+    /// Allocates the probabilities of all the values between `lowest` and `highest`.
+    ///
+    /// # Errors
+    /// [`Error::WayTooManyDices`] is returned when there are more than
+    /// [`MAX_DISTRIBUTION_SIZE`] values.
+    fn zeroes(lowest: i64, highest: i64) -> Result<Vec<f64>, Error> {
+        let size = highest
+            .checked_sub(lowest)
+            .and_then(|d| u64::try_from(d).ok())
+            .and_then(|d| d.checked_add(1))
+            .filter(|size| *size <= MAX_DISTRIBUTION_SIZE)
+            .ok_or(Error::WayTooManyDices)?;
+        let size = usize::try_from(size).map_err(|_| Error::WayTooManyDices)?;
+        Ok(vec![0.0; size])
+    }
+
+    fn len(&self) -> u64 {
+        self.probabilities.len() as u64
+    }
+
+    /// `lower_bound` returns the lowest possible outcome.
+    #[must_use]
+    pub fn lower_bound(&self) -> i64 {
+        self.lowest
+    }
+
+    /// `upper_bound` returns the highest possible outcome.
+    #[must_use]
+    pub fn upper_bound(&self) -> i64 {
+        self.lowest + i64::try_from(self.probabilities.len()).unwrap_or(i64::MAX) - 1
+    }
+
+    /// `iter` returns every outcome between the lower and the upper bounds along with its
+    /// probability.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, f64)> {
+        (self.lowest..).zip(self.probabilities.iter().copied())
+    }
+
+    /// Probability Mass Function: returns the probability of the outcome to be `value`.
+    #[must_use]
+    pub fn pmf(&self, value: i64) -> f64 {
+        value
+            .checked_sub(self.lowest)
+            .and_then(|i| usize::try_from(i).ok())
+            .and_then(|i| self.probabilities.get(i))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Cumulative Distribution Function: returns the probability of the outcome to be lower
+    /// or equal to `value`.
+    #[must_use]
+    pub fn cdf(&self, value: i64) -> f64 {
+        self.iter()
+            .take_while(|(v, _)| *v <= value)
+            .map(|(_, p)| p)
+            .sum::<f64>()
+            .min(1.0)
+    }
+
+    /// Returns the probability of the outcome to be greater or equal to `target`, e.g. the
+    /// chance to reach the difficulty of a test.
+    #[must_use]
+    pub fn probability_at_least(&self, target: i64) -> f64 {
+        self.iter()
+            .skip_while(|(v, _)| *v < target)
+            .map(|(_, p)| p)
+            .sum::<f64>()
+            .min(1.0)
+    }
+
+    /// `mean` returns the expected value of the outcome.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> f64 {
+        self.iter().map(|(v, p)| v as f64 * p).sum()
+    }
+
+    /// `variance` returns how spread the outcomes are around the [`Distribution::mean`].
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+        self.iter()
+            .map(|(v, p)| (v as f64 - mean).powi(2) * p)
+            .sum()
+    }
+
+    /// Returns the distribution of the sum of two independent outcomes.
+    fn add(&self, other: &Self) -> Result<Self, Error> {
+        check_steps(self.len().saturating_mul(other.len()))?;
+        let lowest = self
+            .lowest
+            .checked_add(other.lowest)
+            .ok_or(Error::WayTooManyDices)?;
+        let highest = self
+            .upper_bound()
+            .checked_add(other.upper_bound())
+            .ok_or(Error::WayTooManyDices)?;
+        let mut probabilities = Self::zeroes(lowest, highest)?;
+        for (i, p) in self.probabilities.iter().enumerate() {
+            for (j, q) in other.probabilities.iter().enumerate() {
+                probabilities[i + j] += p * q;
+            }
+        }
+        Ok(Self::new(lowest, probabilities))
+    }
+
+    /// Returns the distribution of the opposite of the outcome.
+    fn neg(&self) -> Result<Self, Error> {
+        Ok(Self {
+            lowest: self
+                .upper_bound()
+                .checked_neg()
+                .ok_or(Error::WayTooManyDices)?,
+            probabilities: self.probabilities.iter().rev().copied().collect(),
+        })
+    }
+
+    /// Returns the distribution of `scale * outcome + offset`, `scale` being positive.
+    fn scale_and_shift(&self, scale: i64, offset: i64) -> Result<Self, Error> {
+        let map = |v: i64| {
+            v.checked_mul(scale)
+                .and_then(|v| v.checked_add(offset))
+                .ok_or(Error::WayTooManyDices)
+        };
+        let lowest = map(self.lower_bound())?;
+        let mut probabilities = Self::zeroes(lowest, map(self.upper_bound())?)?;
+        for (v, p) in self.iter() {
+            let index = usize::try_from(map(v)? - lowest).map_err(|_| Error::WayTooManyDices)?;
+            probabilities[index] += p;
+        }
+        Ok(Self::new(lowest, probabilities))
+    }
+
+    /// Returns the distribution of the product of two independent outcomes.
+    fn mul(&self, other: &Self) -> Result<Self, Error> {
+        check_steps(self.len().saturating_mul(other.len()))?;
+        let products = [
+            self.lower_bound().checked_mul(other.lower_bound()),
+            self.lower_bound().checked_mul(other.upper_bound()),
+            self.upper_bound().checked_mul(other.lower_bound()),
+            self.upper_bound().checked_mul(other.upper_bound()),
+        ]
+        .into_iter()
+        .collect::<Option<Vec<i64>>>()
+        .ok_or(Error::WayTooManyDices)?;
+        let lowest = *products.iter().min().ok_or(Error::WayTooManyDices)?;
+        let highest = *products.iter().max().ok_or(Error::WayTooManyDices)?;
+
+        let mut probabilities = Self::zeroes(lowest, highest)?;
+        for (v, p) in self.iter() {
+            for (w, q) in other.iter() {
+                let index = usize::try_from(v * w - lowest).map_err(|_| Error::WayTooManyDices)?;
+                probabilities[index] += p * q;
+            }
+        }
+        Ok(Self::new(lowest, probabilities))
+    }
+}
+
+fn check_steps(steps: u64) -> Result<(), Error> {
+    if steps > MAX_DISTRIBUTION_STEPS {
+        return Err(Error::WayTooManyDices);
+    }
+    Ok(())
+}
+
+/// Computes the distribution of the given expression.
+///
+/// # Errors
+/// - [`Error::WayTooManyDices`] is returned when the distribution is too large or too
+///   expensive to compute.
+/// - [`Error::NoExactDistribution`] is returned when one of the terms keeps or drops
+///   exploding dices.
+pub(super) fn of_expression(expression: &Expression<DiceTerm>) -> Result<Distribution, Error> {
+    match expression {
+        Expression::Constant(c) => Ok(Distribution::constant(*c)),
+        Expression::Dices(term) => of_term(term),
+        Expression::Neg(e) => of_expression(e)?.neg(),
+        Expression::Add(l, r) => of_expression(l)?.add(&of_expression(r)?),
+        Expression::Sub(l, r) => of_expression(l)?.add(&of_expression(r)?.neg()?),
+        Expression::Mul(l, r) => of_expression(l)?.mul(&of_expression(r)?),
+    }
+}
+
+/// Computes the distribution of the total of a [`DiceTerm`].
+///
+/// The distribution is computed out of the index of the faces rolled and then turned into
+/// their values, which only holds for special dices since they cannot explode.
+fn of_term(term: &DiceTerm) -> Result<Distribution, Error> {
+    let (lowest, highest) = term.bounds().ok_or(Error::WayTooManyDices)?;
+    Distribution::zeroes(lowest, highest)?;
+
+    let (scale, offset) = term.dice.face_scale_offset();
+    let summed = term
+        .selection
+        .map_or(term.count, |selection| selection.kept_count(term.count));
+    of_results(term)?.scale_and_shift(scale, offset * i64::from(summed))
+}
+
+/// Computes the distribution of the sum of the results of a [`DiceTerm`].
+fn of_results(term: &DiceTerm) -> Result<Distribution, Error> {
+    let unit = of_unit(term);
+    match term.selection {
+        None => {
+            check_steps(
+                u64::from(term.count)
+                    .saturating_mul(unit.len())
+                    .saturating_mul(MAX_DISTRIBUTION_SIZE),
+            )?;
+            let mut distribution = Distribution::constant(0);
+            for _ in 0..term.count {
+                distribution = distribution.add(&unit)?;
+            }
+            Ok(distribution)
+        }
+        Some(_) if matches!(term.explosion, Some(Explosion::Explode(_))) => {
+            Err(Error::NoExactDistribution(term.to_string()))
+        }
+        Some(selection) => of_selection(&unit, term.count, selection),
+    }
+}
+
+/// Computes the distribution of the value contributed by a single dice of the term, once
+/// rerolled and exploded. Exploding dices are summed the same way compound ones are, which
+/// holds as long as no selection applies.
+fn of_unit(term: &DiceTerm) -> Distribution {
+    let sides = term.dice.side_count();
+    let face = 1.0 / f64::from(sides);
+
+    // the result of the initial roll, once rerolled
+    let mut initial = vec![0.0; sides as usize];
+    let (threshold, max_rerolls) = match term.reroll {
+        None => (0, 0),
+        Some(Reroll::Once(n)) => (n, 1),
+        Some(Reroll::Until(n)) => (n, MAX_CHAIN_LENGTH),
+    };
+    let rerolled = f64::from(threshold) * face;
+    for (i, p) in initial.iter_mut().enumerate() {
+        let value = u32::try_from(i).unwrap_or(u32::MAX) + 1;
+        *p = if value <= threshold {
+            rerolled.powi(i32::try_from(max_rerolls).unwrap_or(i32::MAX)) * face
+        } else {
+            (0..=max_rerolls)
+                .map(|r| rerolled.powi(i32::try_from(r).unwrap_or(i32::MAX)) * face)
+                .sum()
+        };
+    }
+    let initial = Distribution::new(1, initial);
+
+    let Some(explosion) = term.explosion else {
+        return initial;
+    };
+    let threshold = i64::from(match explosion {
+        Explosion::Explode(n) | Explosion::Compound(n) => n,
+    });
+
+    // the sum of the rolls following an exploding one, with a given number of explosions left
+    let single = Distribution::new(1, vec![face; sides as usize]);
+    let mut chain = Distribution::constant(0);
+    for _ in 0..MAX_CHAIN_LENGTH {
+        chain = explode(&single, &chain, threshold);
+    }
+    explode(&initial, &chain, threshold)
+}
+
+/// Returns the distribution of a roll of `roll`, followed by `chain` when it reaches the
+/// explosion `threshold`.
+fn explode(roll: &Distribution, chain: &Distribution, threshold: i64) -> Distribution {
+    let highest = roll.upper_bound() + chain.upper_bound();
+    let mut probabilities = vec![0.0; usize::try_from(highest).unwrap_or_default() + 1];
+    for (v, p) in roll.iter() {
+        if v < threshold {
+            probabilities[usize::try_from(v).unwrap_or_default()] += p;
+        } else {
+            for (w, q) in chain.iter() {
+                probabilities[usize::try_from(v + w).unwrap_or_default()] += p * q;
+            }
+        }
+    }
+    Distribution::new(0, probabilities)
+}
+
+/// Computes the distribution of the sum of the dices kept by the `selection` among `count`
+/// independent dices following the `unit` distribution.
+///
+/// The values are visited from the most to the least favoured by the selection while
+/// counting how many dices got each value, so that each combination of results is only
+/// enumerated once whatever the order the dices have been rolled in.
+fn of_selection(
+    unit: &Distribution,
+    count: u32,
+    selection: Selection,
+) -> Result<Distribution, Error> {
+    let kept = selection.kept_count(count) as usize;
+    let count = count as usize;
+    let highest = unit.upper_bound().max(0).unsigned_abs();
+    check_steps(
+        unit.len()
+            .saturating_mul(kept as u64)
+            .saturating_mul(count as u64)
+            .saturating_mul(highest.saturating_mul(kept as u64)),
+    )?;
+
+    let mut values = unit.iter().filter(|(_, p)| *p > 0.0).collect::<Vec<_>>();
+    if matches!(
+        selection,
+        Selection::KeepHighest(_) | Selection::DropLowest(_)
+    ) {
+        values.reverse();
+    }
+
+    let max_sum = usize::try_from(highest).map_err(|_| Error::WayTooManyDices)? * kept;
+    let mut result = vec![0.0; max_sum + 1];
+    // states[j][s] is the probability that j dices got one of the visited values, summing to s
+    let mut states = vec![vec![0.0; max_sum + 1]; kept];
+    states[0][0] = 1.0;
+    let mut remaining_probability = 1.0;
+
+    for (value, p) in values {
+        let value = usize::try_from(value).map_err(|_| Error::WayTooManyDices)?;
+        remaining_probability -= p;
+        let remaining_probability = remaining_probability.max(0.0);
+
+        let mut next_states = vec![vec![0.0; max_sum + 1]; kept];
+        for (j, sums) in states.iter().enumerate() {
+            for (sum, w) in sums.iter().enumerate().filter(|(_, w)| **w > 0.0) {
+                // binomial(count - j, c) * p^c
+                let mut weight = 1.0;
+                for c in 0..=count - j {
+                    if c > 0 {
+                        #[allow(clippy::cast_precision_loss)]
+                        let ratio = (count - j - c + 1) as f64 / c as f64;
+                        weight *= ratio * p;
+                    }
+                    if j + c >= kept {
+                        // the other dices are not kept, they only need to be on the other side
+                        let exponent = i32::try_from(count - j - c).unwrap_or(i32::MAX);
+                        result[sum + (kept - j) * value] +=
+                            w * weight * remaining_probability.powi(exponent);
+                    } else {
+                        next_states[j + c][sum + c * value] += w * weight;
+                    }
+                }
+            }
+        }
+        states = next_states;
+    }
+
+    Ok(Distribution::new(0, result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::model::dice::{Dice, DiceSet};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected} but got {actual}"
+        );
+    }
+
+    fn distribution(notation: &str) -> Distribution {
+        DiceSet::from_str(notation).unwrap().distribution().unwrap()
+    }
+
+    #[test]
+    fn can_compute_distribution_of_dices() {
+        let d6 = distribution("d6");
+        assert_eq!((d6.lower_bound(), d6.upper_bound()), (1, 6));
+        for v in 1..=6 {
+            assert_close(d6.pmf(v), 1.0 / 6.0);
+        }
+        assert_close(d6.pmf(0), 0.0);
+        assert_close(d6.mean(), 3.5);
+        assert_close(d6.variance(), 35.0 / 12.0);
+
+        let two_d6 = distribution("2d6 + 3");
+        assert_eq!((two_d6.lower_bound(), two_d6.upper_bound()), (5, 15));
+        assert_close(two_d6.pmf(10), 6.0 / 36.0);
+        assert_close(two_d6.mean(), 10.0);
+        assert_close(two_d6.variance(), 35.0 / 6.0);
+        assert_close(two_d6.cdf(15), 1.0);
+        assert_close(two_d6.cdf(6), 3.0 / 36.0);
+        assert_close(two_d6.probability_at_least(12), 10.0 / 36.0);
+        assert_close(two_d6.probability_at_least(0), 1.0);
+        assert_close(two_d6.iter().map(|(_, p)| p).sum(), 1.0);
+    }
+
+    #[test]
+    fn can_compute_distribution_of_expressions() {
+        let test_cases = &[
+            ("d20 - 2", -1, 18, 8.5),
+            ("-d4", -4, -1, -2.5),
+            ("(d4 + 1) * 2", 4, 10, 7.0),
+            ("d4 * d4", 1, 16, 6.25),
+            ("10 - (d6 - 1)", 5, 10, 7.5),
+        ];
+
+        for tc in test_cases {
+            let d = distribution(tc.0);
+            assert_eq!((d.lower_bound(), d.upper_bound()), (tc.1, tc.2), "{}", tc.0);
+            assert_close(d.mean(), tc.3);
+            assert_close(d.iter().map(|(_, p)| p).sum(), 1.0);
+        }
+
+        assert_close(distribution("d4 * d4").pmf(5), 0.0);
+        assert_close(distribution("d4 * d4").pmf(4), 3.0 / 16.0);
+    }
+
+    #[test]
+    fn can_compute_distribution_of_selections() {
+        let advantage = distribution("2d20kh1");
+        assert_eq!((advantage.lower_bound(), advantage.upper_bound()), (1, 20));
+        for v in 1..=20u32 {
+            assert_close(advantage.pmf(i64::from(v)), f64::from(2 * v - 1) / 400.0);
+        }
+        assert_close(advantage.mean(), 13.825);
+
+        let disadvantage = distribution("2d20kl1");
+        assert_close(disadvantage.pmf(1), 39.0 / 400.0);
+        assert_close(disadvantage.mean(), 21.0 - 13.825);
+
+        let characteristic = distribution("4d6dl1");
+        assert_eq!(
+            (characteristic.lower_bound(), characteristic.upper_bound()),
+            (3, 18)
+        );
+        assert_close(characteristic.pmf(18), 21.0 / 1296.0);
+        assert_close(characteristic.pmf(3), 1.0 / 1296.0);
+        assert_close(characteristic.mean(), 15869.0 / 1296.0);
+
+        assert_close(distribution("3d6kh3").variance(), 3.0 * 35.0 / 12.0);
+        assert_close(distribution("4d6dh1").mean(), 8.0 + 979.0 / 1296.0);
+    }
+
+    #[test]
+    fn can_compute_distribution_of_rerolls_and_explosions() {
+        let reroll = distribution("d6r1");
+        assert_close(reroll.pmf(1), 1.0 / 36.0);
+        assert_close(reroll.pmf(6), 7.0 / 36.0);
+
+        let reroll_until = distribution("d6rr2");
+        assert_close(reroll_until.pmf(1), (1.0f64 / 3.0).powi(10) / 6.0);
+        assert_close(reroll_until.iter().map(|(_, p)| p).sum(), 1.0);
+
+        let explode = distribution("d6!");
+        assert_eq!((explode.lower_bound(), explode.upper_bound()), (1, 66));
+        assert_close(explode.pmf(6), 0.0);
+        assert_close(explode.pmf(7), 1.0 / 36.0);
+        assert_close(explode.pmf(66), (1.0f64 / 6.0).powi(11));
+        assert_close(
+            explode.mean(),
+            (0..=10).map(|i| 3.5 / 6f64.powi(i)).sum::<f64>(),
+        );
+
+        assert_eq!(distribution("d6!!"), explode);
+        assert_eq!(distribution("2d6!"), distribution("2d6!!"));
+        assert_close(distribution("2d6!!kh1").iter().map(|(_, p)| p).sum(), 1.0);
+    }
+
+    #[test]
+    fn cannot_compute_some_distributions() {
+        assert!(matches!(
+            DiceSet::from_str("2d6!kh1").unwrap().distribution(),
+            Err(Error::NoExactDistribution(_))
+        ));
+        assert!(matches!(
+            DiceSet::from_str("1000d100").unwrap().distribution(),
+            Err(Error::WayTooManyDices)
+        ));
+        assert!(matches!(
+            DiceSet::from_str("1001d6").unwrap().distribution(),
+            Err(Error::WayTooManyDices)
+        ));
+        assert!(
+            DiceSet::new([Dice::D20; 2].into_iter())
+                .distribution()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn can_compute_distribution_of_special_dices() {
+        let d7 = distribution("2d7");
+        assert_eq!((d7.lower_bound(), d7.upper_bound()), (2, 14));
+        assert_close(d7.mean(), 8.0);
+
+        let fudge = distribution("4dF");
+        assert_eq!((fudge.lower_bound(), fudge.upper_bound()), (-4, 4));
+        assert_close(fudge.mean(), 0.0);
+        assert_close(fudge.pmf(4), 1.0 / 81.0);
+        assert_close(fudge.pmf(0), 19.0 / 81.0);
+        assert_close(distribution("4dFkh1").pmf(-1), 1.0 / 81.0);
+
+        let percentile = distribution("d00 + d10");
+        assert_eq!(
+            (percentile.lower_bound(), percentile.upper_bound()),
+            (1, 100)
+        );
+        for v in 1..=100 {
+            assert_close(percentile.pmf(v), 0.01);
+        }
+        assert_close(distribution("d00").pmf(5), 0.0);
+    }
+}
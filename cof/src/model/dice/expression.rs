@@ -0,0 +1,288 @@
+use std::fmt::Display;
+
+#[cfg(doc)]
+use super::{DiceTerm, RolledDiceTerm};
+
+/// An `Expression` is the abstract syntax tree of a dice notation such as `2d6 + 3` or
+/// `(d8 + 2) * 2`.
+///
+/// The tree is generic over its leaves so that the very same structure holds either the
+/// [`DiceTerm`]s that are meant to be rolled, or the [`RolledDiceTerm`]s once they have
+/// been rolled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression<T> {
+    /// A flat modifier, such as the `3` in `d20 + 3`.
+    Constant(i64),
+    /// A group of dices, such as the `2d6` in `2d6 + 3`.
+    Dices(T),
+    /// The opposite of the inner expression.
+    Neg(Box<Expression<T>>),
+    /// The sum of both expressions.
+    Add(Box<Expression<T>>, Box<Expression<T>>),
+    /// The difference between both expressions.
+    Sub(Box<Expression<T>>, Box<Expression<T>>),
+    /// The product of both expressions.
+    Mul(Box<Expression<T>>, Box<Expression<T>>),
+}
+
+impl<T> Expression<T> {
+    /// `leaves` returns all the leaves of the expression, from left to right.
+    pub fn leaves(&self) -> impl Iterator<Item = &T> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        leaves.into_iter()
+    }
+
+    fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a T>) {
+        match self {
+            Self::Constant(_) => {}
+            Self::Dices(leaf) => leaves.push(leaf),
+            Self::Neg(e) => e.collect_leaves(leaves),
+            Self::Add(l, r) | Self::Sub(l, r) | Self::Mul(l, r) => {
+                l.collect_leaves(leaves);
+                r.collect_leaves(leaves);
+            }
+        }
+    }
+
+    /// Builds a new expression with the same structure by transforming every leaf, from
+    /// left to right, with the given function.
+    ///
+    /// # Errors
+    /// The first error returned by `f` is forwarded.
+    pub fn try_map<U, E>(
+        &self,
+        f: &mut impl FnMut(&T) -> Result<U, E>,
+    ) -> Result<Expression<U>, E> {
+        Ok(match self {
+            Self::Constant(c) => Expression::Constant(*c),
+            Self::Dices(leaf) => Expression::Dices(f(leaf)?),
+            Self::Neg(e) => Expression::Neg(Box::new(e.try_map(f)?)),
+            Self::Add(l, r) => Expression::Add(Box::new(l.try_map(f)?), Box::new(r.try_map(f)?)),
+            Self::Sub(l, r) => Expression::Sub(Box::new(l.try_map(f)?), Box::new(r.try_map(f)?)),
+            Self::Mul(l, r) => Expression::Mul(Box::new(l.try_map(f)?), Box::new(r.try_map(f)?)),
+        })
+    }
+
+    /// Builds a new expression with the same structure by transforming every leaf, from
+    /// left to right, with the given function.
+    pub fn map<U>(&self, f: &mut impl FnMut(&T) -> U) -> Expression<U> {
+        match self.try_map(&mut |leaf| Ok::<U, std::convert::Infallible>(f(leaf))) {
+            Ok(expression) => expression,
+        }
+    }
+
+    /// Builds the sum of all the given expressions, the sum of nothing being `0`.
+    pub fn sum(expressions: impl Iterator<Item = Expression<T>>) -> Self {
+        expressions
+            .reduce(|acc, e| Self::Add(Box::new(acc), Box::new(e)))
+            .unwrap_or(Self::Constant(0))
+    }
+
+    /// Computes the value of the expression given the value of each leaf.
+    ///
+    /// The operations saturate instead of overflowing, callers that care about overflows
+    /// should check [`Expression::bounds`] first.
+    pub fn evaluate(&self, leaf_value: &impl Fn(&T) -> i64) -> i64 {
+        match self {
+            Self::Constant(c) => *c,
+            Self::Dices(leaf) => leaf_value(leaf),
+            Self::Neg(e) => e.evaluate(leaf_value).saturating_neg(),
+            Self::Add(l, r) => l
+                .evaluate(leaf_value)
+                .saturating_add(r.evaluate(leaf_value)),
+            Self::Sub(l, r) => l
+                .evaluate(leaf_value)
+                .saturating_sub(r.evaluate(leaf_value)),
+            Self::Mul(l, r) => l
+                .evaluate(leaf_value)
+                .saturating_mul(r.evaluate(leaf_value)),
+        }
+    }
+
+    /// Computes the lowest and the highest possible values of the expression given the
+    /// bounds of each leaf.
+    ///
+    /// Returns `None` when one of the bounds cannot be represented as an [`i64`].
+    pub fn bounds(&self, leaf_bounds: &impl Fn(&T) -> Option<(i64, i64)>) -> Option<(i64, i64)> {
+        match self {
+            Self::Constant(c) => Some((*c, *c)),
+            Self::Dices(leaf) => leaf_bounds(leaf),
+            Self::Neg(e) => {
+                let (low, high) = e.bounds(leaf_bounds)?;
+                Some((high.checked_neg()?, low.checked_neg()?))
+            }
+            Self::Add(l, r) => {
+                let ((l_low, l_high), (r_low, r_high)) =
+                    (l.bounds(leaf_bounds)?, r.bounds(leaf_bounds)?);
+                Some((l_low.checked_add(r_low)?, l_high.checked_add(r_high)?))
+            }
+            Self::Sub(l, r) => {
+                let ((l_low, l_high), (r_low, r_high)) =
+                    (l.bounds(leaf_bounds)?, r.bounds(leaf_bounds)?);
+                Some((l_low.checked_sub(r_high)?, l_high.checked_sub(r_low)?))
+            }
+            Self::Mul(l, r) => {
+                let ((l_low, l_high), (r_low, r_high)) =
+                    (l.bounds(leaf_bounds)?, r.bounds(leaf_bounds)?);
+                let products = [
+                    l_low.checked_mul(r_low)?,
+                    l_low.checked_mul(r_high)?,
+                    l_high.checked_mul(r_low)?,
+                    l_high.checked_mul(r_high)?,
+                ];
+                Some((*products.iter().min()?, *products.iter().max()?))
+            }
+        }
+    }
+
+    /// Binding strength of the root operator, used to only print the required parenthesis.
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Add(_, _) | Self::Sub(_, _) => 1,
+            Self::Mul(_, _) => 2,
+            Self::Neg(_) => 3,
+            Self::Constant(_) | Self::Dices(_) => 4,
+        }
+    }
+}
+
+impl<T: Display> Expression<T> {
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>, min_precedence: u8) -> std::fmt::Result {
+        if self.precedence() < min_precedence {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+impl<T: Display> Display for Expression<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Constant(c) => write!(f, "{c}"),
+            Self::Dices(leaf) => write!(f, "{leaf}"),
+            Self::Neg(e) => {
+                write!(f, "-")?;
+                e.fmt_operand(f, 3)
+            }
+            Self::Add(l, r) => {
+                l.fmt_operand(f, 1)?;
+                write!(f, " + ")?;
+                r.fmt_operand(f, 2)
+            }
+            Self::Sub(l, r) => {
+                l.fmt_operand(f, 1)?;
+                write!(f, " - ")?;
+                r.fmt_operand(f, 2)
+            }
+            Self::Mul(l, r) => {
+                l.fmt_operand(f, 2)?;
+                write!(f, " * ")?;
+                r.fmt_operand(f, 3)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::dice::{Dice, DiceTerm};
+
+    #[allow(clippy::unnecessary_box_returns)]
+    fn d(count: u32, dice: Dice) -> Box<Expression<DiceTerm>> {
+        Box::new(Expression::Dices(DiceTerm::new(count, dice)))
+    }
+
+    #[allow(clippy::unnecessary_box_returns)]
+    fn c(value: i64) -> Box<Expression<DiceTerm>> {
+        Box::new(Expression::Constant(value))
+    }
+
+    #[test]
+    fn can_compute_expression_bounds() {
+        let test_cases = &[
+            (Expression::Add(d(1, Dice::D20), c(3)), (4, 23)),
+            (Expression::Sub(d(1, Dice::D20), c(2)), (-1, 18)),
+            (Expression::Sub(c(2), d(2, Dice::D6)), (-10, 0)),
+            (Expression::Mul(d(2, Dice::D6), c(2)), (4, 24)),
+            (Expression::Mul(d(1, Dice::D4), c(-2)), (-8, -2)),
+            (Expression::Neg(d(1, Dice::D4)), (-4, -1)),
+            (
+                Expression::Mul(
+                    Box::new(Expression::Sub(d(1, Dice::D4), c(3))),
+                    d(1, Dice::D6),
+                ),
+                (-12, 6),
+            ),
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.0.bounds(&|t: &DiceTerm| t.bounds()),
+                Some(tc.1),
+                "bounds of {}",
+                tc.0
+            );
+        }
+
+        let overflowing = Expression::Mul(c(i64::MAX), d(1, Dice::D4));
+        assert_eq!(overflowing.bounds(&|t: &DiceTerm| t.bounds()), None);
+    }
+
+    #[test]
+    fn can_evaluate_expression() {
+        let expr = Expression::Mul(
+            Box::new(Expression::Sub(d(2, Dice::D6), c(3))),
+            Box::new(Expression::Neg(c(2))),
+        );
+        assert_eq!(expr.evaluate(&|t: &DiceTerm| i64::from(t.count)), 2);
+    }
+
+    #[test]
+    fn can_list_leaves_from_left_to_right() {
+        let expr = Expression::Add(
+            Box::new(Expression::Mul(d(1, Dice::D8), d(2, Dice::D4))),
+            Box::new(Expression::Neg(d(3, Dice::D6))),
+        );
+        let leaves = expr.leaves().copied().collect::<Vec<_>>();
+        assert_eq!(
+            leaves,
+            vec![
+                DiceTerm::new(1, Dice::D8),
+                DiceTerm::new(2, Dice::D4),
+                DiceTerm::new(3, Dice::D6)
+            ]
+        );
+    }
+
+    #[test]
+    fn can_display_expression_with_minimal_parenthesis() {
+        let test_cases = &[
+            (Expression::Add(d(1, Dice::D20), c(3)), "d20 + 3"),
+            (Expression::Sub(d(1, Dice::D20), c(2)), "d20 - 2"),
+            (
+                Expression::Mul(Box::new(Expression::Add(d(1, Dice::D8), c(2))), c(2)),
+                "(d8 + 2) * 2",
+            ),
+            (
+                Expression::Sub(c(10), Box::new(Expression::Sub(d(1, Dice::D6), c(1)))),
+                "10 - (d6 - 1)",
+            ),
+            (
+                Expression::Add(Box::new(Expression::Mul(c(2), d(3, Dice::D6))), c(1)),
+                "2 * 3d6 + 1",
+            ),
+            (Expression::Neg(d(1, Dice::D4)), "-d4"),
+            (
+                Expression::Neg(Box::new(Expression::Add(d(1, Dice::D4), c(1)))),
+                "-(d4 + 1)",
+            ),
+        ];
+
+        for tc in test_cases {
+            assert_eq!(tc.0.to_string(), tc.1);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use super::{Dice, Error};
+
+/// The lowest number of rolls expected on each face for the chi-squared test to be meaningful,
+/// the approximation of its statistic by the chi-squared distribution being rough below.
+pub const MIN_EXPECTED_FACE_COUNT: f64 = 5.0;
+
+/// The maximum number of iterations of the series and of the continued fraction computing the
+/// incomplete gamma function.
+const MAX_GAMMA_ITERATIONS: usize = 1000;
+
+/// The relative precision at which the incomplete gamma function is computed.
+const GAMMA_EPSILON: f64 = 1e-14;
+
+/// `FaceCounts` counts how many times each face of a [`Dice`] has been rolled, so that the
+/// fairness of the dice can be assessed with a chi-squared goodness-of-fit test against the
+/// uniform distribution of its faces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaceCounts {
+    dice: Dice,
+    counts: Vec<u64>,
+}
+
+impl FaceCounts {
+    /// Creates the counts of a dice that has not been rolled yet.
+    #[must_use]
+    pub fn new(dice: Dice) -> Self {
+        Self {
+            dice,
+            counts: vec![0; dice.side_count() as usize],
+        }
+    }
+
+    /// Counts a roll of the dice, `result` being the index of the face rolled between 1 and the
+    /// number of sides of the dice.
+    ///
+    /// # Errors
+    /// [`Error::FaceUnknown`] is returned when the dice has no such face.
+    pub fn add(&mut self, result: u32) -> Result<(), Error> {
+        let count = result
+            .checked_sub(1)
+            .and_then(|face| self.counts.get_mut(face as usize))
+            .ok_or(Error::FaceUnknown(self.dice, result))?;
+        *count += 1;
+        Ok(())
+    }
+
+    /// `dice` returns the dice whose faces are counted.
+    #[must_use]
+    pub fn dice(&self) -> Dice {
+        self.dice
+    }
+
+    /// `counts` returns the number of rolls of each face, from the first one.
+    #[must_use]
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// `total` returns the number of rolls of the dice.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// `is_significant` tells whether the dice has been rolled enough for the chi-squared test
+    /// to be meaningful, that is [`MIN_EXPECTED_FACE_COUNT`] times per face on average.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn is_significant(&self) -> bool {
+        self.total() as f64 >= MIN_EXPECTED_FACE_COUNT * self.counts.len() as f64
+    }
+
+    /// `chi_squared` returns the statistic of Pearson's chi-squared test of the counts against
+    /// a fair dice, 0 when the dice has not been rolled.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn chi_squared(&self) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        let expected = total as f64 / self.counts.len() as f64;
+        self.counts
+            .iter()
+            .map(|count| {
+                let deviation = *count as f64 - expected;
+                deviation * deviation / expected
+            })
+            .sum()
+    }
+
+    /// `p_value` returns the probability for a fair dice to deviate at least as much as the
+    /// counts from the uniform distribution: the lower it is, the more the dice looks biased.
+    #[must_use]
+    pub fn p_value(&self) -> f64 {
+        let degrees_of_freedom = self.dice.side_count() - 1;
+        chi_squared_survival(self.chi_squared(), degrees_of_freedom)
+    }
+}
+
+/// Returns the probability for a chi-squared distributed variable with the given degrees of
+/// freedom to be at least `statistic`.
+#[must_use]
+pub fn chi_squared_survival(statistic: f64, degrees_of_freedom: u32) -> f64 {
+    if statistic <= 0.0 {
+        return 1.0;
+    }
+    regularized_upper_gamma(f64::from(degrees_of_freedom) / 2.0, statistic / 2.0)
+}
+
+/// Computes the regularized upper incomplete gamma function `Q(shape, point)`, by its series
+/// when `point` is below `shape + 1` and by its continued fraction otherwise, both converging
+/// quickly there.
+fn regularized_upper_gamma(shape: f64, point: f64) -> f64 {
+    // the common factor point^shape e^-point / Γ(shape) of both expansions
+    let prefactor = (shape * point.ln() - point - ln_gamma(shape)).exp();
+    if point < shape + 1.0 {
+        let mut term = 1.0 / shape;
+        let mut sum = term;
+        let mut denominator = shape;
+        for _ in 0..MAX_GAMMA_ITERATIONS {
+            denominator += 1.0;
+            term *= point / denominator;
+            sum += term;
+            if term.abs() < sum.abs() * GAMMA_EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * prefactor).clamp(0.0, 1.0)
+    } else {
+        // modified Lentz's method
+        let tiny = f64::MIN_POSITIVE / GAMMA_EPSILON;
+        let mut b_n = point + 1.0 - shape;
+        let mut c_n = 1.0 / tiny;
+        let mut d_n = 1.0 / b_n;
+        let mut fraction = d_n;
+        for n in 1..MAX_GAMMA_ITERATIONS {
+            let n = f64::from(u32::try_from(n).unwrap_or(u32::MAX));
+            let a_n = -n * (n - shape);
+            b_n += 2.0;
+            d_n = a_n * d_n + b_n;
+            if d_n.abs() < tiny {
+                d_n = tiny;
+            }
+            c_n = b_n + a_n / c_n;
+            if c_n.abs() < tiny {
+                c_n = tiny;
+            }
+            d_n = 1.0 / d_n;
+            let delta = d_n * c_n;
+            fraction *= delta;
+            if (delta - 1.0).abs() < GAMMA_EPSILON {
+                break;
+            }
+        }
+        (fraction * prefactor).clamp(0.0, 1.0)
+    }
+}
+
+/// Computes the natural logarithm of the gamma function with the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000_000_000_190_015;
+    let mut y = x;
+    for coefficient in COEFFICIENTS {
+        y += 1.0;
+        series += coefficient / y;
+    }
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn can_count_faces() {
+        let mut counts = FaceCounts::new(Dice::D4);
+        for result in [1, 4, 4, 2] {
+            counts.add(result).unwrap();
+        }
+        assert_eq!(counts.counts(), &[1, 1, 0, 2]);
+        assert_eq!(counts.total(), 4);
+        assert!(!counts.is_significant());
+
+        assert!(matches!(
+            counts.add(0),
+            Err(Error::FaceUnknown(Dice::D4, 0))
+        ));
+        assert!(matches!(
+            counts.add(5),
+            Err(Error::FaceUnknown(Dice::D4, 5))
+        ));
+    }
+
+    #[test]
+    fn can_compute_chi_squared_survival() {
+        // with 2 degrees of freedom the survival function is exp(-x / 2)
+        for statistic in [0.5_f64, 2.0, 5.991, 13.8] {
+            assert_close(chi_squared_survival(statistic, 2), (-statistic / 2.0).exp());
+        }
+        // the critical values of the usual significance levels
+        assert_close(chi_squared_survival(3.841_458_820_694_124, 1), 0.05);
+        assert_close(chi_squared_survival(30.143_527_205_646_16, 19), 0.05);
+        assert_close(chi_squared_survival(36.190_869_129_270_9, 19), 0.01);
+        assert_close(chi_squared_survival(0.0, 19), 1.0);
+    }
+
+    #[test]
+    fn can_test_fairness() {
+        let mut fair = FaceCounts::new(Dice::D6);
+        for result in (1..=6).cycle().take(600) {
+            fair.add(result).unwrap();
+        }
+        assert!(fair.is_significant());
+        assert_close(fair.chi_squared(), 0.0);
+        assert_close(fair.p_value(), 1.0);
+
+        let mut loaded = FaceCounts::new(Dice::D6);
+        for result in (1..=6).cycle().take(600).chain([6; 100]) {
+            loaded.add(result).unwrap();
+        }
+        assert!(loaded.chi_squared() > 50.0);
+        assert!(loaded.p_value() < 1e-6);
+
+        let unrolled = FaceCounts::new(Dice::D20);
+        assert_close(unrolled.chi_squared(), 0.0);
+        assert_close(unrolled.p_value(), 1.0);
+    }
+}
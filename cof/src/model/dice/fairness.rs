@@ -0,0 +1,167 @@
+use rand::{RngCore, TryRngCore, rngs::OsRng};
+use sha2::{Digest, Sha256};
+
+use super::{ChaChaRoller, Error, RolledDiceSet};
+
+/// The maximum length in bytes of the seed a client can mix into a fair roll.
+pub const MAX_CLIENT_SEED_LEN: usize = 256;
+
+/// Draws a new server seed from the generator of the operating system.
+#[must_use]
+pub fn generate_server_seed() -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    OsRng.unwrap_err().fill_bytes(&mut seed);
+    seed
+}
+
+/// Returns the commitment to the given server seed, its SHA-256 hash. The commitment is
+/// disclosed before the roll while the seed is only revealed afterwards.
+#[must_use]
+pub fn commit(server_seed: &[u8; 32]) -> [u8; 32] {
+    Sha256::digest(server_seed).into()
+}
+
+/// A `FairnessProof` holds everything needed to recompute a provably fair roll: the server
+/// seed, committed before the roll, and the seed provided by the client.
+///
+/// The dices are rolled by a [`ChaChaRoller`] seeded with the SHA-256 hash of both seeds, so
+/// that neither the server nor the client can choose the outcome alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairnessProof {
+    server_seed: [u8; 32],
+    client_seed: Vec<u8>,
+}
+
+impl FairnessProof {
+    /// Creates the proof of a roll mixing the given seeds.
+    ///
+    /// # Errors
+    /// [`Error::ClientSeedTooLong`] is returned when the client seed is longer than
+    /// [`MAX_CLIENT_SEED_LEN`] bytes.
+    pub fn new(server_seed: [u8; 32], client_seed: Vec<u8>) -> Result<Self, Error> {
+        if client_seed.len() > MAX_CLIENT_SEED_LEN {
+            return Err(Error::ClientSeedTooLong);
+        }
+        Ok(Self {
+            server_seed,
+            client_seed,
+        })
+    }
+
+    /// `server_seed` returns the seed of the server, revealed once the roll is made.
+    #[must_use]
+    pub fn server_seed(&self) -> &[u8; 32] {
+        &self.server_seed
+    }
+
+    /// `client_seed` returns the seed provided by the client.
+    #[must_use]
+    pub fn client_seed(&self) -> &[u8] {
+        &self.client_seed
+    }
+
+    /// `commitment` returns the commitment to the server seed disclosed before the roll.
+    #[must_use]
+    pub fn commitment(&self) -> [u8; 32] {
+        commit(&self.server_seed)
+    }
+
+    /// Returns the [`ChaChaRoller`] the roll is made with.
+    #[must_use]
+    pub fn roller(&self) -> ChaChaRoller {
+        let seed = Sha256::new()
+            .chain_update(self.server_seed)
+            .chain_update(&self.client_seed)
+            .finalize();
+        ChaChaRoller::from_seed(seed.into())
+    }
+}
+
+/// Verifies offline that `rolled_dice_set` is the outcome of a fair roll: the revealed server
+/// seed must match the `commitment` disclosed before the roll, and rolling the dice set again
+/// with the seeds of the `proof` must give the very same dices.
+///
+/// # Errors
+/// - [`Error::CommitmentMismatch`] is returned when the server seed does not match the
+///   commitment.
+/// - [`Error::RolledDicesMismatch`] is returned when the dices are not the ones given by the
+///   seeds.
+pub fn verify_fair_roll(
+    rolled_dice_set: &RolledDiceSet,
+    commitment: &[u8; 32],
+    proof: &FairnessProof,
+) -> Result<(), Error> {
+    if proof.commitment() != *commitment {
+        return Err(Error::CommitmentMismatch);
+    }
+
+    let rerolled = rolled_dice_set.dice_set().roll_with(&mut proof.roller())?;
+    if rerolled != *rolled_dice_set {
+        return Err(Error::RolledDicesMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::model::dice::{DiceSet, RolledDice};
+
+    #[test]
+    fn can_verify_fair_rolls() {
+        let server_seed = generate_server_seed();
+        let commitment = commit(&server_seed);
+
+        let proof = FairnessProof::new(server_seed, b"lucky charm".to_vec()).unwrap();
+        let rolled_dice_set = DiceSet::from_str("2d20kh1 + 3d6! + 2")
+            .unwrap()
+            .roll_with(&mut proof.roller())
+            .unwrap();
+
+        assert!(verify_fair_roll(&rolled_dice_set, &commitment, &proof).is_ok());
+    }
+
+    #[test]
+    fn cannot_verify_unfair_rolls() {
+        let server_seed = [3; 32];
+        let commitment = commit(&server_seed);
+        let proof = FairnessProof::new(server_seed, b"client".to_vec()).unwrap();
+        let rolled_dice_set = DiceSet::from_str("4d6")
+            .unwrap()
+            .roll_with(&mut proof.roller())
+            .unwrap();
+
+        let other_seed = FairnessProof::new([4; 32], b"client".to_vec()).unwrap();
+        assert!(matches!(
+            verify_fair_roll(&rolled_dice_set, &commitment, &other_seed),
+            Err(Error::CommitmentMismatch)
+        ));
+
+        let other_client = FairnessProof::new(server_seed, b"other".to_vec()).unwrap();
+        assert!(matches!(
+            verify_fair_roll(&rolled_dice_set, &commitment, &other_client),
+            Err(Error::RolledDicesMismatch)
+        ));
+
+        let fudged = RolledDiceSet::new(
+            rolled_dice_set
+                .iter()
+                .map(|rd| RolledDice::new(rd.dice(), rd.dice().side_count())),
+        );
+        assert!(matches!(
+            verify_fair_roll(&fudged, &commitment, &proof),
+            Err(Error::RolledDicesMismatch)
+        ));
+    }
+
+    #[test]
+    fn cannot_use_too_long_client_seeds() {
+        assert!(FairnessProof::new([0; 32], vec![0; MAX_CLIENT_SEED_LEN]).is_ok());
+        assert!(matches!(
+            FairnessProof::new([0; 32], vec![0; MAX_CLIENT_SEED_LEN + 1]),
+            Err(Error::ClientSeedTooLong)
+        ));
+    }
+}
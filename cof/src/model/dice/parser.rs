@@ -0,0 +1,590 @@
+//! Recursive descent parser of the dice notation.
+//!
+//! The grammar understood by the parser is the following one, where blanks are ignored
+//! between tokens but not inside of a dice term:
+//!
+//! ```text
+//! expression := product (('+' | '-') product)*
+//! product    := unary ('*' unary)*
+//! unary      := ('+' | '-') unary | primary
+//! primary    := NUMBER | dices | '(' expression ')'
+//! dices      := NUMBER? 'd' (NUMBER | 'F') modifier*
+//! modifier   := reroll | explosion | selection
+//! reroll     := ('r' | 'rr') NUMBER?
+//! explosion  := ('!' | '!!') NUMBER?
+//! selection  := ('k' | 'kh' | 'kl' | 'dh' | 'dl') NUMBER?
+//! ```
+//!
+//! Each kind of modifier can be given at most once per dice term, in any order.
+
+use super::{Dice, DiceTerm, Error, Explosion, Expression, Reroll, Selection};
+
+/// Maximum nesting level of parenthesis and unary operators, it protects the parser
+/// against stack exhaustion on purposely crafted inputs.
+const MAX_DEPTH: usize = 32;
+
+/// The reason why a dice notation cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseErrorKind {
+    #[error("unexpected character '{0}'")]
+    UnexpectedCharacter(char),
+
+    #[error("unexpected '{0}'")]
+    UnexpectedToken(String),
+
+    #[error("unexpected end of the expression")]
+    UnexpectedEnd,
+
+    #[error("number is too large")]
+    NumberTooLarge,
+
+    #[error("missing number of sides after 'd'")]
+    MissingSides,
+
+    #[error("dice {0} does not exist")]
+    UnknownDice(String),
+
+    #[error("at least one dice must be rolled")]
+    NoDices,
+
+    #[error("cannot keep or drop that many dices in {0}")]
+    InvalidSelection(String),
+
+    #[error("cannot reroll or explode the dices of {0}")]
+    InvalidModifier(String),
+
+    #[error("{0} modifier is given more than once")]
+    DuplicateModifier(String),
+
+    #[error("parenthesis is never closed")]
+    UnclosedParenthesis,
+
+    #[error("expression is too deeply nested")]
+    TooDeeplyNested,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Dices(DiceTerm),
+    Plus,
+    Minus,
+    Star,
+    LeftParenthesis,
+    RightParenthesis,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(n) => n.to_string(),
+            Token::Dices(term) => term.to_string(),
+            Token::Plus => "+".to_string(),
+            Token::Minus => "-".to_string(),
+            Token::Star => "*".to_string(),
+            Token::LeftParenthesis => "(".to_string(),
+            Token::RightParenthesis => ")".to_string(),
+        }
+    }
+}
+
+/// A modifier following the number of sides of a dice term.
+#[derive(Debug, Clone, Copy)]
+enum Modifier {
+    Reroll(Reroll),
+    Explosion(Explosion),
+    Selection(Selection),
+}
+
+impl Modifier {
+    fn kind(self) -> &'static str {
+        match self {
+            Modifier::Reroll(_) => "reroll",
+            Modifier::Explosion(_) => "explosion",
+            Modifier::Selection(_) => "keep or drop",
+        }
+    }
+}
+
+fn error(position: usize, kind: ParseErrorKind) -> Error {
+    Error::DiceSetParseError { position, kind }
+}
+
+/// Parses the given dice notation into an [`Expression`].
+///
+/// # Errors
+/// [`Error::DiceSetParseError`] is returned with the position (in characters) of the
+/// offending part of the input.
+pub(super) fn parse(input: &str) -> Result<Expression<DiceTerm>, Error> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let tokens = Lexer {
+        chars: &chars,
+        pos: 0,
+    }
+    .tokenize()?;
+
+    let mut parser = Parser {
+        tokens: &tokens,
+        index: 0,
+        end: chars.len(),
+    };
+    let expression = parser.parse_expression(0)?;
+
+    match parser.peek() {
+        None => Ok(expression),
+        Some((position, token)) => Err(error(
+            position,
+            ParseErrorKind::UnexpectedToken(token.describe()),
+        )),
+    }
+}
+
+struct Lexer<'a> {
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl Lexer<'_> {
+    fn tokenize(mut self) -> Result<Vec<(usize, Token)>, Error> {
+        let mut tokens = Vec::new();
+
+        while let Some(&c) = self.chars.get(self.pos) {
+            let start = self.pos;
+            let token = match c {
+                c if c.is_whitespace() => {
+                    self.pos += 1;
+                    continue;
+                }
+                '+' => Token::Plus,
+                '-' => Token::Minus,
+                '*' => Token::Star,
+                '(' => Token::LeftParenthesis,
+                ')' => Token::RightParenthesis,
+                'd' => Token::Dices(self.read_dices(start, None)?),
+                c if c.is_ascii_digit() => {
+                    let number = self.read_number()?;
+                    if self.chars.get(self.pos) == Some(&'d') {
+                        Token::Dices(self.read_dices(start, Some(number))?)
+                    } else {
+                        Token::Number(
+                            i64::try_from(number)
+                                .map_err(|_| error(start, ParseErrorKind::NumberTooLarge))?,
+                        )
+                    }
+                }
+                c => return Err(error(start, ParseErrorKind::UnexpectedCharacter(c))),
+            };
+
+            if !matches!(token, Token::Dices(_) | Token::Number(_)) {
+                self.pos += 1;
+            }
+            tokens.push((start, token));
+        }
+
+        Ok(tokens)
+    }
+
+    /// Reads a sequence of digits starting at the current position.
+    fn read_number(&mut self) -> Result<u64, Error> {
+        let start = self.pos;
+        let mut number = 0u64;
+        while let Some(digit) = self.chars.get(self.pos).and_then(|c| c.to_digit(10)) {
+            number = number
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(u64::from(digit)))
+                .ok_or_else(|| error(start, ParseErrorKind::NumberTooLarge))?;
+            self.pos += 1;
+        }
+        Ok(number)
+    }
+
+    /// Reads a dice term, the current position being the one of the `d`.
+    fn read_dices(&mut self, start: usize, count: Option<u64>) -> Result<DiceTerm, Error> {
+        let dice_position = self.pos;
+        self.pos += 1;
+
+        let sides_start = self.pos;
+        match self.chars.get(self.pos) {
+            Some('F') => self.pos += 1,
+            Some(c) if c.is_ascii_digit() => {
+                self.read_number()?;
+            }
+            _ => return Err(error(self.pos, ParseErrorKind::MissingSides)),
+        }
+        let notation = format!(
+            "d{}",
+            self.chars[sides_start..self.pos].iter().collect::<String>()
+        );
+        let dice = Dice::try_from(notation.as_str())
+            .map_err(|_| error(dice_position, ParseErrorKind::UnknownDice(notation)))?;
+
+        let count = match count.map(u32::try_from) {
+            None => 1,
+            Some(Ok(0)) => return Err(error(start, ParseErrorKind::NoDices)),
+            Some(Ok(count)) => count,
+            Some(Err(_)) => return Err(error(start, ParseErrorKind::NumberTooLarge)),
+        };
+        let mut term = DiceTerm::new(count, dice);
+
+        let mut seen_kinds = Vec::new();
+        while let Some((position, modifier)) = self.read_modifier(dice)? {
+            if seen_kinds.contains(&modifier.kind()) {
+                return Err(error(
+                    position,
+                    ParseErrorKind::DuplicateModifier(modifier.kind().to_string()),
+                ));
+            }
+            seen_kinds.push(modifier.kind());
+
+            term = match modifier {
+                Modifier::Reroll(reroll) => term.with_reroll(reroll),
+                Modifier::Explosion(explosion) => term.with_explosion(explosion),
+                Modifier::Selection(selection) => term.with_selection(selection),
+            }
+            .map_err(|e| match e {
+                Error::InvalidSelection(notation) => {
+                    error(start, ParseErrorKind::InvalidSelection(notation))
+                }
+                Error::InvalidModifier(notation) => {
+                    error(start, ParseErrorKind::InvalidModifier(notation))
+                }
+                e => e,
+            })?;
+        }
+
+        Ok(term)
+    }
+
+    /// Reads the optional modifier following a dice term along with its position. The number
+    /// of kept or dropped dices and the rerolled value default to 1 while the explosion
+    /// threshold defaults to the number of sides of the `dice`.
+    fn read_modifier(&mut self, dice: Dice) -> Result<Option<(usize, Modifier)>, Error> {
+        let start = self.pos;
+        let (length, default, modifier): (usize, u32, fn(u32) -> Modifier) =
+            match (self.chars.get(self.pos), self.chars.get(self.pos + 1)) {
+                (Some('k'), Some('h')) => {
+                    (2, 1, |n| Modifier::Selection(Selection::KeepHighest(n)))
+                }
+                (Some('k'), Some('l')) => (2, 1, |n| Modifier::Selection(Selection::KeepLowest(n))),
+                (Some('d'), Some('h')) => {
+                    (2, 1, |n| Modifier::Selection(Selection::DropHighest(n)))
+                }
+                (Some('d'), Some('l')) => (2, 1, |n| Modifier::Selection(Selection::DropLowest(n))),
+                (Some('k'), _) => (1, 1, |n| Modifier::Selection(Selection::KeepHighest(n))),
+                (Some('r'), Some('r')) => (2, 1, |n| Modifier::Reroll(Reroll::Until(n))),
+                (Some('r'), _) => (1, 1, |n| Modifier::Reroll(Reroll::Once(n))),
+                (Some('!'), Some('!')) => (2, dice.side_count(), |n| {
+                    Modifier::Explosion(Explosion::Compound(n))
+                }),
+                (Some('!'), _) => (1, dice.side_count(), |n| {
+                    Modifier::Explosion(Explosion::Explode(n))
+                }),
+                _ => return Ok(None),
+            };
+        self.pos += length;
+
+        let number_start = self.pos;
+        if !self.chars.get(self.pos).is_some_and(char::is_ascii_digit) {
+            return Ok(Some((start, modifier(default))));
+        }
+        let number = u32::try_from(self.read_number()?)
+            .map_err(|_| error(number_start, ParseErrorKind::NumberTooLarge))?;
+        Ok(Some((start, modifier(number))))
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    index: usize,
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<(usize, Token)> {
+        self.tokens.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), Error> {
+        let token = self
+            .peek()
+            .ok_or_else(|| error(self.end, ParseErrorKind::UnexpectedEnd))?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    fn parse_expression(&mut self, depth: usize) -> Result<Expression<DiceTerm>, Error> {
+        let mut lhs = self.parse_product(depth)?;
+        loop {
+            lhs = match self.peek() {
+                Some((_, Token::Plus)) => {
+                    self.index += 1;
+                    Expression::Add(Box::new(lhs), Box::new(self.parse_product(depth)?))
+                }
+                Some((_, Token::Minus)) => {
+                    self.index += 1;
+                    Expression::Sub(Box::new(lhs), Box::new(self.parse_product(depth)?))
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn parse_product(&mut self, depth: usize) -> Result<Expression<DiceTerm>, Error> {
+        let mut lhs = self.parse_unary(depth)?;
+        while let Some((_, Token::Star)) = self.peek() {
+            self.index += 1;
+            lhs = Expression::Mul(Box::new(lhs), Box::new(self.parse_unary(depth)?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self, depth: usize) -> Result<Expression<DiceTerm>, Error> {
+        match self.peek() {
+            Some((position, Token::Plus | Token::Minus)) if depth >= MAX_DEPTH => {
+                Err(error(position, ParseErrorKind::TooDeeplyNested))
+            }
+            Some((_, Token::Plus)) => {
+                self.index += 1;
+                self.parse_unary(depth + 1)
+            }
+            Some((_, Token::Minus)) => {
+                self.index += 1;
+                Ok(Expression::Neg(Box::new(self.parse_unary(depth + 1)?)))
+            }
+            _ => self.parse_primary(depth),
+        }
+    }
+
+    fn parse_primary(&mut self, depth: usize) -> Result<Expression<DiceTerm>, Error> {
+        match self.next()? {
+            (_, Token::Number(n)) => Ok(Expression::Constant(n)),
+            (_, Token::Dices(term)) => Ok(Expression::Dices(term)),
+            (position, Token::LeftParenthesis) if depth >= MAX_DEPTH => {
+                Err(error(position, ParseErrorKind::TooDeeplyNested))
+            }
+            (position, Token::LeftParenthesis) => {
+                let inner = self.parse_expression(depth + 1)?;
+                match self.peek() {
+                    Some((_, Token::RightParenthesis)) => {
+                        self.index += 1;
+                        Ok(inner)
+                    }
+                    _ => Err(error(position, ParseErrorKind::UnclosedParenthesis)),
+                }
+            }
+            (position, token) => Err(error(
+                position,
+                ParseErrorKind::UnexpectedToken(token.describe()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(clippy::unnecessary_box_returns)]
+    fn d(count: u32, dice: Dice) -> Box<Expression<DiceTerm>> {
+        Box::new(Expression::Dices(DiceTerm::new(count, dice)))
+    }
+
+    #[allow(clippy::unnecessary_box_returns)]
+    fn s(count: u32, dice: Dice, selection: Selection) -> Box<Expression<DiceTerm>> {
+        let term = DiceTerm::new(count, dice)
+            .with_selection(selection)
+            .unwrap();
+        Box::new(Expression::Dices(term))
+    }
+
+    #[allow(clippy::unnecessary_box_returns)]
+    fn c(value: i64) -> Box<Expression<DiceTerm>> {
+        Box::new(Expression::Constant(value))
+    }
+
+    #[test]
+    fn can_parse_dice_expressions() {
+        let test_cases = &[
+            ("d20", *d(1, Dice::D20)),
+            ("1d20", *d(1, Dice::D20)),
+            ("42", *c(42)),
+            ("1d20 + 3", Expression::Add(d(1, Dice::D20), c(3))),
+            ("1d20+3", Expression::Add(d(1, Dice::D20), c(3))),
+            ("1d20 - 2", Expression::Sub(d(1, Dice::D20), c(2))),
+            (
+                "2d6 + 1d4 - 1",
+                Expression::Sub(
+                    Box::new(Expression::Add(d(2, Dice::D6), d(1, Dice::D4))),
+                    c(1),
+                ),
+            ),
+            (
+                "2 * 3d6 + 1",
+                Expression::Add(Box::new(Expression::Mul(c(2), d(3, Dice::D6))), c(1)),
+            ),
+            (
+                "(d8 + 2) * 2",
+                Expression::Mul(Box::new(Expression::Add(d(1, Dice::D8), c(2))), c(2)),
+            ),
+            ("-d4", Expression::Neg(d(1, Dice::D4))),
+            ("+d4", *d(1, Dice::D4)),
+            (" ( ( d100 ) ) ", *d(1, Dice::D100)),
+            ("2d20kh1", *s(2, Dice::D20, Selection::KeepHighest(1))),
+            ("2d20k", *s(2, Dice::D20, Selection::KeepHighest(1))),
+            ("2d20kl1", *s(2, Dice::D20, Selection::KeepLowest(1))),
+            ("4d6dl1", *s(4, Dice::D6, Selection::DropLowest(1))),
+            ("4d6dh", *s(4, Dice::D6, Selection::DropHighest(1))),
+            (
+                "2d20kh1 + 5",
+                Expression::Add(s(2, Dice::D20, Selection::KeepHighest(1)), c(5)),
+            ),
+            ("2d7", *d(2, Dice::with_sides(7).unwrap())),
+            ("d2", *d(1, Dice::with_sides(2).unwrap())),
+            ("4dF", *d(4, Dice::Fudge)),
+            ("4dFdl1", *s(4, Dice::Fudge, Selection::DropLowest(1))),
+            (
+                "d00 + d10",
+                Expression::Add(d(1, Dice::PercentileTens), d(1, Dice::D10)),
+            ),
+        ];
+
+        for tc in test_cases {
+            let parsed = parse(tc.0);
+            assert_eq!(parsed.as_ref().ok(), Some(&tc.1), "parsing {}", tc.0);
+        }
+    }
+
+    #[test]
+    fn can_parse_rerolls_and_explosions() {
+        let d6 = DiceTerm::new(1, Dice::D6);
+        let test_cases = &[
+            ("d6!", d6.with_explosion(Explosion::Explode(6))),
+            ("d6!5", d6.with_explosion(Explosion::Explode(5))),
+            ("d6!!", d6.with_explosion(Explosion::Compound(6))),
+            ("d6r", d6.with_reroll(Reroll::Once(1))),
+            ("d6r2", d6.with_reroll(Reroll::Once(2))),
+            ("d6rr1", d6.with_reroll(Reroll::Until(1))),
+            (
+                "4d6r1!kh3",
+                DiceTerm::new(4, Dice::D6)
+                    .with_reroll(Reroll::Once(1))
+                    .and_then(|t| t.with_explosion(Explosion::Explode(6)))
+                    .and_then(|t| t.with_selection(Selection::KeepHighest(3))),
+            ),
+            (
+                "4d6kh3!r1",
+                DiceTerm::new(4, Dice::D6)
+                    .with_reroll(Reroll::Once(1))
+                    .and_then(|t| t.with_explosion(Explosion::Explode(6)))
+                    .and_then(|t| t.with_selection(Selection::KeepHighest(3))),
+            ),
+        ];
+
+        for tc in test_cases {
+            let expected = Expression::Dices(*tc.1.as_ref().unwrap());
+            let parsed = parse(tc.0);
+            assert_eq!(parsed.as_ref().ok(), Some(&expected), "parsing {}", tc.0);
+        }
+    }
+
+    #[test]
+    fn can_report_parse_error_positions() {
+        let test_cases = &[
+            ("", 0, ParseErrorKind::UnexpectedEnd),
+            ("d20 +", 5, ParseErrorKind::UnexpectedEnd),
+            ("D100", 0, ParseErrorKind::UnexpectedCharacter('D')),
+            ("1d20 + x", 7, ParseErrorKind::UnexpectedCharacter('x')),
+            ("2d1", 1, ParseErrorKind::UnknownDice("d1".to_string())),
+            (
+                "2d1001",
+                1,
+                ParseErrorKind::UnknownDice("d1001".to_string()),
+            ),
+            ("d07", 0, ParseErrorKind::UnknownDice("d07".to_string())),
+            ("1d20 + d", 8, ParseErrorKind::MissingSides),
+            ("0d6", 0, ParseErrorKind::NoDices),
+            (
+                "d6 + 2d20kh3",
+                5,
+                ParseErrorKind::InvalidSelection("2d20kh3".to_string()),
+            ),
+            (
+                "4d6dl4",
+                0,
+                ParseErrorKind::InvalidSelection("4d6dl4".to_string()),
+            ),
+            ("2d20kh1x", 7, ParseErrorKind::UnexpectedCharacter('x')),
+            (
+                "d6 + d6r6",
+                5,
+                ParseErrorKind::InvalidModifier("d6r6".to_string()),
+            ),
+            (
+                "d6!1",
+                0,
+                ParseErrorKind::InvalidModifier("d6!1".to_string()),
+            ),
+            (
+                "d6!r1!",
+                5,
+                ParseErrorKind::DuplicateModifier("explosion".to_string()),
+            ),
+            (
+                "2d20kh1kl1",
+                7,
+                ParseErrorKind::DuplicateModifier("keep or drop".to_string()),
+            ),
+            ("(d6 + 2", 0, ParseErrorKind::UnclosedParenthesis),
+            (
+                "d6 + 2)",
+                6,
+                ParseErrorKind::UnexpectedToken(")".to_string()),
+            ),
+            ("d6 3", 3, ParseErrorKind::UnexpectedToken("3".to_string())),
+            (
+                "d6 * * 2",
+                5,
+                ParseErrorKind::UnexpectedToken("*".to_string()),
+            ),
+            ("99999999999d6", 0, ParseErrorKind::NumberTooLarge),
+            (
+                "d6 + 99999999999999999999",
+                5,
+                ParseErrorKind::NumberTooLarge,
+            ),
+        ];
+
+        for tc in test_cases {
+            let parsed = parse(tc.0);
+            assert!(
+                matches!(
+                    &parsed,
+                    Err(Error::DiceSetParseError { position, kind }) if *position == tc.1 && *kind == tc.2
+                ),
+                "parsing {:?} returned {parsed:?}",
+                tc.0
+            );
+        }
+    }
+
+    #[test]
+    fn cannot_parse_too_deeply_nested_expressions() {
+        let nested = format!("{}d6{}", "(".repeat(100), ")".repeat(100));
+        assert!(matches!(
+            parse(&nested),
+            Err(Error::DiceSetParseError {
+                kind: ParseErrorKind::TooDeeplyNested,
+                ..
+            })
+        ));
+
+        let negated = format!("{}d6", "-".repeat(100));
+        assert!(matches!(
+            parse(&negated),
+            Err(Error::DiceSetParseError {
+                kind: ParseErrorKind::TooDeeplyNested,
+                ..
+            })
+        ));
+    }
+}
@@ -6,19 +6,24 @@
 //! model into protobuf message and eventually protobuf messages into structs from the model.
 
 use super::pb;
-use super::{Dice, DiceSet, RolledDice, RolledDiceSet};
+use super::{Dice, DiceSet, RollOrigin, RolledDice, RolledDiceSet};
 
-impl From<Dice> for pb::common::dice::v1::DiceType {
-    fn from(value: Dice) -> Self {
+impl TryFrom<Dice> for pb::common::dice::v1::DiceType {
+    type Error = super::Error;
+
+    fn try_from(value: Dice) -> Result<Self, Self::Error> {
         match value {
-            Dice::D3 => Self::DiceType3,
-            Dice::D4 => Self::DiceType4,
-            Dice::D6 => Self::DiceType6,
-            Dice::D8 => Self::DiceType8,
-            Dice::D10 => Self::DiceType10,
-            Dice::D12 => Self::DiceType12,
-            Dice::D20 => Self::DiceType20,
-            Dice::D100 => Self::DiceType100,
+            Dice::D3 => Ok(Self::DiceType3),
+            Dice::D4 => Ok(Self::DiceType4),
+            Dice::D6 => Ok(Self::DiceType6),
+            Dice::D8 => Ok(Self::DiceType8),
+            Dice::D10 => Ok(Self::DiceType10),
+            Dice::D12 => Ok(Self::DiceType12),
+            Dice::D20 => Ok(Self::DiceType20),
+            Dice::D100 => Ok(Self::DiceType100),
+            Dice::Custom(_) | Dice::Fudge | Dice::PercentileTens => {
+                Err(Self::Error::NotAProtoDiceType(value.to_string()))
+            }
         }
     }
 }
@@ -41,12 +46,45 @@ impl TryFrom<pb::common::dice::v1::DiceType> for Dice {
     }
 }
 
-impl From<DiceSet> for Vec<pb::common::dice::v1::DiceType> {
-    fn from(value: DiceSet) -> Self {
+impl From<Dice> for pb::common::dice::v1::rolled_dice::Uncommon {
+    fn from(value: Dice) -> Self {
+        match value {
+            Dice::Fudge => Self::Special(pb::common::dice::v1::SpecialDice::Fudge as i32),
+            Dice::PercentileTens => {
+                Self::Special(pb::common::dice::v1::SpecialDice::PercentileTens as i32)
+            }
+            dice => Self::Sides(dice.side_count()),
+        }
+    }
+}
+
+impl TryFrom<pb::common::dice::v1::rolled_dice::Uncommon> for Dice {
+    type Error = super::Error;
+
+    fn try_from(value: pb::common::dice::v1::rolled_dice::Uncommon) -> Result<Self, Self::Error> {
+        match value {
+            pb::common::dice::v1::rolled_dice::Uncommon::Sides(sides) => Dice::with_sides(sides),
+            pb::common::dice::v1::rolled_dice::Uncommon::Special(special) => {
+                match pb::common::dice::v1::SpecialDice::try_from(special)? {
+                    pb::common::dice::v1::SpecialDice::Fudge => Ok(Dice::Fudge),
+                    pb::common::dice::v1::SpecialDice::PercentileTens => Ok(Dice::PercentileTens),
+                    pb::common::dice::v1::SpecialDice::Unspecified => {
+                        Err(Self::Error::UnspecifiedProtoEnum)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<DiceSet> for Vec<pb::common::dice::v1::DiceType> {
+    type Error = super::Error;
+
+    fn try_from(value: DiceSet) -> Result<Self, Self::Error> {
         value
-            .0
-            .into_iter()
-            .map(pb::common::dice::v1::DiceType::from)
+            .iter()
+            .copied()
+            .map(pb::common::dice::v1::DiceType::try_from)
             .collect()
     }
 }
@@ -60,15 +98,49 @@ impl TryFrom<Vec<pb::common::dice::v1::DiceType>> for DiceSet {
             .map(Dice::try_from)
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Self(encoded_dices))
+        Ok(Self::new(encoded_dices.into_iter()))
+    }
+}
+
+impl From<RollOrigin> for pb::common::dice::v1::RollOrigin {
+    fn from(value: RollOrigin) -> Self {
+        match value {
+            RollOrigin::Initial => Self::Initial,
+            RollOrigin::Reroll => Self::Reroll,
+            RollOrigin::Explosion => Self::Explosion,
+            RollOrigin::Compound => Self::Compound,
+        }
+    }
+}
+
+impl From<pb::common::dice::v1::RollOrigin> for RollOrigin {
+    fn from(value: pb::common::dice::v1::RollOrigin) -> Self {
+        match value {
+            pb::common::dice::v1::RollOrigin::Unspecified
+            | pb::common::dice::v1::RollOrigin::Initial => Self::Initial,
+            pb::common::dice::v1::RollOrigin::Reroll => Self::Reroll,
+            pb::common::dice::v1::RollOrigin::Explosion => Self::Explosion,
+            pb::common::dice::v1::RollOrigin::Compound => Self::Compound,
+        }
     }
 }
 
 impl From<RolledDice> for pb::common::dice::v1::RolledDice {
     fn from(value: RolledDice) -> Self {
+        // the common dices are still given as a dice type, the only one older clients know
+        let (dice, uncommon) = match pb::common::dice::v1::DiceType::try_from(value.dice) {
+            Ok(dice_type) => (dice_type, None),
+            Err(_) => (
+                pb::common::dice::v1::DiceType::Unspecified,
+                Some(value.dice.into()),
+            ),
+        };
         Self {
-            dice: pb::common::dice::v1::DiceType::from(value.dice) as i32,
+            dice: dice as i32,
+            uncommon,
             result: value.result,
+            discarded: value.discarded,
+            origin: pb::common::dice::v1::RollOrigin::from(value.origin) as i32,
         }
     }
 }
@@ -77,10 +149,17 @@ impl TryFrom<pb::common::dice::v1::RolledDice> for RolledDice {
     type Error = super::Error;
 
     fn try_from(value: pb::common::dice::v1::RolledDice) -> Result<Self, Self::Error> {
-        let dice = Dice::try_from(value.dice())?;
+        let dice = match (value.dice(), value.uncommon) {
+            (pb::common::dice::v1::DiceType::Unspecified, Some(uncommon)) => {
+                Dice::try_from(uncommon)?
+            }
+            (dice_type, _) => Dice::try_from(dice_type)?,
+        };
         Ok(Self {
             dice,
             result: value.result,
+            discarded: value.discarded,
+            origin: RollOrigin::from(value.origin()),
         })
     }
 }
@@ -88,8 +167,8 @@ impl TryFrom<pb::common::dice::v1::RolledDice> for RolledDice {
 impl From<RolledDiceSet> for Vec<pb::common::dice::v1::RolledDice> {
     fn from(value: RolledDiceSet) -> Self {
         value
-            .0
-            .into_iter()
+            .iter()
+            .copied()
             .map(pb::common::dice::v1::RolledDice::from)
             .collect()
     }
@@ -104,7 +183,7 @@ impl TryFrom<Vec<pb::common::dice::v1::RolledDice>> for RolledDiceSet {
             .map(RolledDice::try_from)
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Self(encoded_rolled_dices))
+        Ok(Self::new(encoded_rolled_dices.into_iter()))
     }
 }
 
@@ -126,9 +205,20 @@ mod tests {
         ];
 
         for tc in test_cases {
-            let proto_dice = pb::common::dice::v1::DiceType::from(tc.0);
+            let proto_dice = pb::common::dice::v1::DiceType::try_from(tc.0).unwrap();
             assert_eq!(proto_dice, tc.1);
         }
+
+        for dice in [
+            Dice::with_sides(7).unwrap(),
+            Dice::Fudge,
+            Dice::PercentileTens,
+        ] {
+            assert!(matches!(
+                pb::common::dice::v1::DiceType::try_from(dice),
+                Err(crate::model::dice::Error::NotAProtoDiceType(_))
+            ));
+        }
     }
 
     #[test]
@@ -164,19 +254,100 @@ mod tests {
             proto_rolled_dice.dice(),
             pb::common::dice::v1::DiceType::DiceType100
         );
+        assert_eq!(proto_rolled_dice.uncommon, None);
         assert_eq!(proto_rolled_dice.result, rolled_dice.result);
+        assert!(!proto_rolled_dice.discarded);
+
+        let discarded_dice = RolledDice::new_discarded(Dice::D20, 3);
+        let proto_discarded_dice = pb::common::dice::v1::RolledDice::from(discarded_dice);
+        assert!(proto_discarded_dice.discarded);
+
+        let exploded_dice = RolledDice::new(Dice::D6, 4).with_origin(RollOrigin::Explosion);
+        let proto_exploded_dice = pb::common::dice::v1::RolledDice::from(exploded_dice);
+        assert_eq!(
+            proto_exploded_dice.origin(),
+            pb::common::dice::v1::RollOrigin::Explosion
+        );
     }
 
     #[test]
     fn can_decode_rolled_dice_protobuf() {
         let proto_rolled_dice = pb::common::dice::v1::RolledDice {
             dice: pb::common::dice::v1::DiceType::DiceType20 as i32,
+            uncommon: None,
             result: 19u32,
+            discarded: true,
+            origin: pb::common::dice::v1::RollOrigin::Reroll as i32,
         };
 
         let decoded_rolled_dice = RolledDice::try_from(proto_rolled_dice).unwrap();
 
         assert_eq!(decoded_rolled_dice.dice, Dice::D20);
         assert_eq!(decoded_rolled_dice.result, 19);
+        assert!(decoded_rolled_dice.discarded);
+        assert_eq!(decoded_rolled_dice.origin, RollOrigin::Reroll);
+
+        let legacy_rolled_dice = pb::common::dice::v1::RolledDice {
+            dice: pb::common::dice::v1::DiceType::DiceType6 as i32,
+            uncommon: None,
+            result: 2u32,
+            discarded: false,
+            origin: pb::common::dice::v1::RollOrigin::Unspecified as i32,
+        };
+        let decoded_rolled_dice = RolledDice::try_from(legacy_rolled_dice).unwrap();
+        assert_eq!(decoded_rolled_dice.origin, RollOrigin::Initial);
+    }
+
+    #[test]
+    fn can_encode_and_decode_special_dices_protobuf() {
+        let test_cases = &[
+            (
+                Dice::with_sides(7).unwrap(),
+                pb::common::dice::v1::rolled_dice::Uncommon::Sides(7),
+            ),
+            (
+                Dice::Fudge,
+                pb::common::dice::v1::rolled_dice::Uncommon::Special(
+                    pb::common::dice::v1::SpecialDice::Fudge as i32,
+                ),
+            ),
+            (
+                Dice::PercentileTens,
+                pb::common::dice::v1::rolled_dice::Uncommon::Special(
+                    pb::common::dice::v1::SpecialDice::PercentileTens as i32,
+                ),
+            ),
+        ];
+
+        for tc in test_cases {
+            let proto_rolled_dice =
+                pb::common::dice::v1::RolledDice::from(RolledDice::new(tc.0, 2));
+            assert_eq!(
+                proto_rolled_dice.dice(),
+                pb::common::dice::v1::DiceType::Unspecified
+            );
+            assert_eq!(proto_rolled_dice.uncommon, Some(tc.1));
+
+            let decoded_rolled_dice = RolledDice::try_from(proto_rolled_dice).unwrap();
+            assert_eq!(decoded_rolled_dice.dice, tc.0);
+        }
+
+        assert_eq!(
+            Dice::try_from(pb::common::dice::v1::rolled_dice::Uncommon::Sides(20)).unwrap(),
+            Dice::D20
+        );
+        assert!(Dice::try_from(pb::common::dice::v1::rolled_dice::Uncommon::Sides(1)).is_err());
+
+        let missing_dice = pb::common::dice::v1::RolledDice {
+            dice: pb::common::dice::v1::DiceType::Unspecified as i32,
+            uncommon: None,
+            result: 1,
+            discarded: false,
+            origin: 0,
+        };
+        assert!(matches!(
+            RolledDice::try_from(missing_dice),
+            Err(crate::model::dice::Error::UnspecifiedProtoEnum)
+        ));
     }
 }
@@ -0,0 +1,320 @@
+use std::{fmt::Display, str::FromStr};
+
+use rand::{Rng, SeedableRng, TryRngCore, rngs::OsRng};
+use rand_chacha::ChaCha20Rng;
+
+use super::{Dice, Error};
+
+/// A `Roller` is the source of randomness used to roll dices.
+///
+/// Rolling through a `Roller` allows to choose how random the rolls are: a seeded
+/// [`ChaChaRoller`] makes them reproducible, a [`ScriptedRoller`] makes them deterministic in
+/// tests and an [`OsRoller`] makes them as unpredictable as possible.
+pub trait Roller {
+    /// Returns the result of rolling the given dice, between 1 and its number of sides.
+    fn roll(&mut self, dice: Dice) -> u32;
+
+    /// Returns the [`RollSource`] describing the next rolls of the roller, it should be
+    /// recorded before rolling so that the rolls can be audited afterwards.
+    fn source(&self) -> RollSource;
+}
+
+/// A `RollSource` identifies the [`Roller`] a roll has been made with and, when it is
+/// reproducible, the state it has been made from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollSource {
+    /// The roll comes from the thread local generator of the process ([`ThreadRoller`]).
+    Thread,
+    /// The roll comes from the generator of the operating system ([`OsRoller`]).
+    Os,
+    /// The roll comes from a `ChaCha20` generator with the given seed, starting at the given
+    /// position in its stream ([`ChaChaRoller`]).
+    ChaCha20 { seed: [u8; 32], word_pos: u128 },
+    /// The roll comes from a `ChaCha20` generator whose state has been withheld, as it would
+    /// allow to predict the next rolls of the generator.
+    ChaCha20Withheld,
+    /// The roll comes from a sequence of predefined results ([`ScriptedRoller`]).
+    Scripted,
+}
+
+impl Display for RollSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RollSource::Thread => write!(f, "thread"),
+            RollSource::Os => write!(f, "os"),
+            RollSource::ChaCha20 { seed, word_pos } => {
+                write!(f, "chacha20:")?;
+                for byte in seed {
+                    write!(f, "{byte:02x}")?;
+                }
+                write!(f, ":{word_pos}")
+            }
+            RollSource::ChaCha20Withheld => write!(f, "chacha20"),
+            RollSource::Scripted => write!(f, "scripted"),
+        }
+    }
+}
+
+impl RollSource {
+    /// `withheld` returns the source without the state of the generator, only telling which
+    /// [`Roller`] the roll has been made with. This is what can be disclosed to the players,
+    /// the full source being kept in the history for audit.
+    #[must_use]
+    pub fn withheld(self) -> Self {
+        match self {
+            RollSource::ChaCha20 { .. } => RollSource::ChaCha20Withheld,
+            source => source,
+        }
+    }
+}
+
+impl FromStr for RollSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || Error::RollSourceUnknown(s.to_string());
+        match s {
+            "thread" => Ok(Self::Thread),
+            "os" => Ok(Self::Os),
+            "scripted" => Ok(Self::Scripted),
+            "chacha20" => Ok(Self::ChaCha20Withheld),
+            _ => {
+                let mut parts = s.split(':');
+                let (Some("chacha20"), Some(hex_seed), Some(word_pos), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(unknown());
+                };
+                if hex_seed.len() != 64 || !hex_seed.is_ascii() {
+                    return Err(unknown());
+                }
+
+                let mut seed = [0u8; 32];
+                for (i, byte) in seed.iter_mut().enumerate() {
+                    *byte = u8::from_str_radix(&hex_seed[2 * i..2 * i + 2], 16)
+                        .map_err(|_| unknown())?;
+                }
+                let word_pos = word_pos.parse().map_err(|_| unknown())?;
+                Ok(Self::ChaCha20 { seed, word_pos })
+            }
+        }
+    }
+}
+
+/// `ThreadRoller` rolls dices with the thread local generator of [`rand`], it is the default
+/// [`Roller`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadRoller;
+
+impl Roller for ThreadRoller {
+    fn roll(&mut self, dice: Dice) -> u32 {
+        rand::rng().random_range(1..=dice.side_count())
+    }
+
+    fn source(&self) -> RollSource {
+        RollSource::Thread
+    }
+}
+
+/// `OsRoller` rolls dices with the cryptographically secure generator of the operating
+/// system, for the rolls that must not be predictable such as tournament ones.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsRoller;
+
+impl Roller for OsRoller {
+    fn roll(&mut self, dice: Dice) -> u32 {
+        OsRng.unwrap_err().random_range(1..=dice.side_count())
+    }
+
+    fn source(&self) -> RollSource {
+        RollSource::Os
+    }
+}
+
+/// `ChaChaRoller` rolls dices with a seeded `ChaCha20` generator, so that all its rolls can be
+/// reproduced from its seed.
+///
+/// Anyone knowing the seed can predict the next rolls, it must be kept secret as long as the
+/// roller is used.
+#[derive(Debug, Clone)]
+pub struct ChaChaRoller {
+    rng: ChaCha20Rng,
+}
+
+impl ChaChaRoller {
+    /// Creates a `ChaChaRoller` from the given seed.
+    #[must_use]
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            rng: ChaCha20Rng::from_seed(seed),
+        }
+    }
+
+    /// Creates a `ChaChaRoller` with a seed drawn from the generator of the operating system.
+    #[must_use]
+    pub fn from_os_rng() -> Self {
+        Self {
+            rng: ChaCha20Rng::from_os_rng(),
+        }
+    }
+
+    /// Creates a `ChaChaRoller` that replays the rolls made from the given [`RollSource`],
+    /// if they can be reproduced.
+    #[must_use]
+    pub fn resume(source: RollSource) -> Option<Self> {
+        let RollSource::ChaCha20 { seed, word_pos } = source else {
+            return None;
+        };
+        let mut roller = Self::from_seed(seed);
+        roller.rng.set_word_pos(word_pos);
+        Some(roller)
+    }
+}
+
+impl Roller for ChaChaRoller {
+    fn roll(&mut self, dice: Dice) -> u32 {
+        self.rng.random_range(1..=dice.side_count())
+    }
+
+    fn source(&self) -> RollSource {
+        RollSource::ChaCha20 {
+            seed: self.rng.get_seed(),
+            word_pos: self.rng.get_word_pos(),
+        }
+    }
+}
+
+/// `ScriptedRoller` returns predefined results, looping over them. Results that the rolled
+/// dice cannot give are clamped between 1 and its number of sides.
+#[derive(Debug, Clone)]
+pub struct ScriptedRoller {
+    results: Vec<u32>,
+    next: usize,
+}
+
+impl ScriptedRoller {
+    /// Creates a `ScriptedRoller` returning the given results in order.
+    #[must_use]
+    pub fn new(results: impl IntoIterator<Item = u32>) -> Self {
+        Self {
+            results: results.into_iter().collect(),
+            next: 0,
+        }
+    }
+}
+
+impl Roller for ScriptedRoller {
+    fn roll(&mut self, dice: Dice) -> u32 {
+        let result = self
+            .results
+            .get(self.next % self.results.len().max(1))
+            .copied()
+            .unwrap_or(1);
+        self.next += 1;
+        result.clamp(1, dice.side_count())
+    }
+
+    fn source(&self) -> RollSource {
+        RollSource::Scripted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_roll_within_dice_sides() {
+        let mut rollers: Vec<Box<dyn Roller>> = vec![
+            Box::new(ThreadRoller),
+            Box::new(OsRoller),
+            Box::new(ChaChaRoller::from_os_rng()),
+        ];
+
+        for roller in &mut rollers {
+            for _ in 0..1000 {
+                let result = roller.roll(Dice::D20);
+                assert!((1..=20).contains(&result));
+            }
+        }
+    }
+
+    #[test]
+    fn can_reproduce_seeded_rolls() {
+        let mut roller = ChaChaRoller::from_seed([42; 32]);
+        let _ = roller.roll(Dice::D6);
+
+        let source = roller.source();
+        let rolls = (0..100)
+            .map(|_| roller.roll(Dice::D100))
+            .collect::<Vec<_>>();
+
+        let mut replayed = ChaChaRoller::resume(source).unwrap();
+        let replayed_rolls = (0..100)
+            .map(|_| replayed.roll(Dice::D100))
+            .collect::<Vec<_>>();
+        assert_eq!(rolls, replayed_rolls);
+
+        let mut other = ChaChaRoller::from_seed([43; 32]);
+        let other_rolls = (0..100).map(|_| other.roll(Dice::D100)).collect::<Vec<_>>();
+        assert_ne!(rolls, other_rolls);
+
+        assert!(ChaChaRoller::resume(RollSource::Os).is_none());
+    }
+
+    #[test]
+    fn can_script_rolls() {
+        let mut roller = ScriptedRoller::new([3, 25, 0]);
+        let rolls = (0..4).map(|_| roller.roll(Dice::D20)).collect::<Vec<_>>();
+        assert_eq!(rolls, vec![3, 20, 1, 3]);
+
+        let mut empty = ScriptedRoller::new([]);
+        assert_eq!(empty.roll(Dice::D6), 1);
+    }
+
+    #[test]
+    fn can_encode_and_decode_roll_sources() {
+        let test_cases = &[
+            (RollSource::Thread, "thread".to_string()),
+            (RollSource::Os, "os".to_string()),
+            (RollSource::Scripted, "scripted".to_string()),
+            (RollSource::ChaCha20Withheld, "chacha20".to_string()),
+            (
+                RollSource::ChaCha20 {
+                    seed: [0xab; 32],
+                    word_pos: 1234,
+                },
+                format!("chacha20:{}:1234", "ab".repeat(32)),
+            ),
+        ];
+
+        for tc in test_cases {
+            assert_eq!(tc.0.to_string(), tc.1);
+            assert_eq!(RollSource::from_str(&tc.1).unwrap(), tc.0);
+        }
+
+        let seeded = RollSource::ChaCha20 {
+            seed: [0xab; 32],
+            word_pos: 1234,
+        };
+        assert_eq!(seeded.withheld(), RollSource::ChaCha20Withheld);
+        assert_eq!(RollSource::Os.withheld(), RollSource::Os);
+        assert!(ChaChaRoller::resume(seeded.withheld()).is_none());
+
+        let error_cases = &[
+            "",
+            "chacha",
+            "chacha20:abab:12",
+            "chacha20:1234",
+            &format!("chacha20:{}:-1", "ab".repeat(32)),
+            &format!("chacha20:{}:1:2", "ab".repeat(32)),
+            &format!("chacha20:{}:1", "zz".repeat(32)),
+        ];
+        for tc in error_cases {
+            assert!(matches!(
+                RollSource::from_str(tc),
+                Err(Error::RollSourceUnknown(_))
+            ));
+        }
+    }
+}
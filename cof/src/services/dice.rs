@@ -8,11 +8,37 @@ use async_trait::async_trait;
 use thiserror::Error;
 use uuid::Uuid;
 
-use crate::model::dice::{DiceSet, Error as DiceError, RolledDiceSet};
+use crate::model::dice::{
+    AbilityTest, DiceSet, Error as DiceError, FairnessProof, RollSource, RolledDiceSet, TestOutcome,
+};
+
+/// Records the value of a field declared by the span of the current call, the calls being only
+/// traced along with the `opentelemetry` feature. The value is still borrowed without it, so
+/// that the values computed for the span alone do not end up unused.
+macro_rules! record_in_span {
+    ($field:literal, $value:expr) => {
+        #[cfg(feature = "opentelemetry")]
+        tracing::Span::current().record($field, tracing::field::display($value));
+        #[cfg(not(feature = "opentelemetry"))]
+        let _ = &$value;
+    };
+}
 
 mod service;
 pub use service::*;
 
+mod metadata;
+pub use metadata::*;
+
+mod identity;
+pub use identity::*;
+
+mod history;
+pub use history::*;
+
+mod fairness;
+pub use fairness::*;
+
 pub mod implem;
 
 #[derive(Debug, Error)]
@@ -23,6 +49,30 @@ pub enum Error {
     #[error("The provided Roll ID cannot be parsed")]
     RollIdParseError,
 
+    #[error("The given commitment cannot be found or has already been used")]
+    NonExistingCommitment,
+
+    #[error("Invalid roll metadata: {0}")]
+    InvalidMetadata(String),
+
+    #[error("The watcher fell behind the feed of the rolls, it must resume from its last roll")]
+    RollFeedLagged,
+
+    #[error("The fairness of the dices is not monitored by the service")]
+    FairnessNotMonitored,
+
+    #[error("Invalid idempotency key: {0}")]
+    InvalidIdempotencyKey(String),
+
+    #[error("The idempotency key has already been used by a different roll request")]
+    IdempotencyKeyReused,
+
+    #[error("Invalid batch of dice sets: {0}")]
+    InvalidBatch(String),
+
+    #[error("Cannot roll the dice set {0} of the batch: {1}")]
+    InvalidBatchItem(usize, #[source] Box<Error>),
+
     #[error(transparent)]
     FromModel(#[from] DiceError),
 
@@ -32,22 +82,121 @@ pub enum Error {
 
 #[async_trait]
 pub trait DiceService {
-    /// Roll the provided dices and save the result in the history
+    /// Roll the provided dices and save the result in the history, the roll being attributed
+    /// to the caller when it is authenticated. A request repeating the idempotency key of a
+    /// previous one within the retention window returns the roll saved for it instead of
+    /// rolling again.
     ///
     /// # Errors
     ///
-    /// [`Error::WayTooManyDices`] if the result can outbound [`u32`].
-    async fn roll_dices(&self, req: &RollDicesRequest) -> Result<RollDicesResponse, Error>;
+    /// [`Error::FromModel`] if the dice set cannot be rolled (e.g. way too many dices).
+    /// [`Error::NonExistingCommitment`] if the commitment of a fair roll cannot be used.
+    /// [`Error::InvalidMetadata`] if the metadata of the roll cannot be saved.
+    /// [`Error::InvalidIdempotencyKey`] if the idempotency key is empty or too long.
+    /// [`Error::IdempotencyKeyReused`] if the idempotency key has been used by a request
+    /// rolling other dices or made by someone else.
+    async fn roll_dices(
+        &self,
+        caller: &Caller,
+        req: &RollDicesRequest,
+    ) -> Result<RollDicesResponse, Error>;
+
+    /// Roll the dice sets of the batch independently of each other and save all the rolls in
+    /// the history at once, each of them with its own id. The rolls are attributed to the
+    /// caller when it is authenticated.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidBatch`] if the batch is empty or holds too many dice sets.
+    /// [`Error::InvalidBatchItem`] with the position of the first dice set that cannot be
+    /// rolled (e.g. way too many dices) or whose metadata cannot be saved, nothing being rolled.
+    async fn roll_dices_batch(
+        &self,
+        caller: &Caller,
+        req: &RollDicesBatchRequest,
+    ) -> Result<RollDicesBatchResponse, Error>;
 
     /// Get the past dice roll with the given UUID
     ///
     /// # Errors
     ///
-    /// [`Error::NonExistingDiceRoll`] if the provided UUID cannot be found in the repo.
-    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error>;
+    /// [`Error::NonExistingDiceRoll`] if the provided UUID cannot be found in the repo, or if
+    /// the caller is not meant to see the roll so that its existence is not disclosed.
+    async fn get_dice_roll(&self, caller: &Caller, id: &RollId)
+    -> Result<RollDicesResponse, Error>;
+
+    /// Commit to the server seed of an upcoming provably fair roll. The seed itself is only
+    /// revealed in the [`FairnessProof`] of the roll.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Underlying`] if the commitment cannot be saved.
+    async fn commit_dice_roll(&self) -> Result<RollCommitment, Error>;
+
+    /// Roll the dices of an ability test, save the roll in the history and resolve the test,
+    /// the roll being attributed to the caller when it is authenticated
+    ///
+    /// # Errors
+    ///
+    /// [`Error::FromModel`] if the dice set of the test cannot be rolled.
+    /// [`Error::InvalidMetadata`] if the metadata of the roll cannot be saved.
+    async fn resolve_test(
+        &self,
+        caller: &Caller,
+        req: &ResolveTestRequest,
+    ) -> Result<ResolveTestResponse, Error>;
+
+    /// List the past dice rolls matching the filter of the request, page by page in the order
+    /// of their ids, that is the order in which they have been made. The rolls the caller is
+    /// not meant to see are left out.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Underlying`] if the history cannot be read.
+    async fn list_dice_rolls(
+        &self,
+        caller: &Caller,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error>;
+
+    /// Watch the dice rolls as they are made or revealed, the rolls made after the last seen
+    /// one being replayed from the history first. The rolls the caller is not meant to see
+    /// are left out.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Underlying`] if the feed cannot be opened. The feed itself ends with
+    /// [`Error::RollFeedLagged`] when the watcher does not keep up with the rolls.
+    async fn watch_dice_rolls(
+        &self,
+        caller: &Caller,
+        req: &WatchDiceRollsRequest,
+    ) -> Result<RollFeed, Error>;
+
+    /// Reveal a hidden roll to the whole table, making it public and recording when it has
+    /// been revealed. A roll that is already public is returned as it is.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::NonExistingDiceRoll`] if the provided UUID cannot be found in the repo, or if
+    /// the roll is hidden and the caller is neither the one who made it nor a Game Master who
+    /// sees it.
+    async fn reveal_dice_roll(
+        &self,
+        caller: &Caller,
+        id: &RollId,
+    ) -> Result<RollDicesResponse, Error>;
+
+    /// Get the chi-squared goodness-of-fit tests of the dices rolled by the service, telling
+    /// whether some of them look biased.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::FairnessNotMonitored`] if the service does not count the faces rolled.
+    async fn get_fairness_report(&self) -> Result<FairnessReport, Error>;
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct RollId(Uuid);
 
 #[allow(clippy::new_without_default)]
@@ -97,14 +246,117 @@ impl Display for RollId {
 pub struct RollDicesRequest {
     /// The dice set to be rolled.
     pub dice_set: DiceSet,
+
+    /// Makes the roll provably fair when provided.
+    pub fair_roll: Option<FairRollRequest>,
+
+    /// The context of the roll, saved along with it.
+    pub metadata: RollMetadata,
+
+    /// The key of the request chosen by the client, so that retrying the request returns the
+    /// roll it has made rather than rolling again.
+    pub idempotency_key: Option<String>,
 }
 
-/// The result of rolling a set of dices.
+/// Structure that holds the dice sets that are meant to be rolled together.
+#[derive(Debug, Clone, Default)]
+pub struct RollDicesBatchRequest {
+    /// The dice sets to be rolled, each one independently of the others.
+    pub items: Vec<RollDicesBatchItem>,
+
+    /// The context shared by the rolls of the batch, saved along with each of them.
+    pub metadata: RollMetadata,
+}
+
+/// A dice set of a batch, along with what it is rolled for.
 #[derive(Debug, Clone)]
+pub struct RollDicesBatchItem {
+    /// The dice set to be rolled.
+    pub dice_set: DiceSet,
+
+    /// What the dice set is rolled for (e.g. `Goblin 3`), replacing the label of the metadata
+    /// of the batch when provided.
+    pub label: Option<String>,
+}
+
+/// The rolls of a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollDicesBatchResponse {
+    /// The rolls, in the order of the dice sets of the batch.
+    pub rolls: Vec<RollDicesResponse>,
+}
+
+/// Structure that holds what is needed to make a provably fair roll.
+#[derive(Debug, Clone)]
+pub struct FairRollRequest {
+    /// The id returned along with the commitment of the server.
+    pub commitment_id: RollId,
+
+    /// The seed of the client that is mixed with the committed seed of the server.
+    pub client_seed: Vec<u8>,
+}
+
+/// The commitment of the server to the seed of an upcoming provably fair roll.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollCommitment {
+    /// The UUID of the commitment, given back when the roll is made.
+    pub id: RollId,
+
+    /// The SHA-256 hash of the server seed.
+    pub commitment: [u8; 32],
+}
+
+/// The result of rolling a set of dices.
+#[derive(Debug, Clone, PartialEq)]
 pub struct RollDicesResponse {
     /// The UUID that uniquely identifies the dice roll.
     pub id: RollId,
 
     /// The result of rolling the provided dice set.
     pub rolled_dice_set: RolledDiceSet,
+
+    /// The source of randomness the dice set has been rolled with, allowing to audit the roll.
+    pub source: RollSource,
+
+    /// The proof of the roll when it is provably fair.
+    pub proof: Option<FairnessProof>,
+
+    /// The id of the commitment the roll has been made with when it is provably fair.
+    pub commitment_id: Option<RollId>,
+
+    /// The context of the roll.
+    pub metadata: RollMetadata,
 }
+
+/// Structure that holds the ability test that is meant to be resolved.
+#[derive(Debug, Clone)]
+pub struct ResolveTestRequest {
+    /// The test to be resolved.
+    pub test: AbilityTest,
+
+    /// The context of the roll of the test, saved along with it.
+    pub metadata: RollMetadata,
+}
+
+/// Structure that holds the roll of an ability test along with its outcome.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveTestResponse {
+    /// The roll of the dices of the test, saved in the history like any other roll.
+    pub roll: RollDicesResponse,
+
+    /// The resolution of the test.
+    pub outcome: TestOutcome,
+}
+
+/// Structure that holds the rolls to watch.
+#[derive(Debug, Clone, Default)]
+pub struct WatchDiceRollsRequest {
+    /// Only the rolls of this campaign session, all the rolls when not provided.
+    pub session_id: Option<String>,
+
+    /// The last roll received before reconnecting, the rolls made after it are replayed.
+    pub after: Option<RollId>,
+}
+
+/// The live feed of the dice rolls returned by [`DiceService::watch_dice_rolls`].
+pub type RollFeed = tokio::sync::mpsc::Receiver<Result<RollDicesResponse, Error>>;
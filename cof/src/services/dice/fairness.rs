@@ -0,0 +1,191 @@
+//! Module that contains the report of the fairness of the dices rolled by the service, each
+//! kind of dice being checked with a chi-squared goodness-of-fit test.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use anyhow::{Context, anyhow};
+
+use super::Error;
+use crate::model::dice::{Dice, FaceCounts};
+
+/// The p-value below which a dice is reported as biased when none is given. It is kept low
+/// since the dices are checked over and over, every check being a chance of a false alarm.
+pub const DEFAULT_SIGNIFICANCE: f64 = 0.001;
+
+/// `DiceFairness` is the chi-squared goodness-of-fit test of the rolls of a kind of dice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceFairness {
+    /// The kind of dice tested.
+    pub dice: Dice,
+
+    /// The number of rolls of each face of the dice, from the first one.
+    pub face_counts: Vec<u64>,
+
+    /// The statistic of the test, the further from 0 the less the faces come up evenly.
+    pub chi_squared: f64,
+
+    /// The probability for a fair dice to deviate at least as much from an even distribution.
+    pub p_value: f64,
+
+    /// Whether the p-value is below the significance level, the dice having been rolled enough
+    /// for the test to be meaningful.
+    pub biased: bool,
+}
+
+impl DiceFairness {
+    /// Tests the given counts of the faces of a dice at the given significance level.
+    #[must_use]
+    pub fn new(counts: &FaceCounts, significance: f64) -> Self {
+        let p_value = counts.p_value();
+        Self {
+            dice: counts.dice(),
+            face_counts: counts.counts().to_vec(),
+            chi_squared: counts.chi_squared(),
+            p_value,
+            biased: counts.is_significant() && p_value < significance,
+        }
+    }
+
+    /// `roll_count` returns the number of rolls the test is made on.
+    #[must_use]
+    pub fn roll_count(&self) -> u64 {
+        self.face_counts.iter().sum()
+    }
+}
+
+/// `FairnessReport` gathers the fairness tests of every kind of dice rolled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairnessReport {
+    /// The tests of the dices, ordered by kind of dice.
+    pub dices: Vec<DiceFairness>,
+
+    /// The p-value below which a dice is reported as biased.
+    pub significance: f64,
+}
+
+impl Default for FairnessReport {
+    fn default() -> Self {
+        Self {
+            dices: Vec::new(),
+            significance: DEFAULT_SIGNIFICANCE,
+        }
+    }
+}
+
+impl FairnessReport {
+    /// Tests the counts of the faces of each kind of dice at the given significance level.
+    #[must_use]
+    pub fn new<'a>(counts: impl IntoIterator<Item = &'a FaceCounts>, significance: f64) -> Self {
+        let mut dices = counts
+            .into_iter()
+            .map(|counts| DiceFairness::new(counts, significance))
+            .collect::<Vec<_>>();
+        dices.sort_by_key(|fairness| fairness.dice);
+        Self {
+            dices,
+            significance,
+        }
+    }
+
+    /// Tests the dices of a CSV export of the history, each line holding a dice in dice
+    /// notation and the index of the face rolled, such as the export of the Postgres history:
+    ///
+    /// ```sql
+    /// \copy (SELECT dice, result FROM dice_rolls) TO 'rolls.csv' WITH CSV HEADER
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Underlying`] if the export cannot be read or if one of its lines cannot be
+    /// parsed. [`Error::FromModel`] if a dice is unknown or has no such face.
+    pub fn from_export(export: impl BufRead, significance: f64) -> Result<Self, Error> {
+        let mut counts = HashMap::new();
+        for (index, line) in export.lines().enumerate() {
+            let line = line.context("error reading the export of the history")?;
+            let line = line.trim();
+            if line.is_empty() || (index == 0 && line == "dice,result") {
+                continue;
+            }
+            let (dice, result) = line
+                .split_once(',')
+                .ok_or_else(|| anyhow!("line {} is not a dice and a result", index + 1))?;
+            let dice = Dice::try_from(dice.trim())?;
+            let result = result
+                .trim()
+                .parse()
+                .with_context(|| format!("cannot parse the result of line {}", index + 1))?;
+            counts
+                .entry(dice)
+                .or_insert_with(|| FaceCounts::new(dice))
+                .add(result)?;
+        }
+        Ok(Self::new(counts.values(), significance))
+    }
+
+    /// `biased` returns the tests of the dices that look biased.
+    pub fn biased(&self) -> impl Iterator<Item = &DiceFairness> {
+        self.dices.iter().filter(|fairness| fairness.biased)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::dice::Error as DiceError;
+
+    #[test]
+    fn can_report_biased_dices() {
+        let mut fair = FaceCounts::new(Dice::D6);
+        let mut loaded = FaceCounts::new(Dice::D4);
+        let mut barely_rolled = FaceCounts::new(Dice::D20);
+        for result in (1..=6).cycle().take(120) {
+            fair.add(result).unwrap();
+        }
+        for result in (1..=4).cycle().take(80).chain([4; 40]) {
+            loaded.add(result).unwrap();
+        }
+        for _ in 0..10 {
+            barely_rolled.add(20).unwrap();
+        }
+
+        let report = FairnessReport::new([&fair, &loaded, &barely_rolled], DEFAULT_SIGNIFICANCE);
+        assert_eq!(
+            report.dices.iter().map(|f| f.dice).collect::<Vec<_>>(),
+            vec![Dice::D4, Dice::D6, Dice::D20]
+        );
+        assert_eq!(
+            report.biased().map(|f| f.dice).collect::<Vec<_>>(),
+            vec![Dice::D4]
+        );
+        assert_eq!(report.dices[0].roll_count(), 120);
+        assert_eq!(report.dices[0].face_counts, vec![20, 20, 20, 60]);
+        // the d20 deviates a lot but has not been rolled enough to be judged
+        assert!(report.dices[2].p_value < DEFAULT_SIGNIFICANCE);
+        assert!(!report.dices[2].biased);
+    }
+
+    #[test]
+    fn can_report_from_export() {
+        let export = "dice,result\nd6,1\nd6,6\nd20, 20\n\ndF,3\n";
+        let report = FairnessReport::from_export(export.as_bytes(), 0.05).unwrap();
+        assert!((report.significance - 0.05).abs() < f64::EPSILON);
+        assert_eq!(
+            report
+                .dices
+                .iter()
+                .map(|f| (f.dice, f.roll_count()))
+                .collect::<Vec<_>>(),
+            vec![(Dice::D6, 2), (Dice::D20, 1), (Dice::Fudge, 1)]
+        );
+
+        assert!(matches!(
+            FairnessReport::from_export("d6,7".as_bytes(), 0.05),
+            Err(Error::FromModel(DiceError::FaceUnknown(Dice::D6, 7)))
+        ));
+        assert!(matches!(
+            FairnessReport::from_export("d6;1".as_bytes(), 0.05),
+            Err(Error::Underlying(_))
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! Module that contains the structures used to list the history of the dice rolls, page by
+//! page, in the order of their ids.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use uuid::Uuid;
+
+use super::{Caller, RollDicesResponse, RollId};
+use crate::model::dice::Dice;
+
+/// The number of rolls of a page when the request does not give one.
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// The maximum number of rolls of a page, larger page sizes are lowered to it.
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// `RollFilter` selects the rolls of the history, a roll must match all the criteria given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollFilter {
+    /// Only the rolls made at or after this instant.
+    pub since: Option<SystemTime>,
+
+    /// Only the rolls made before this instant.
+    pub until: Option<SystemTime>,
+
+    /// Only the rolls with at least one dice of this kind.
+    pub dice: Option<Dice>,
+
+    /// Only the rolls made by this player or Game Master.
+    pub rolled_by: Option<String>,
+
+    /// Only the rolls of this campaign session.
+    pub session_id: Option<String>,
+
+    /// Only the rolls this caller is meant to see, see [`Caller::restrict`].
+    pub visible_to: Caller,
+}
+
+impl RollFilter {
+    /// Returns the bounds of the ids of the rolls made within the time range of the filter,
+    /// the lower one being inclusive and the upper one exclusive.
+    ///
+    /// Ids are UUID v7 whose most significant bits are the milliseconds elapsed since the Unix
+    /// epoch, so a time range is a range of ids.
+    #[must_use]
+    pub(crate) fn id_bounds(&self) -> (Option<Uuid>, Option<Uuid>) {
+        (self.since.map(first_id_at), self.until.map(first_id_at))
+    }
+
+    /// Returns the subject of the caller the rolls must be visible to and whether it is a Game
+    /// Master, `None` when the caller sees every roll, for the repositories filtering the
+    /// rolls in their queries.
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    #[must_use]
+    pub(crate) fn viewer(&self) -> Option<(&str, bool)> {
+        match &self.visible_to {
+            Caller::Trusted => None,
+            Caller::Authenticated(identity) => Some((
+                identity.subject.as_str(),
+                identity.role == super::Role::GameMaster,
+            )),
+        }
+    }
+
+    /// Tells whether the given roll matches the filter.
+    #[must_use]
+    pub fn matches(&self, roll: &RollDicesResponse) -> bool {
+        let (lower, upper) = self.id_bounds();
+        let id = roll.id.as_ref();
+        lower.is_none_or(|lower| *id >= lower)
+            && upper.is_none_or(|upper| *id < upper)
+            && self
+                .rolled_by
+                .as_ref()
+                .is_none_or(|rolled_by| roll.metadata.rolled_by.as_ref() == Some(rolled_by))
+            && self
+                .session_id
+                .as_ref()
+                .is_none_or(|session_id| roll.metadata.session_id.as_ref() == Some(session_id))
+            && self
+                .dice
+                .is_none_or(|dice| roll.rolled_dice_set.iter().any(|rd| rd.dice() == dice))
+            && self.visible_to.can_see(roll)
+    }
+}
+
+/// Returns the smallest UUID v7 that can be generated at the given instant.
+fn first_id_at(time: SystemTime) -> Uuid {
+    const MAX_MILLIS: u128 = (1 << 48) - 1;
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis())
+        .min(MAX_MILLIS);
+    Uuid::from_u128(millis << 80)
+}
+
+/// Structure that holds the filter and the page of the rolls to list.
+#[derive(Debug, Clone, Default)]
+pub struct ListDiceRollsRequest {
+    /// The criteria the listed rolls must match.
+    pub filter: RollFilter,
+
+    /// The cursor returned along with the previous page, the rolls are listed from the
+    /// beginning of the history when it is not provided.
+    pub after: Option<RollId>,
+
+    /// The maximum number of rolls of the page, [`DEFAULT_PAGE_SIZE`] when 0.
+    pub page_size: u32,
+}
+
+impl ListDiceRollsRequest {
+    /// Returns the number of rolls of the page, between 1 and [`MAX_PAGE_SIZE`].
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        let page_size = match self.page_size {
+            0 => DEFAULT_PAGE_SIZE,
+            page_size => page_size.min(MAX_PAGE_SIZE),
+        };
+        page_size as usize
+    }
+}
+
+/// A page of the history of the rolls, ordered by their ids.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListDiceRollsResponse {
+    /// The rolls of the page.
+    pub rolls: Vec<RollDicesResponse>,
+
+    /// The cursor to give to get the next page, `None` on the last page.
+    pub next_cursor: Option<RollId>,
+}
+
+impl ListDiceRollsResponse {
+    /// Creates the page out of the first `limit + 1` matching rolls, the extra one only
+    /// telling that there is a next page.
+    #[must_use]
+    pub(crate) fn from_rolls(mut rolls: Vec<RollDicesResponse>, limit: usize) -> Self {
+        let next_cursor = if rolls.len() > limit {
+            rolls.truncate(limit);
+            rolls.last().map(|roll| roll.id.clone())
+        } else {
+            None
+        };
+        Self { rolls, next_cursor }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::model::dice::{DiceSet, RollSource};
+    use crate::services::dice::{Identity, RollMetadata, Visibility};
+
+    fn roll_at(secs: u64, notation: &str, session_id: Option<&str>) -> RollDicesResponse {
+        let time = uuid::Timestamp::from_unix(uuid::NoContext, secs, 0);
+        RollDicesResponse {
+            id: RollId::from(Uuid::new_v7(time)),
+            rolled_dice_set: DiceSet::from_str(notation).unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata {
+                session_id: session_id.map(ToString::to_string),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn can_filter_rolls() {
+        let roll = roll_at(10, "d20 + 2d6", Some("session-1"));
+        let at = |secs| Some(UNIX_EPOCH + Duration::from_secs(secs));
+
+        let matching = [
+            RollFilter::default(),
+            RollFilter {
+                since: at(10),
+                until: at(11),
+                ..Default::default()
+            },
+            RollFilter {
+                dice: Some(Dice::D6),
+                session_id: Some("session-1".to_string()),
+                ..Default::default()
+            },
+        ];
+        for filter in matching {
+            assert!(filter.matches(&roll), "{filter:?}");
+        }
+
+        let not_matching = [
+            RollFilter {
+                since: at(11),
+                ..Default::default()
+            },
+            RollFilter {
+                until: at(10),
+                ..Default::default()
+            },
+            RollFilter {
+                dice: Some(Dice::D8),
+                ..Default::default()
+            },
+            RollFilter {
+                rolled_by: Some("Aldric".to_string()),
+                ..Default::default()
+            },
+            RollFilter {
+                session_id: Some("session-2".to_string()),
+                ..Default::default()
+            },
+        ];
+        for filter in not_matching {
+            assert!(!filter.matches(&roll), "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn can_filter_hidden_rolls() {
+        let mut roll = roll_at(10, "d20", None);
+        roll.metadata.visibility = Visibility::GameMaster;
+
+        let gm = RollFilter {
+            visible_to: Caller::Authenticated(Identity::game_master("gm")),
+            ..Default::default()
+        };
+        let player = RollFilter {
+            visible_to: Caller::Authenticated(Identity::player("aldric")),
+            ..Default::default()
+        };
+        assert!(RollFilter::default().matches(&roll));
+        assert!(gm.matches(&roll));
+        assert!(!player.matches(&roll));
+    }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    #[test]
+    fn can_tell_the_viewer_of_the_rolls() {
+        let gm = RollFilter {
+            visible_to: Caller::Authenticated(Identity::game_master("gm")),
+            ..Default::default()
+        };
+        let player = RollFilter {
+            visible_to: Caller::Authenticated(Identity::player("aldric")),
+            ..Default::default()
+        };
+        assert_eq!(RollFilter::default().viewer(), None);
+        assert_eq!(gm.viewer(), Some(("gm", true)));
+        assert_eq!(player.viewer(), Some(("aldric", false)));
+    }
+
+    #[test]
+    fn can_paginate_rolls() {
+        let request = ListDiceRollsRequest::default();
+        assert_eq!(request.limit(), DEFAULT_PAGE_SIZE as usize);
+        let request = ListDiceRollsRequest {
+            page_size: MAX_PAGE_SIZE + 1,
+            ..Default::default()
+        };
+        assert_eq!(request.limit(), MAX_PAGE_SIZE as usize);
+
+        let rolls = (0..3).map(|i| roll_at(i, "d6", None)).collect::<Vec<_>>();
+        let page = ListDiceRollsResponse::from_rolls(rolls.clone(), 2);
+        assert_eq!(page.rolls, rolls[..2]);
+        assert_eq!(page.next_cursor, Some(rolls[1].id.clone()));
+
+        let page = ListDiceRollsResponse::from_rolls(rolls.clone(), 3);
+        assert_eq!(page.rolls, rolls);
+        assert_eq!(page.next_cursor, None);
+    }
+}
@@ -0,0 +1,254 @@
+//! Module that contains the identity of the callers of the service, used to attribute the rolls
+//! to their players and to hide the rolls they are not meant to see.
+
+use std::{fmt::Display, str::FromStr};
+
+use super::{RollDicesResponse, RollFilter, RollMetadata, Visibility};
+
+/// `Role` tells what a caller is at the table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Role {
+    /// A player, who sees the public rolls and their own private ones.
+    #[default]
+    Player,
+    /// The Game Master, who sees the public rolls and the ones behind the screen, but not the
+    /// private rolls of the players.
+    GameMaster,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Player => write!(f, "player"),
+            Role::GameMaster => write!(f, "gm"),
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "player" => Ok(Self::Player),
+            "gm" => Ok(Self::GameMaster),
+            _ => Err(anyhow::anyhow!("unknown role {s}")),
+        }
+    }
+}
+
+/// `Identity` is an authenticated player or Game Master.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// The unique name of the player or Game Master, the rolls are attributed to it.
+    pub subject: String,
+
+    /// What the player or Game Master is at the table.
+    pub role: Role,
+}
+
+impl Identity {
+    /// Creates the identity of a player.
+    #[must_use]
+    pub fn player(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            role: Role::Player,
+        }
+    }
+
+    /// Creates the identity of a Game Master.
+    #[must_use]
+    pub fn game_master(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            role: Role::GameMaster,
+        }
+    }
+}
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.subject, self.role)
+    }
+}
+
+/// `Caller` is who calls the service.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Caller {
+    /// A call that has not been authenticated, made in process or to a server without
+    /// authentication: it sees every roll and attributes its rolls as it wishes.
+    #[default]
+    Trusted,
+    /// A call authenticated as a player or a Game Master.
+    Authenticated(Identity),
+}
+
+impl Caller {
+    /// Tells whether the roll is meant to be seen by the caller, according to its visibility.
+    ///
+    /// A private roll is only seen by the one who made it, the Game Master included: a player
+    /// hides it from the whole table, until it is revealed.
+    #[must_use]
+    pub fn can_see(&self, roll: &RollDicesResponse) -> bool {
+        let Caller::Authenticated(identity) = self else {
+            return true;
+        };
+        match roll.metadata.visibility {
+            Visibility::Public => true,
+            Visibility::GameMaster => identity.role == Role::GameMaster,
+            Visibility::Private => roll.metadata.rolled_by.as_ref() == Some(&identity.subject),
+        }
+    }
+
+    /// Tells whether the caller may reveal the roll to the whole table: the one who made it
+    /// may, as may a Game Master who sees it.
+    #[must_use]
+    pub fn can_reveal(&self, roll: &RollDicesResponse) -> bool {
+        let Caller::Authenticated(identity) = self else {
+            return true;
+        };
+        roll.metadata.rolled_by.as_ref() == Some(&identity.subject)
+            || (identity.role == Role::GameMaster && self.can_see(roll))
+    }
+
+    /// Returns the metadata of a roll made by the caller, the roll being attributed to the
+    /// authenticated player or Game Master whatever the metadata tells.
+    #[must_use]
+    pub fn attribute(&self, metadata: &RollMetadata) -> RollMetadata {
+        match self {
+            Caller::Trusted => metadata.clone(),
+            Caller::Authenticated(identity) => RollMetadata {
+                rolled_by: Some(identity.subject.clone()),
+                ..metadata.clone()
+            },
+        }
+    }
+
+    /// Returns the filter of a listing made by the caller, the rolls it is not meant to see
+    /// being left out whatever the filter tells.
+    #[must_use]
+    pub fn restrict(&self, filter: &RollFilter) -> RollFilter {
+        match self {
+            Caller::Trusted => filter.clone(),
+            Caller::Authenticated(_) => RollFilter {
+                visible_to: self.clone(),
+                ..filter.clone()
+            },
+        }
+    }
+}
+
+impl Display for Caller {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Caller::Trusted => write!(f, "trusted"),
+            Caller::Authenticated(identity) => write!(f, "{identity}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::dice::{DiceSet, RollSource, ScriptedRoller};
+    use crate::services::dice::RollId;
+
+    fn roll(rolled_by: &str, visibility: Visibility) -> RollDicesResponse {
+        RollDicesResponse {
+            id: RollId::new(),
+            rolled_dice_set: DiceSet::from_str("d20")
+                .unwrap()
+                .roll_with(&mut ScriptedRoller::new([12]))
+                .unwrap(),
+            source: RollSource::Scripted,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata {
+                rolled_by: Some(rolled_by.to_string()),
+                visibility,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn can_encode_and_decode_roles() {
+        for role in [Role::Player, Role::GameMaster] {
+            assert_eq!(Role::from_str(&role.to_string()).unwrap(), role);
+        }
+        assert!(Role::from_str("dm").is_err());
+    }
+
+    #[test]
+    fn can_see_rolls_by_visibility() {
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let brunhild = Caller::Authenticated(Identity::player("brunhild"));
+        let gm = Caller::Authenticated(Identity::game_master("gm"));
+
+        let public = roll("aldric", Visibility::Public);
+        let behind_screen = roll("gm", Visibility::GameMaster);
+        let private = roll("aldric", Visibility::Private);
+
+        for caller in [&aldric, &brunhild, &gm, &Caller::Trusted] {
+            assert!(caller.can_see(&public));
+        }
+        assert!(gm.can_see(&behind_screen));
+        assert!(!aldric.can_see(&behind_screen));
+        assert!(aldric.can_see(&private));
+        assert!(!brunhild.can_see(&private));
+        // the Game Master does not see the private rolls of the players
+        assert!(!gm.can_see(&private));
+        assert!(gm.can_see(&roll("gm", Visibility::Private)));
+        assert!(Caller::Trusted.can_see(&behind_screen));
+        assert!(Caller::Trusted.can_see(&private));
+    }
+
+    #[test]
+    fn can_reveal_own_rolls_or_rolls_seen_by_the_game_master() {
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let brunhild = Caller::Authenticated(Identity::player("brunhild"));
+        let gm = Caller::Authenticated(Identity::game_master("gm"));
+
+        let behind_screen = roll("aldric", Visibility::GameMaster);
+        let private = roll("aldric", Visibility::Private);
+
+        assert!(aldric.can_reveal(&behind_screen));
+        assert!(aldric.can_reveal(&private));
+        assert!(gm.can_reveal(&behind_screen));
+        assert!(!gm.can_reveal(&private));
+        assert!(!brunhild.can_reveal(&behind_screen));
+        assert!(!brunhild.can_reveal(&private));
+        assert!(!brunhild.can_reveal(&roll("aldric", Visibility::Public)));
+        assert!(Caller::Trusted.can_reveal(&private));
+    }
+
+    #[test]
+    fn can_attribute_rolls() {
+        let metadata = RollMetadata {
+            rolled_by: Some("someone else".to_string()),
+            label: Some("Stealth".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(Caller::Trusted.attribute(&metadata), metadata);
+
+        let attributed = Caller::Authenticated(Identity::player("aldric")).attribute(&metadata);
+        assert_eq!(attributed.rolled_by.as_deref(), Some("aldric"));
+        assert_eq!(attributed.label, metadata.label);
+    }
+
+    #[test]
+    fn can_restrict_listings() {
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let filter = RollFilter {
+            session_id: Some("session-1".to_string()),
+            visible_to: Caller::Authenticated(Identity::game_master("gm")),
+            ..Default::default()
+        };
+        assert_eq!(Caller::Trusted.restrict(&filter), filter);
+
+        let restricted = aldric.restrict(&filter);
+        assert_eq!(restricted.visible_to, aldric);
+        assert_eq!(restricted.session_id, filter.session_id);
+    }
+}
@@ -1,13 +1,26 @@
 //! Module containing some default implementations of the adapters to the Dice Service.
 
+pub mod fairness;
 pub mod in_memory;
 pub mod noop;
 
 #[cfg(feature = "protobuf")]
 pub mod grpc;
 
+#[cfg(feature = "protobuf")]
+pub mod auth;
+
 #[cfg(feature = "opentelemetry")]
 pub mod opentelemetry;
 
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+mod sql;
+
 #[cfg(feature = "postgres")]
 pub mod postgres;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[cfg(feature = "journal")]
+pub mod journal;
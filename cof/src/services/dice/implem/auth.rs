@@ -0,0 +1,231 @@
+//! This module authenticates the callers of the gRPC API with the bearer token of their
+//! requests, resolving the [`Identity`] of the player or Game Master who made them.
+//!
+//! The tokens are checked by [`Authenticator`]s: static [`ApiKeys`] and, along with the `jwt`
+//! feature, JSON Web Tokens signed with the keys of a local JWKS file. The [`AuthInterceptor`]
+//! hands the [`Caller`] they authenticate to the service, see
+//! [`Service::into_authenticated_tonic_service`](crate::services::dice::Service::into_authenticated_tonic_service).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tonic::metadata::MetadataMap;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use crate::services::dice::{Caller, Identity};
+
+#[cfg(feature = "jwt")]
+mod jwt;
+#[cfg(feature = "jwt")]
+pub use jwt::*;
+
+/// The metadata key of the bearer token of a request.
+pub const AUTHORIZATION_KEY: &str = "authorization";
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("The request has no bearer token")]
+    MissingToken,
+
+    #[error("The bearer token is unknown")]
+    UnknownToken,
+
+    #[error("The bearer token is invalid: {0}")]
+    InvalidToken(String),
+
+    #[error("The bearer token has expired")]
+    ExpiredToken,
+}
+
+/// `Authenticator` resolves the identity a bearer token has been issued to.
+pub trait Authenticator: Send + Sync + 'static {
+    /// Returns the identity the token has been issued to.
+    ///
+    /// # Errors
+    ///
+    /// [`AuthError::UnknownToken`] if the token is not one of the authenticator, so that
+    /// another one may know it, any other [`AuthError`] if the token is rejected.
+    fn authenticate(&self, token: &str) -> Result<Identity, AuthError>;
+}
+
+/// `ApiKeys` authenticates the callers with static keys, each one being issued to a player or
+/// a Game Master.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    /// The identities by the SHA-256 hash of their key, so that the keys are not compared
+    /// byte by byte.
+    identities: HashMap<[u8; 32], Identity>,
+}
+
+impl ApiKeys {
+    /// Creates the authenticator of the given keys and of the identities they are issued to.
+    #[must_use]
+    pub fn new(keys: impl IntoIterator<Item = (String, Identity)>) -> Self {
+        Self {
+            identities: keys
+                .into_iter()
+                .map(|(key, identity)| (Sha256::digest(key).into(), identity))
+                .collect(),
+        }
+    }
+
+    /// `len` returns the number of keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.identities.len()
+    }
+
+    /// `is_empty` tells whether there are no keys.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.identities.is_empty()
+    }
+}
+
+impl Authenticator for ApiKeys {
+    fn authenticate(&self, token: &str) -> Result<Identity, AuthError> {
+        let hash: [u8; 32] = Sha256::digest(token).into();
+        self.identities
+            .get(&hash)
+            .cloned()
+            .ok_or(AuthError::UnknownToken)
+    }
+}
+
+/// Interceptor of the requests of the gRPC server that authenticates their bearer token,
+/// rejecting the requests without a valid one, and hands the [`Caller`] to the service through
+/// the extensions of the request.
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    authenticators: Vec<Arc<dyn Authenticator>>,
+}
+
+impl AuthInterceptor {
+    /// Creates an interceptor checking the tokens with the given authenticator.
+    #[must_use]
+    pub fn new(authenticator: impl Authenticator) -> Self {
+        Self {
+            authenticators: vec![Arc::new(authenticator)],
+        }
+    }
+
+    /// Adds an authenticator checking the tokens unknown to the previous ones.
+    #[must_use]
+    pub fn or(mut self, authenticator: impl Authenticator) -> Self {
+        self.authenticators.push(Arc::new(authenticator));
+        self
+    }
+
+    /// Returns the identity of the bearer token of the given metadata.
+    ///
+    /// # Errors
+    ///
+    /// [`AuthError`] if there is no bearer token or if none of the authenticators accepts it.
+    pub fn authenticate(&self, metadata: &MetadataMap) -> Result<Identity, AuthError> {
+        let token = metadata
+            .get(AUTHORIZATION_KEY)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or(AuthError::MissingToken)?;
+
+        for authenticator in &self.authenticators {
+            match authenticator.authenticate(token) {
+                Err(AuthError::UnknownToken) => {}
+                result => return result,
+            }
+        }
+        Err(AuthError::UnknownToken)
+    }
+}
+
+impl std::fmt::Debug for AuthInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthInterceptor")
+            .field("authenticators", &self.authenticators.len())
+            .finish()
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let identity = self.authenticate(request.metadata()).map_err(|e| {
+            log::warn!("Rejected an unauthenticated request: {e}");
+            Status::unauthenticated(e.to_string())
+        })?;
+        request
+            .extensions_mut()
+            .insert(Caller::Authenticated(identity));
+        Ok(request)
+    }
+}
+
+/// Returns the caller authenticated by the [`AuthInterceptor`], the requests of a service
+/// served without it being trusted.
+pub(crate) fn caller_of<T>(request: &Request<T>) -> Caller {
+    request
+        .extensions()
+        .get::<Caller>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with(authorization: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION_KEY, authorization.parse().unwrap());
+        request
+    }
+
+    fn api_keys() -> ApiKeys {
+        ApiKeys::new([
+            ("aldric-key".to_string(), Identity::player("aldric")),
+            ("gm-key".to_string(), Identity::game_master("gm")),
+        ])
+    }
+
+    #[test]
+    fn can_authenticate_api_keys() {
+        let keys = api_keys();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(
+            keys.authenticate("gm-key").unwrap(),
+            Identity::game_master("gm")
+        );
+        assert!(matches!(
+            keys.authenticate("gm-key "),
+            Err(AuthError::UnknownToken)
+        ));
+    }
+
+    #[test]
+    fn can_intercept_authenticated_requests() {
+        let mut sut = AuthInterceptor::new(ApiKeys::default()).or(api_keys());
+
+        let request = sut.call(request_with("Bearer aldric-key")).unwrap();
+        assert_eq!(
+            caller_of(&request),
+            Caller::Authenticated(Identity::player("aldric"))
+        );
+        assert_eq!(caller_of(&Request::new(())), Caller::Trusted);
+
+        for rejected in [
+            Request::new(()),
+            request_with("Bearer "),
+            request_with("Basic aldric-key"),
+            request_with("Bearer unknown-key"),
+        ] {
+            let status = sut.call(rejected).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, bail};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::{AuthError, Authenticator};
+use crate::services::dice::{Identity, Role};
+
+/// The clock skew tolerated between the issuer of the tokens and the server.
+pub const DEFAULT_LEEWAY: Duration = Duration::from_secs(60);
+
+/// `JwtAuthenticator` authenticates the callers with JSON Web Tokens signed with the keys of a
+/// JWKS, either HMAC keys (`HS256`) or RSA ones (`RS256`).
+///
+/// The identity is the `sub` claim of the token, a Game Master one when its `role` claim is
+/// `gm`. The tokens must expire, their `exp` claim being required.
+#[derive(Debug, Clone)]
+pub struct JwtAuthenticator {
+    keys: Vec<Jwk>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    leeway: Duration,
+}
+
+/// A key of the JWKS that tokens can be signed with.
+#[derive(Debug, Clone)]
+struct Jwk {
+    id: Option<String>,
+    key: VerifyingKeyKind,
+}
+
+#[derive(Debug, Clone)]
+enum VerifyingKeyKind {
+    Hmac(Vec<u8>),
+    Rsa(VerifyingKey<Sha256>),
+}
+
+#[derive(Debug, Deserialize)]
+struct JwkSet {
+    keys: Vec<JwkEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwkEntry {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    usage: Option<String>,
+    alg: Option<String>,
+    k: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Header {
+    alg: String,
+    kid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    exp: u64,
+    nbf: Option<u64>,
+    iss: Option<String>,
+    #[serde(default)]
+    aud: Audience,
+    role: Option<String>,
+}
+
+/// The `aud` claim, which is either a single audience or a list of them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, audience: &str) -> bool {
+        match self {
+            Audience::None => false,
+            Audience::One(one) => one == audience,
+            Audience::Many(many) => many.iter().any(|one| one == audience),
+        }
+    }
+}
+
+impl JwtAuthenticator {
+    /// Creates the authenticator of the tokens signed with the keys of the given JWKS, the
+    /// keys that are not signing keys or whose type or algorithm is not supported being
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the JWKS cannot be parsed, if one of its
+    /// supported keys is malformed or if it has no supported signing key.
+    pub fn from_jwks(jwks: &str) -> Result<Self, anyhow::Error> {
+        let JwkSet { keys } = serde_json::from_str(jwks).context("Cannot parse the JWKS")?;
+        let keys = keys
+            .into_iter()
+            .filter(|entry| entry.usage.as_deref().is_none_or(|usage| usage == "sig"))
+            .filter_map(|entry| Jwk::from_entry(entry).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            bail!("The JWKS has no supported signing key");
+        }
+
+        Ok(Self {
+            keys,
+            issuer: None,
+            audience: None,
+            leeway: DEFAULT_LEEWAY,
+        })
+    }
+
+    /// Only accepts the tokens issued by the given issuer, their `iss` claim.
+    #[must_use]
+    pub fn with_issuer(self, issuer: impl Into<String>) -> Self {
+        Self {
+            issuer: Some(issuer.into()),
+            ..self
+        }
+    }
+
+    /// Only accepts the tokens issued for the given audience, one of their `aud` claim.
+    #[must_use]
+    pub fn with_audience(self, audience: impl Into<String>) -> Self {
+        Self {
+            audience: Some(audience.into()),
+            ..self
+        }
+    }
+
+    /// Replaces the clock skew tolerated when checking the validity period of the tokens.
+    #[must_use]
+    pub fn with_leeway(self, leeway: Duration) -> Self {
+        Self { leeway, ..self }
+    }
+
+    /// Checks the signature of the token and returns its claims.
+    fn verify(&self, token: &str) -> Result<Claims, AuthError> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(AuthError::UnknownToken);
+        };
+        // a bearer token that is not a JWT may be known to another authenticator
+        let Header { alg, kid } = decode_json(header).map_err(|_| AuthError::UnknownToken)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AuthError::InvalidToken("the signature is not base64url".into()))?;
+        // the signature covers the encoded header and payload
+        let message = &token[..header.len() + 1 + payload.len()];
+
+        let verified = self
+            .keys
+            .iter()
+            .filter(|jwk| kid.is_none() || jwk.id == kid)
+            .any(|jwk| jwk.verify(&alg, message.as_bytes(), &signature));
+        if !verified {
+            return Err(AuthError::InvalidToken(format!(
+                "no {alg} key of the JWKS verifies the signature"
+            )));
+        }
+        decode_json(payload)
+    }
+
+    /// Checks the validity period, the issuer and the audience of the claims.
+    fn check(&self, claims: &Claims) -> Result<(), AuthError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        if Duration::from_secs(claims.exp) + self.leeway <= now {
+            return Err(AuthError::ExpiredToken);
+        }
+        if claims
+            .nbf
+            .is_some_and(|nbf| Duration::from_secs(nbf) > now + self.leeway)
+        {
+            return Err(AuthError::InvalidToken("the token is not valid yet".into()));
+        }
+        if let Some(issuer) = &self.issuer
+            && claims.iss.as_ref() != Some(issuer)
+        {
+            return Err(AuthError::InvalidToken(format!(
+                "the token is not issued by {issuer}"
+            )));
+        }
+        if let Some(audience) = &self.audience
+            && !claims.aud.contains(audience)
+        {
+            return Err(AuthError::InvalidToken(format!(
+                "the token is not issued for {audience}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Authenticator for JwtAuthenticator {
+    fn authenticate(&self, token: &str) -> Result<Identity, AuthError> {
+        let claims = self.verify(token)?;
+        self.check(&claims)?;
+
+        let role = match claims.role.as_deref() {
+            None => Role::Player,
+            Some(role) => role
+                .parse()
+                .map_err(|e| AuthError::InvalidToken(format!("{e}")))?,
+        };
+        if claims.sub.is_empty() {
+            return Err(AuthError::InvalidToken("the subject is empty".into()));
+        }
+        Ok(Identity {
+            subject: claims.sub,
+            role,
+        })
+    }
+}
+
+impl Jwk {
+    /// Tells whether the key verifies the signature of the message with the given algorithm.
+    fn verify(&self, alg: &str, message: &[u8], signature: &[u8]) -> bool {
+        match (&self.key, alg) {
+            (VerifyingKeyKind::Hmac(secret), "HS256") => Hmac::<Sha256>::new_from_slice(secret)
+                .is_ok_and(|mut mac| {
+                    mac.update(message);
+                    mac.verify_slice(signature).is_ok()
+                }),
+            (VerifyingKeyKind::Rsa(key), "RS256") => Signature::try_from(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+            _ => false,
+        }
+    }
+
+    /// Creates the key from an entry of the JWKS, `None` if its type or algorithm is not
+    /// supported.
+    fn from_entry(entry: JwkEntry) -> Result<Option<Self>, anyhow::Error> {
+        let decode = |field: Option<String>, name: &str| {
+            let field = field.with_context(|| format!("The {} key has no {name}", entry.kty))?;
+            URL_SAFE_NO_PAD
+                .decode(field)
+                .with_context(|| format!("The {name} of the {} key is not base64url", entry.kty))
+        };
+        let key = match (entry.kty.as_str(), entry.alg.as_deref()) {
+            ("oct", None | Some("HS256")) => VerifyingKeyKind::Hmac(decode(entry.k.clone(), "k")?),
+            ("RSA", None | Some("RS256")) => {
+                let n = BigUint::from_bytes_be(&decode(entry.n.clone(), "n")?);
+                let e = BigUint::from_bytes_be(&decode(entry.e.clone(), "e")?);
+                let key = RsaPublicKey::new(n, e).context("Invalid RSA key")?;
+                VerifyingKeyKind::Rsa(VerifyingKey::new(key))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Self { id: entry.kid, key }))
+    }
+}
+
+/// Decodes a base64url encoded JSON part of a token.
+fn decode_json<T: for<'de> Deserialize<'de>>(part: &str) -> Result<T, AuthError> {
+    let json = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| AuthError::InvalidToken("a part of the token is not base64url".into()))?;
+    serde_json::from_slice(&json).map_err(|e| AuthError::InvalidToken(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::RsaPrivateKey;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::rand_core::OsRng;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::traits::PublicKeyParts;
+    use serde_json::json;
+    use tonic::metadata::MetadataMap;
+
+    use super::super::{AUTHORIZATION_KEY, ApiKeys, AuthInterceptor};
+    use super::*;
+
+    const SECRET: &[u8] = b"a secret shared with the issuer of the tokens";
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn encode(header: &serde_json::Value, claims: &serde_json::Value) -> String {
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    fn hs256(claims: &serde_json::Value) -> String {
+        let message = encode(&json!({"alg": "HS256", "typ": "JWT"}), claims);
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).unwrap();
+        mac.update(message.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{message}.{signature}")
+    }
+
+    fn hmac_jwks() -> String {
+        json!({"keys": [{"kty": "oct", "kid": "hmac", "k": URL_SAFE_NO_PAD.encode(SECRET)}]})
+            .to_string()
+    }
+
+    #[test]
+    fn can_authenticate_hmac_tokens() {
+        let sut = JwtAuthenticator::from_jwks(&hmac_jwks())
+            .unwrap()
+            .with_issuer("https://auth.example.org")
+            .with_audience("dice_server");
+
+        let claims = json!({
+            "sub": "aldric",
+            "exp": now() + 300,
+            "iss": "https://auth.example.org",
+            "aud": ["dice_server", "party_manager"],
+        });
+        assert_eq!(
+            sut.authenticate(&hs256(&claims)).unwrap(),
+            Identity::player("aldric")
+        );
+
+        let gm_claims = json!({
+            "sub": "gm",
+            "exp": now() + 300,
+            "iss": "https://auth.example.org",
+            "aud": "dice_server",
+            "role": "gm",
+        });
+        assert_eq!(
+            sut.authenticate(&hs256(&gm_claims)).unwrap(),
+            Identity::game_master("gm")
+        );
+    }
+
+    #[test]
+    fn cannot_authenticate_invalid_tokens() {
+        let sut = JwtAuthenticator::from_jwks(&hmac_jwks())
+            .unwrap()
+            .with_audience("dice_server");
+        let valid = json!({"sub": "aldric", "exp": now() + 300, "aud": "dice_server"});
+
+        assert!(matches!(
+            sut.authenticate("aldric-key"),
+            Err(AuthError::UnknownToken)
+        ));
+        assert!(matches!(
+            sut.authenticate(&hs256(
+                &json!({"sub": "aldric", "exp": now() - 120, "aud": "dice_server"})
+            )),
+            Err(AuthError::ExpiredToken)
+        ));
+
+        let tampered = hs256(&valid).replacen('.', ".e30", 1);
+        let unsigned = format!(
+            "{}.",
+            encode(&json!({"alg": "none"}), &json!({"sub": "gm", "role": "gm"}))
+        );
+        let invalid_cases = [
+            tampered,
+            unsigned,
+            hs256(&json!({"sub": "aldric", "aud": "dice_server"})),
+            hs256(&json!({"sub": "aldric", "exp": now() + 300, "aud": "other"})),
+            hs256(
+                &json!({"sub": "aldric", "exp": now() + 300, "nbf": now() + 3600, "aud": "dice_server"}),
+            ),
+            hs256(
+                &json!({"sub": "aldric", "exp": now() + 300, "aud": "dice_server", "role": "god"}),
+            ),
+        ];
+        for token in invalid_cases {
+            assert!(
+                matches!(sut.authenticate(&token), Err(AuthError::InvalidToken(_))),
+                "{token} is accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn can_authenticate_rsa_tokens() {
+        let private_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key = private_key.to_public_key();
+        let jwks = json!({"keys": [
+            {"kty": "oct", "use": "enc", "k": URL_SAFE_NO_PAD.encode(SECRET)},
+            {
+                "kty": "RSA",
+                "kid": "rsa",
+                "alg": "RS256",
+                "n": URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+                "e": URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+            },
+        ]});
+        let sut = JwtAuthenticator::from_jwks(&jwks.to_string()).unwrap();
+
+        let message = encode(
+            &json!({"alg": "RS256", "kid": "rsa"}),
+            &json!({"sub": "brunhild", "exp": now() + 300}),
+        );
+        let signature = SigningKey::<Sha256>::new(private_key).sign(message.as_bytes());
+        let token = format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature.to_bytes()));
+        assert_eq!(
+            sut.authenticate(&token).unwrap(),
+            Identity::player("brunhild")
+        );
+
+        // the encryption key cannot be used to sign tokens
+        assert!(matches!(
+            sut.authenticate(&hs256(&json!({"sub": "gm", "exp": now() + 300}))),
+            Err(AuthError::InvalidToken(_))
+        ));
+        assert!(JwtAuthenticator::from_jwks(r#"{"keys": []}"#).is_err());
+        assert!(JwtAuthenticator::from_jwks(r#"{"keys": [{"kty": "EC"}]}"#).is_err());
+    }
+
+    #[test]
+    fn can_skip_unsupported_keys() {
+        let jwks = json!({"keys": [
+            {"kty": "EC", "crv": "P-256", "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU"},
+            {"kty": "oct", "alg": "HS512", "k": URL_SAFE_NO_PAD.encode(b"another secret")},
+            {"kty": "oct", "kid": "hmac", "k": URL_SAFE_NO_PAD.encode(SECRET)},
+        ]});
+        let sut = JwtAuthenticator::from_jwks(&jwks.to_string()).unwrap();
+        assert_eq!(
+            sut.authenticate(&hs256(&json!({"sub": "aldric", "exp": now() + 300})))
+                .unwrap(),
+            Identity::player("aldric")
+        );
+
+        // a supported key that is malformed is still rejected
+        assert!(JwtAuthenticator::from_jwks(r#"{"keys": [{"kty": "RSA", "n": "AQAB"}]}"#).is_err());
+    }
+
+    #[test]
+    fn can_fall_through_bearers_that_are_not_jwts() {
+        let sut = JwtAuthenticator::from_jwks(&hmac_jwks()).unwrap();
+        for token in ["aldric.key.v2", "bm90.anNvbg.c2ln", "e30.e30.c2ln"] {
+            assert!(
+                matches!(sut.authenticate(token), Err(AuthError::UnknownToken)),
+                "{token} is not unknown"
+            );
+        }
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert(AUTHORIZATION_KEY, "Bearer aldric.key.v2".parse().unwrap());
+        let interceptor = AuthInterceptor::new(sut).or(ApiKeys::new([(
+            "aldric.key.v2".to_owned(),
+            Identity::player("aldric"),
+        )]));
+        assert_eq!(
+            interceptor.authenticate(&metadata).unwrap(),
+            Identity::player("aldric")
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! This module implements the trait [`DiceMeter`] with a monitor of the fairness of the dices,
+//! counting the faces rolled of each kind of dice before handing the rolls to another meter.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::task::JoinHandle;
+
+use crate::model::dice::{Dice, FaceCounts, RolledDiceSet, TestOutcome};
+use crate::services::dice::{DEFAULT_SIGNIFICANCE, DiceMeter, Error, FairnessReport};
+
+use super::noop::NoopMeter;
+
+#[derive(Debug, Clone)]
+pub struct FairnessMonitor<M: DiceMeter = NoopMeter> {
+    inner: M,
+    significance: f64,
+    counts: Arc<Mutex<HashMap<Dice, FaceCounts>>>,
+    /// The report of the last periodic check, shared with the gauges exposing it.
+    pub(super) latest: Arc<Mutex<FairnessReport>>,
+}
+
+impl<M: DiceMeter> FairnessMonitor<M> {
+    /// Creates a monitor handing the rolls to the given meter once their faces are counted.
+    #[must_use]
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            significance: DEFAULT_SIGNIFICANCE,
+            counts: Arc::default(),
+            latest: Arc::default(),
+        }
+    }
+
+    /// Replaces the p-value below which a dice is reported as biased.
+    #[must_use]
+    pub fn with_significance(self, significance: f64) -> Self {
+        Self {
+            significance,
+            latest: Arc::new(Mutex::new(FairnessReport {
+                significance,
+                ..Default::default()
+            })),
+            ..self
+        }
+    }
+
+    /// Tests the faces counted so far.
+    #[must_use]
+    pub fn report(&self) -> FairnessReport {
+        let counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        FairnessReport::new(counts.values(), self.significance)
+    }
+
+    /// Returns the report of the last periodic check, empty until the first one.
+    #[must_use]
+    pub fn latest_report(&self) -> FairnessReport {
+        self.latest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Tests the faces counted so far, warning about the dices that look biased, and keeps the
+    /// report as the latest one.
+    pub fn check(&self) -> FairnessReport {
+        let report = self.report();
+        for fairness in report.biased() {
+            log::warn!(
+                "The {} looks biased: chi-squared of {:.2} over {} rolls, p-value of {:.2e}",
+                fairness.dice,
+                fairness.chi_squared,
+                fairness.roll_count(),
+                fairness.p_value,
+            );
+        }
+        *self.latest.lock().unwrap_or_else(PoisonError::into_inner) = report.clone();
+        report
+    }
+
+    /// Checks the dices every `period` in the background, until the returned task is aborted.
+    pub fn spawn_checks(&self, period: Duration) -> JoinHandle<()>
+    where
+        M: Clone,
+    {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            // the first tick completes immediately, when nothing has been rolled yet
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                monitor.check();
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl<M: DiceMeter> DiceMeter for FairnessMonitor<M> {
+    async fn register_roll(&self, rolled_dice_set: &RolledDiceSet) {
+        {
+            let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+            for rolled_dice in rolled_dice_set.iter() {
+                let dice = rolled_dice.dice();
+                // the discarded dices have been rolled as fairly as the kept ones
+                if let Err(e) = counts
+                    .entry(dice)
+                    .or_insert_with(|| FaceCounts::new(dice))
+                    .add(rolled_dice.result())
+                {
+                    log::error!("Cannot count the face rolled: {e}");
+                }
+            }
+        }
+        self.inner.register_roll(rolled_dice_set).await;
+    }
+
+    async fn register_test(&self, outcome: &TestOutcome) {
+        self.inner.register_test(outcome).await;
+    }
+
+    async fn register_request(
+        &self,
+        method: &'static str,
+        duration: Duration,
+        error: Option<&Error>,
+    ) {
+        self.inner.register_request(method, duration, error).await;
+    }
+
+    async fn fairness_report(&self) -> Option<FairnessReport> {
+        Some(self.report())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::model::dice::{DiceSet, ScriptedRoller};
+
+    async fn roll(sut: &FairnessMonitor, notation: &str, results: &[u32]) {
+        let rolled = DiceSet::from_str(notation)
+            .unwrap()
+            .roll_with(&mut ScriptedRoller::new(results.iter().copied()))
+            .unwrap();
+        sut.register_roll(&rolled).await;
+    }
+
+    #[tokio::test]
+    async fn can_monitor_fairness() {
+        let sut = FairnessMonitor::new(NoopMeter).with_significance(0.01);
+        for _ in 0..20 {
+            roll(&sut, "4d6dl1", &[1, 2, 3, 4]).await;
+            roll(&sut, "d6 + d4", &[5, 4]).await;
+            roll(&sut, "d6", &[6]).await;
+        }
+        roll(&sut, "2d20kh1", &[20, 20]).await;
+
+        let report = sut.fairness_report().await.unwrap();
+        assert!((report.significance - 0.01).abs() < f64::EPSILON);
+        assert_eq!(report.dices.len(), 3);
+        // the discarded dices are counted along with the kept ones
+        assert_eq!(report.dices[1].dice, Dice::D6);
+        assert_eq!(report.dices[1].face_counts, vec![20; 6]);
+        assert!(!report.dices[1].biased);
+        assert_eq!(report.dices[0].dice, Dice::D4);
+        assert_eq!(report.dices[0].face_counts, vec![0, 0, 0, 20]);
+        assert!(report.dices[0].biased);
+
+        assert!(sut.latest_report().dices.is_empty());
+        assert_eq!(sut.check(), report);
+        assert_eq!(sut.latest_report(), report);
+    }
+}
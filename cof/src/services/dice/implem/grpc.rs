@@ -1,20 +1,38 @@
 //! This module provides the protobuf encoding and decoding methods as well as trivial Tonic
 //! client and servers wrappers to call the remote service exactly as the local one.
 
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use anyhow::Context;
 use log::error;
+use tokio::sync::mpsc;
+use tonic::codegen::tokio_stream::{Stream, StreamExt, wrappers::ReceiverStream};
+use tonic::metadata::{Ascii, MetadataValue};
+#[cfg(feature = "opentelemetry")]
+use tonic::metadata::{KeyRef, MetadataKey, MetadataMap};
+use tonic::service::{Interceptor, interceptor::InterceptedService};
 use tonic::{Request, Response, Status, transport::Channel};
 
-use crate::model::dice::RolledDiceSet;
+use super::auth::{AUTHORIZATION_KEY, AuthInterceptor, caller_of};
+use crate::model::dice::{
+    AbilityTest, DEFAULT_CRITICAL_THRESHOLD, Dice, DiceSet, FairnessProof, RollSource, RolledDice,
+    RolledDiceSet, Roller, TestOutcome, commit,
+};
 use crate::services::dice::{
-    DiceHistorySaver, DiceMeter, DiceService, Error, RollDicesRequest, RollDicesResponse, RollId,
-    Service,
+    Caller, DiceFairness, DiceHistorySaver, DiceMeter, DiceService, Error, FairRollRequest,
+    FairnessReport, ListDiceRollsRequest, ListDiceRollsResponse, ResolveTestRequest,
+    ResolveTestResponse, RollCommitment, RollDicesBatchItem, RollDicesBatchRequest,
+    RollDicesBatchResponse, RollDicesRequest, RollDicesResponse, RollFeed, RollFilter, RollId,
+    RollMetadata, Service, Visibility, WATCHER_CAPACITY, WatchDiceRollsRequest,
 };
 
 /// Module that contains the Prost! code generation for the dice API.
 pub mod pb {
     pub use crate::model::dice::pb::common;
     pub mod dice_api {
+        #[allow(clippy::pedantic)]
         pub mod v1 {
             tonic::include_proto!("cof.dice_api.v1");
 
@@ -31,37 +49,215 @@ use pb::dice_api::v1;
 /// This type allows to build a gRPC server that wraps the service.
 ///
 /// This structure can be build from [`Service::into_tonic_service`] method.
-pub struct DiceServiceWrapper<R, M>
+pub struct DiceServiceWrapper<R, M, G>
 where
     R: DiceHistorySaver,
     M: DiceMeter,
+    G: Roller + Send + 'static,
 {
-    svc: Service<R, M>,
+    svc: Service<R, M, G>,
 }
 
 #[tonic::async_trait]
-impl<R, M> v1::dice_service_server::DiceService for DiceServiceWrapper<R, M>
+impl<R, M, G> v1::dice_service_server::DiceService for DiceServiceWrapper<R, M, G>
 where
     R: DiceHistorySaver,
     M: DiceMeter,
+    G: Roller + Send + 'static,
 {
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/RollDices",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "RollDices")
+        )
+    )]
     async fn roll_dices(
         &self,
         request: Request<v1::RollDicesRequest>,
     ) -> Result<Response<v1::RollDicesResponse>, Status> {
-        let req = RollDicesRequest::try_from(request.into_inner()).map_err(Error::Underlying)?;
-        let resp = self.svc.roll_dices(&req).await?;
+        follow_trace_context(&request);
+        let caller = caller_of(&request);
+        let req = RollDicesRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        let resp = self.svc.roll_dices(&caller, &req).await?;
 
         Ok(Response::new(resp.into()))
     }
 
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/GetDiceRoll",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "GetDiceRoll")
+        )
+    )]
     async fn get_dice_roll(
         &self,
         request: Request<v1::GetDiceRollRequest>,
     ) -> Result<Response<v1::GetDiceRollResponse>, Status> {
+        follow_trace_context(&request);
+        let caller = caller_of(&request);
         let v1::GetDiceRollRequest { id } = request.into_inner();
         let id = RollId::parse(&id)?;
-        let resp = self.svc.get_dice_roll(&id).await?;
+        let resp = self.svc.get_dice_roll(&caller, &id).await?;
+
+        Ok(Response::new(resp.into()))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/CommitDiceRoll",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "CommitDiceRoll")
+        )
+    )]
+    async fn commit_dice_roll(
+        &self,
+        request: Request<v1::CommitDiceRollRequest>,
+    ) -> Result<Response<v1::CommitDiceRollResponse>, Status> {
+        follow_trace_context(&request);
+        let RollCommitment { id, commitment } = self.svc.commit_dice_roll().await?;
+
+        Ok(Response::new(v1::CommitDiceRollResponse {
+            id: id.to_string(),
+            commitment: commitment.to_vec(),
+        }))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/ResolveTest",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "ResolveTest")
+        )
+    )]
+    async fn resolve_test(
+        &self,
+        request: Request<v1::ResolveTestRequest>,
+    ) -> Result<Response<v1::ResolveTestResponse>, Status> {
+        follow_trace_context(&request);
+        let caller = caller_of(&request);
+        let req = ResolveTestRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        let resp = self.svc.resolve_test(&caller, &req).await?;
+
+        Ok(Response::new(resp.into()))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/ListDiceRolls",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "ListDiceRolls")
+        )
+    )]
+    async fn list_dice_rolls(
+        &self,
+        request: Request<v1::ListDiceRollsRequest>,
+    ) -> Result<Response<v1::ListDiceRollsResponse>, Status> {
+        follow_trace_context(&request);
+        let caller = caller_of(&request);
+        let req = ListDiceRollsRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        let resp = self.svc.list_dice_rolls(&caller, &req).await?;
+
+        Ok(Response::new(resp.into()))
+    }
+
+    type WatchDiceRollsStream =
+        Pin<Box<dyn Stream<Item = Result<v1::WatchDiceRollsResponse, Status>> + Send>>;
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/WatchDiceRolls",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "WatchDiceRolls")
+        )
+    )]
+    async fn watch_dice_rolls(
+        &self,
+        request: Request<v1::WatchDiceRollsRequest>,
+    ) -> Result<Response<Self::WatchDiceRollsStream>, Status> {
+        follow_trace_context(&request);
+        let caller = caller_of(&request);
+        let req = WatchDiceRollsRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        let feed = self.svc.watch_dice_rolls(&caller, &req).await?;
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(feed).map(
+            |roll| {
+                Ok(v1::WatchDiceRollsResponse {
+                    roll: Some(roll?.into()),
+                })
+            },
+        ))))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/GetFairnessReport",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "GetFairnessReport")
+        )
+    )]
+    async fn get_fairness_report(
+        &self,
+        request: Request<v1::GetFairnessReportRequest>,
+    ) -> Result<Response<v1::GetFairnessReportResponse>, Status> {
+        follow_trace_context(&request);
+        let report = self.svc.get_fairness_report().await?;
+
+        Ok(Response::new(report.into()))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/RevealDiceRoll",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "RevealDiceRoll")
+        )
+    )]
+    async fn reveal_dice_roll(
+        &self,
+        request: Request<v1::RevealDiceRollRequest>,
+    ) -> Result<Response<v1::RevealDiceRollResponse>, Status> {
+        follow_trace_context(&request);
+        let caller = caller_of(&request);
+        let v1::RevealDiceRollRequest { id } = request.into_inner();
+        let id = RollId::parse(&id)?;
+        let roll = self.svc.reveal_dice_roll(&caller, &id).await?;
+
+        Ok(Response::new(v1::RevealDiceRollResponse {
+            roll: Some(roll.into()),
+        }))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/RollDicesBatch",
+            skip_all,
+            fields(otel.kind = "server", rpc.system = "grpc", rpc.method = "RollDicesBatch")
+        )
+    )]
+    async fn roll_dices_batch(
+        &self,
+        request: Request<v1::RollDicesBatchRequest>,
+    ) -> Result<Response<v1::RollDicesBatchResponse>, Status> {
+        follow_trace_context(&request);
+        let caller = caller_of(&request);
+        let req = RollDicesBatchRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        let resp = self.svc.roll_dices_batch(&caller, &req).await?;
 
         Ok(Response::new(resp.into()))
     }
@@ -74,9 +270,27 @@ impl From<Error> for Status {
                 Status::not_found("The dice roll requested cannot be found")
             }
             Error::RollIdParseError => Status::internal("Something went wrong"),
+            Error::NonExistingCommitment => Status::failed_precondition(
+                "The commitment cannot be found or has already been used",
+            ),
+            Error::InvalidMetadata(reason) | Error::InvalidIdempotencyKey(reason) => {
+                Status::invalid_argument(reason)
+            }
+            Error::RollFeedLagged => {
+                Status::aborted("The feed of the rolls has to be resumed from the last roll")
+            }
+            Error::FairnessNotMonitored => {
+                Status::unimplemented("The fairness of the dices is not monitored")
+            }
+            Error::InvalidBatch(_) | Error::InvalidBatchItem(..) => {
+                Status::invalid_argument(value.to_string())
+            }
+            Error::IdempotencyKeyReused => Status::already_exists(
+                "The idempotency key has already been used by a different roll request",
+            ),
             Error::FromModel(error) => {
                 error!("Error from model: {error:?}");
-                Status::failed_precondition("")
+                Status::failed_precondition(error.to_string())
             }
             Error::Underlying(error) => {
                 error!("Error from underlying implementation: {error:?}");
@@ -86,39 +300,174 @@ impl From<Error> for Status {
     }
 }
 
-impl<R, M> Service<R, M>
+impl<R, M, G> Service<R, M, G>
 where
     R: DiceHistorySaver,
     M: DiceMeter,
+    G: Roller + Send + 'static,
 {
     /// Create a gRPC Tonic server from the actual service.
     pub fn into_tonic_service(
         self,
-    ) -> v1::dice_service_server::DiceServiceServer<DiceServiceWrapper<R, M>> {
+    ) -> v1::dice_service_server::DiceServiceServer<DiceServiceWrapper<R, M, G>> {
         v1::dice_service_server::DiceServiceServer::new(DiceServiceWrapper { svc: self })
     }
+
+    /// Create a gRPC Tonic server from the actual service, only serving the requests whose
+    /// bearer token is authenticated by the given interceptor.
+    pub fn into_authenticated_tonic_service(
+        self,
+        interceptor: AuthInterceptor,
+    ) -> InterceptedService<
+        v1::dice_service_server::DiceServiceServer<DiceServiceWrapper<R, M, G>>,
+        AuthInterceptor,
+    > {
+        InterceptedService::new(self.into_tonic_service(), interceptor)
+    }
 }
 
 /// [`DiceService`] implementation for a remote `DiceService` served over gRPC.
 /// Instead of calling the service implementation, the `DiceServiceGrpcClient` uses
 /// gRPC to call a remote service.
+///
+/// The calls are made as the caller the client authenticates as, the caller given to its
+/// methods being ignored.
 pub struct DiceServiceGrpcClient {
-    client: v1::dice_service_client::DiceServiceClient<Channel>,
+    client:
+        v1::dice_service_client::DiceServiceClient<InterceptedService<Channel, ClientInterceptor>>,
 }
 
 impl DiceServiceGrpcClient {
     /// Create a new `DiceServiceGrpcClient` with the given underlying channel.
     #[must_use]
     pub fn new(channel: Channel) -> Self {
+        Self::with_interceptor(channel, ClientInterceptor::default())
+    }
+
+    /// Create a new `DiceServiceGrpcClient` authenticating its calls with the given bearer
+    /// token.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Underlying`] if the token cannot be sent in the metadata of a request.
+    pub fn authenticated(channel: Channel, token: &str) -> Result<Self, Error> {
+        let authorization = format!("Bearer {token}")
+            .parse()
+            .context("The bearer token is not a valid metadata value")?;
+        Ok(Self::with_interceptor(
+            channel,
+            ClientInterceptor {
+                authorization: Some(authorization),
+            },
+        ))
+    }
+
+    fn with_interceptor(channel: Channel, interceptor: ClientInterceptor) -> Self {
         Self {
-            client: v1::dice_service_client::DiceServiceClient::new(channel),
+            client: v1::dice_service_client::DiceServiceClient::with_interceptor(
+                channel,
+                interceptor,
+            ),
+        }
+    }
+}
+
+/// Interceptor of the requests of the [`DiceServiceGrpcClient`] that sends its bearer token and
+/// propagates the W3C trace context of the current span in their metadata, so that the remote
+/// calls belong to the trace of the caller.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInterceptor {
+    authorization: Option<MetadataValue<Ascii>>,
+}
+
+impl Interceptor for ClientInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_KEY, authorization.clone());
+        }
+        #[cfg(feature = "opentelemetry")]
+        {
+            use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+            let context = tracing::Span::current().context();
+            opentelemetry::global::get_text_map_propagator(|propagator| {
+                propagator.inject_context(&context, &mut MetadataInjector(request.metadata_mut()));
+            });
+        }
+        Ok(request)
+    }
+}
+
+/// Continues the trace of the caller in the span of the current call, its context being
+/// propagated in the metadata of the request.
+#[cfg(feature = "opentelemetry")]
+fn follow_trace_context<T>(request: &Request<T>) {
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&MetadataExtractor(request.metadata()))
+    });
+    tracing::Span::current().set_parent(context);
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+fn follow_trace_context<T>(_request: &Request<T>) {}
+
+/// Reads the trace context propagated in the metadata of a gRPC request.
+#[cfg(feature = "opentelemetry")]
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .filter_map(|key| match key {
+                KeyRef::Ascii(key) => Some(key.as_str()),
+                KeyRef::Binary(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Writes the trace context to propagate in the metadata of a gRPC request.
+#[cfg(feature = "opentelemetry")]
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(key.as_bytes()),
+            MetadataValue::try_from(value),
+        ) {
+            self.0.insert(key, value);
         }
     }
 }
 
 #[tonic::async_trait]
 impl DiceService for DiceServiceGrpcClient {
-    async fn roll_dices(&self, req: &RollDicesRequest) -> Result<RollDicesResponse, Error> {
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/RollDices",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "RollDices")
+        )
+    )]
+    async fn roll_dices(
+        &self,
+        _caller: &Caller,
+        req: &RollDicesRequest,
+    ) -> Result<RollDicesResponse, Error> {
         let mut client = self.client.clone();
         let grpc_resp = client
             .roll_dices(v1::RollDicesRequest::from(req.clone()))
@@ -130,7 +479,20 @@ impl DiceService for DiceServiceGrpcClient {
             .context("Error decoding RollDices gRPC response")?)
     }
 
-    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error> {
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/GetDiceRoll",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "GetDiceRoll")
+        )
+    )]
+    async fn get_dice_roll(
+        &self,
+        _caller: &Caller,
+        id: &RollId,
+    ) -> Result<RollDicesResponse, Error> {
         let mut client = self.client.clone();
         let grpc_resp = client
             .get_dice_roll(v1::GetDiceRollRequest {
@@ -143,17 +505,255 @@ impl DiceService for DiceServiceGrpcClient {
         Ok(RollDicesResponse::try_from(grpc_resp)
             .context("Error decoding RollDices gRPC response")?)
     }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/CommitDiceRoll",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "CommitDiceRoll")
+        )
+    )]
+    async fn commit_dice_roll(&self) -> Result<RollCommitment, Error> {
+        let mut client = self.client.clone();
+        let v1::CommitDiceRollResponse { id, commitment } = client
+            .commit_dice_roll(v1::CommitDiceRollRequest {})
+            .await
+            .context("Error while getting gRPC response from CommitDiceRoll")?
+            .into_inner();
+
+        Ok(RollCommitment {
+            id: RollId::parse(&id).context("Cannot parse UUID")?,
+            commitment: commitment
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("The commitment is not 32 bytes long"))?,
+        })
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/ResolveTest",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "ResolveTest")
+        )
+    )]
+    async fn resolve_test(
+        &self,
+        _caller: &Caller,
+        req: &ResolveTestRequest,
+    ) -> Result<ResolveTestResponse, Error> {
+        let mut client = self.client.clone();
+        let grpc_resp = client
+            .resolve_test(v1::ResolveTestRequest::from(req.clone()))
+            .await
+            .context("Error while getting gRPC response from ResolveTest")?
+            .into_inner();
+
+        let roll = RollDicesResponse::try_from(
+            grpc_resp
+                .roll
+                .context("The ResolveTest gRPC response has no roll")?,
+        )
+        .context("Error decoding ResolveTest gRPC response")?;
+        // the outcome is resolved again from the roll so that it matches the test
+        let outcome = req.test.outcome(&roll.rolled_dice_set)?;
+
+        Ok(ResolveTestResponse { roll, outcome })
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/ListDiceRolls",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "ListDiceRolls")
+        )
+    )]
+    async fn list_dice_rolls(
+        &self,
+        _caller: &Caller,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error> {
+        let mut client = self.client.clone();
+        let grpc_resp = client
+            .list_dice_rolls(v1::ListDiceRollsRequest::from(req.clone()))
+            .await
+            .context("Error while getting gRPC response from ListDiceRolls")?
+            .into_inner();
+
+        Ok(ListDiceRollsResponse::try_from(grpc_resp)
+            .context("Error decoding ListDiceRolls gRPC response")?)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/WatchDiceRolls",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "WatchDiceRolls")
+        )
+    )]
+    async fn watch_dice_rolls(
+        &self,
+        _caller: &Caller,
+        req: &WatchDiceRollsRequest,
+    ) -> Result<RollFeed, Error> {
+        let mut client = self.client.clone();
+        let mut grpc_stream = client
+            .watch_dice_rolls(v1::WatchDiceRollsRequest::from(req.clone()))
+            .await
+            .context("Error while getting gRPC response from WatchDiceRolls")?
+            .into_inner();
+
+        let (tx, feed) = mpsc::channel(WATCHER_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                let roll = match grpc_stream.message().await {
+                    Ok(Some(v1::WatchDiceRollsResponse { roll })) => roll
+                        .context("The WatchDiceRolls gRPC response has no roll")
+                        .and_then(RollDicesResponse::try_from)
+                        .context("Error decoding WatchDiceRolls gRPC response")
+                        .map_err(Error::from),
+                    Ok(None) => return,
+                    Err(status) if status.code() == tonic::Code::Aborted => {
+                        Err(Error::RollFeedLagged)
+                    }
+                    Err(status) => Err(anyhow::Error::from(status)
+                        .context("Error while streaming WatchDiceRolls gRPC responses")
+                        .into()),
+                };
+                if tx.send(roll).await.is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(feed)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/GetFairnessReport",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "GetFairnessReport")
+        )
+    )]
+    async fn get_fairness_report(&self) -> Result<FairnessReport, Error> {
+        let mut client = self.client.clone();
+        let grpc_resp = match client
+            .get_fairness_report(v1::GetFairnessReportRequest {})
+            .await
+        {
+            Ok(resp) => resp.into_inner(),
+            Err(status) if status.code() == tonic::Code::Unimplemented => {
+                return Err(Error::FairnessNotMonitored);
+            }
+            Err(status) => {
+                return Err(anyhow::Error::from(status)
+                    .context("Error while getting gRPC response from GetFairnessReport")
+                    .into());
+            }
+        };
+
+        Ok(FairnessReport::try_from(grpc_resp)
+            .context("Error decoding GetFairnessReport gRPC response")?)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/RevealDiceRoll",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "RevealDiceRoll")
+        )
+    )]
+    async fn reveal_dice_roll(
+        &self,
+        _caller: &Caller,
+        id: &RollId,
+    ) -> Result<RollDicesResponse, Error> {
+        let mut client = self.client.clone();
+        let grpc_resp = match client
+            .reveal_dice_roll(v1::RevealDiceRollRequest {
+                id: id.clone().into_string(),
+            })
+            .await
+        {
+            Ok(resp) => resp.into_inner(),
+            Err(status) if status.code() == tonic::Code::NotFound => {
+                return Err(Error::NonExistingDiceRoll);
+            }
+            Err(status) => {
+                return Err(anyhow::Error::from(status)
+                    .context("Error while getting gRPC response from RevealDiceRoll")
+                    .into());
+            }
+        };
+
+        Ok(RollDicesResponse::try_from(
+            grpc_resp
+                .roll
+                .context("The RevealDiceRoll gRPC response has no roll")?,
+        )
+        .context("Error decoding RevealDiceRoll gRPC response")?)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            name = "cof.dice_api.v1.DiceService/RollDicesBatch",
+            skip_all,
+            err,
+            fields(otel.kind = "client", rpc.system = "grpc", rpc.method = "RollDicesBatch")
+        )
+    )]
+    async fn roll_dices_batch(
+        &self,
+        _caller: &Caller,
+        req: &RollDicesBatchRequest,
+    ) -> Result<RollDicesBatchResponse, Error> {
+        let mut client = self.client.clone();
+        let grpc_resp = client
+            .roll_dices_batch(v1::RollDicesBatchRequest::from(req.clone()))
+            .await
+            .context("Error while getting gRPC response from RollDicesBatch")?
+            .into_inner();
+
+        Ok(RollDicesBatchResponse::try_from(grpc_resp)
+            .context("Error decoding RollDicesBatch gRPC response")?)
+    }
 }
 
 impl From<RollDicesRequest> for v1::RollDicesRequest {
     fn from(value: RollDicesRequest) -> Self {
+        // only the common dices can be given as DiceType, the notation carries the other ones
         let dices = value
             .dice_set
             .iter()
-            .map(|d| pb::common::dice::v1::DiceType::from(*d) as i32)
-            .collect();
+            .map(|d| pb::common::dice::v1::DiceType::try_from(*d).map(|d| d as i32))
+            .collect::<Result<_, _>>()
+            .unwrap_or_default();
+
+        let (commitment_id, client_seed) = value
+            .fair_roll
+            .map(|fr| (fr.commitment_id.to_string(), fr.client_seed))
+            .unwrap_or_default();
 
-        Self { dices }
+        Self {
+            dices,
+            dice_set: value.dice_set.to_string(),
+            commitment_id,
+            client_seed,
+            metadata: Some(value.metadata.into()),
+            idempotency_key: value.idempotency_key.unwrap_or_default(),
+        }
     }
 }
 
@@ -161,33 +761,306 @@ impl TryFrom<v1::RollDicesRequest> for RollDicesRequest {
     type Error = anyhow::Error;
 
     fn try_from(value: v1::RollDicesRequest) -> Result<Self, Self::Error> {
-        Ok(Self {
-            dice_set: value
+        let dice_set = if value.dice_set.is_empty() {
+            value
                 .dices()
                 .collect::<Vec<_>>()
                 .try_into()
-                .context("Cannot parse DiceSet")?,
+                .context("Cannot parse DiceSet")?
+        } else {
+            DiceSet::from_str(&value.dice_set).context("Cannot parse DiceSet notation")?
+        };
+
+        let fair_roll = if value.commitment_id.is_empty() {
+            None
+        } else {
+            Some(FairRollRequest {
+                commitment_id: RollId::parse(&value.commitment_id)
+                    .context("Cannot parse the commitment UUID")?,
+                client_seed: value.client_seed,
+            })
+        };
+
+        Ok(Self {
+            dice_set,
+            fair_roll,
+            metadata: value.metadata.map(Into::into).unwrap_or_default(),
+            idempotency_key: (!value.idempotency_key.is_empty()).then_some(value.idempotency_key),
         })
     }
 }
 
-impl From<RollDicesResponse> for v1::RollDicesResponse {
-    fn from(value: RollDicesResponse) -> Self {
+/// Decodes the rolled dices of a response, using the dice set notation when provided to
+/// restore the modifiers and arithmetic of the roll.
+fn decode_rolled_dice_set(
+    dice_set: &str,
+    rolled_dices: Vec<pb::common::dice::v1::RolledDice>,
+) -> Result<RolledDiceSet, anyhow::Error> {
+    if dice_set.is_empty() {
+        return RolledDiceSet::try_from(rolled_dices)
+            .context("Cannot parse the resulting dice set");
+    }
+
+    let dice_set = DiceSet::from_str(dice_set).context("Cannot parse DiceSet notation")?;
+    let rolled_dices = rolled_dices
+        .into_iter()
+        .map(RolledDice::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .context("Cannot parse the resulting dice set")?;
+
+    RolledDiceSet::try_from_parts(&dice_set, rolled_dices)
+        .context("Cannot parse the resulting dice set")
+}
+
+/// Decodes the source of a roll, responses that do not provide it come from servers that
+/// only roll with the thread local generator.
+fn decode_roll_source(roll_source: &str) -> Result<RollSource, anyhow::Error> {
+    if roll_source.is_empty() {
+        return Ok(RollSource::Thread);
+    }
+    RollSource::from_str(roll_source).context("Cannot parse the source of the roll")
+}
+
+/// Decodes the id of the commitment of a roll, the roll not being provably fair when it is
+/// empty.
+fn decode_commitment_id(commitment_id: &str) -> Result<Option<RollId>, anyhow::Error> {
+    if commitment_id.is_empty() {
+        return Ok(None);
+    }
+    RollId::parse(commitment_id)
+        .map(Some)
+        .context("Cannot parse the commitment UUID")
+}
+
+impl From<RollDicesBatchRequest> for v1::RollDicesBatchRequest {
+    fn from(value: RollDicesBatchRequest) -> Self {
         Self {
-            id: value.id.to_string(),
-            rolled_dices: value.rolled_dice_set.into(),
+            items: value
+                .items
+                .into_iter()
+                .map(|item| v1::RollDicesBatchItem {
+                    dice_set: item.dice_set.to_string(),
+                    label: item.label.unwrap_or_default(),
+                })
+                .collect(),
+            metadata: Some(value.metadata.into()),
+        }
+    }
+}
+
+impl TryFrom<v1::RollDicesBatchRequest> for RollDicesBatchRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::RollDicesBatchRequest) -> Result<Self, Self::Error> {
+        let items = value
+            .items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                Ok(RollDicesBatchItem {
+                    dice_set: DiceSet::from_str(&item.dice_set).with_context(|| {
+                        format!("Cannot parse the DiceSet notation of the item {index}")
+                    })?,
+                    label: (!item.label.is_empty()).then_some(item.label),
+                })
+            })
+            .collect::<Result<_, Self::Error>>()?;
+
+        Ok(Self {
+            items,
+            metadata: value.metadata.map(Into::into).unwrap_or_default(),
+        })
+    }
+}
+
+impl From<RollDicesBatchResponse> for v1::RollDicesBatchResponse {
+    fn from(value: RollDicesBatchResponse) -> Self {
+        Self {
+            rolls: value.rolls.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<v1::RollDicesBatchResponse> for RollDicesBatchResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::RollDicesBatchResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            rolls: value
+                .rolls
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<ResolveTestRequest> for v1::ResolveTestRequest {
+    fn from(value: ResolveTestRequest) -> Self {
+        Self {
+            dice_set: value.test.dice_set().to_string(),
+            modifier: value.test.modifier(),
+            difficulty: value.test.difficulty(),
+            critical_threshold: value.test.critical_threshold(),
+            metadata: Some(value.metadata.into()),
+        }
+    }
+}
+
+impl TryFrom<v1::ResolveTestRequest> for ResolveTestRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::ResolveTestRequest) -> Result<Self, Self::Error> {
+        let dice_set =
+            DiceSet::from_str(&value.dice_set).context("Cannot parse DiceSet notation")?;
+        let critical_threshold = match value.critical_threshold {
+            0 => DEFAULT_CRITICAL_THRESHOLD,
+            threshold => threshold,
+        };
+        let test = AbilityTest::new(dice_set, value.modifier, value.difficulty)
+            .and_then(|test| test.with_critical_threshold(critical_threshold))
+            .context("Cannot build the ability test")?;
+
+        Ok(Self {
+            test,
+            metadata: value.metadata.map(Into::into).unwrap_or_default(),
+        })
+    }
+}
+
+impl From<TestOutcome> for v1::TestOutcome {
+    fn from(value: TestOutcome) -> Self {
+        Self {
+            natural: value.natural(),
+            total: value.total(),
+            margin: value.margin(),
+            success: value.is_success(),
+            critical: value.is_critical(),
+            fumble: value.is_fumble(),
+        }
+    }
+}
+
+impl From<ResolveTestResponse> for v1::ResolveTestResponse {
+    fn from(value: ResolveTestResponse) -> Self {
+        Self {
+            roll: Some(value.roll.into()),
+            outcome: Some(value.outcome.into()),
+        }
+    }
+}
+
+impl From<Visibility> for v1::Visibility {
+    fn from(value: Visibility) -> Self {
+        match value {
+            Visibility::Public => Self::Public,
+            Visibility::GameMaster => Self::GameMaster,
+            Visibility::Private => Self::Private,
         }
     }
 }
 
+impl From<v1::Visibility> for Visibility {
+    fn from(value: v1::Visibility) -> Self {
+        match value {
+            v1::Visibility::Unspecified | v1::Visibility::Public => Self::Public,
+            v1::Visibility::GameMaster => Self::GameMaster,
+            v1::Visibility::Private => Self::Private,
+        }
+    }
+}
+
+impl From<RollMetadata> for v1::RollMetadata {
+    fn from(value: RollMetadata) -> Self {
+        Self {
+            label: value.label.unwrap_or_default(),
+            rolled_by: value.rolled_by.unwrap_or_default(),
+            session_id: value.session_id.unwrap_or_default(),
+            visibility: v1::Visibility::from(value.visibility) as i32,
+            tags: value.tags,
+            revealed_at_unix_ms: encode_unix_ms(value.revealed_at),
+        }
+    }
+}
+
+impl From<v1::RollMetadata> for RollMetadata {
+    fn from(value: v1::RollMetadata) -> Self {
+        let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+        Self {
+            visibility: value.visibility().into(),
+            label: non_empty(value.label),
+            rolled_by: non_empty(value.rolled_by),
+            session_id: non_empty(value.session_id),
+            tags: value.tags,
+            revealed_at: decode_unix_ms(value.revealed_at_unix_ms),
+        }
+    }
+}
+
+impl From<FairnessProof> for v1::FairnessProof {
+    fn from(value: FairnessProof) -> Self {
+        Self {
+            commitment: value.commitment().to_vec(),
+            server_seed: value.server_seed().to_vec(),
+            client_seed: value.client_seed().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<v1::FairnessProof> for FairnessProof {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::FairnessProof) -> Result<Self, Self::Error> {
+        let server_seed: [u8; 32] = value
+            .server_seed
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("The server seed is not 32 bytes long"))?;
+        if commit(&server_seed).as_slice() != value.commitment {
+            anyhow::bail!("The server seed does not match the commitment");
+        }
+        FairnessProof::new(server_seed, value.client_seed).context("Cannot parse the proof")
+    }
+}
+
+/// Encodes a roll along with the full state of the generator it has been made with, so that
+/// it can be audited. It is only meant for the history, the rolls sent to the clients
+/// withholding that state as it would allow to predict the next rolls.
+pub(crate) fn encode_audited_roll(value: RollDicesResponse) -> v1::RollDicesResponse {
+    v1::RollDicesResponse {
+        id: value.id.to_string(),
+        dice_set: value.rolled_dice_set.dice_set().to_string(),
+        total: value.rolled_dice_set.total(),
+        rolled_dices: value.rolled_dice_set.into(),
+        roll_source: value.source.to_string(),
+        proof: value.proof.map(Into::into),
+        commitment_id: value
+            .commitment_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        metadata: Some(value.metadata.into()),
+    }
+}
+
+impl From<RollDicesResponse> for v1::RollDicesResponse {
+    fn from(value: RollDicesResponse) -> Self {
+        encode_audited_roll(RollDicesResponse {
+            source: value.source.withheld(),
+            ..value
+        })
+    }
+}
+
 impl TryFrom<v1::RollDicesResponse> for RollDicesResponse {
     type Error = anyhow::Error;
 
     fn try_from(value: v1::RollDicesResponse) -> Result<Self, Self::Error> {
         Ok(Self {
             id: RollId::parse(&value.id).context("Cannot parse UUID")?,
-            rolled_dice_set: RolledDiceSet::try_from(value.rolled_dices)
-                .context("Cannot parse the resulting dice set")?,
+            rolled_dice_set: decode_rolled_dice_set(&value.dice_set, value.rolled_dices)?,
+            source: decode_roll_source(&value.roll_source)?,
+            proof: value.proof.map(TryInto::try_into).transpose()?,
+            commitment_id: decode_commitment_id(&value.commitment_id)?,
+            metadata: value.metadata.map(Into::into).unwrap_or_default(),
         })
     }
 }
@@ -196,7 +1069,16 @@ impl From<RollDicesResponse> for v1::GetDiceRollResponse {
     fn from(value: RollDicesResponse) -> Self {
         Self {
             id: value.id.to_string(),
+            dice_set: value.rolled_dice_set.dice_set().to_string(),
+            total: value.rolled_dice_set.total(),
             rolled_dices: value.rolled_dice_set.into(),
+            roll_source: value.source.withheld().to_string(),
+            proof: value.proof.map(Into::into),
+            commitment_id: value
+                .commitment_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            metadata: Some(value.metadata.into()),
         }
     }
 }
@@ -207,8 +1089,195 @@ impl TryFrom<v1::GetDiceRollResponse> for RollDicesResponse {
     fn try_from(value: v1::GetDiceRollResponse) -> Result<Self, Self::Error> {
         Ok(Self {
             id: RollId::parse(&value.id).context("Cannot parse UUID")?,
-            rolled_dice_set: RolledDiceSet::try_from(value.rolled_dices)
-                .context("Cannot parse the resulting dice set")?,
+            rolled_dice_set: decode_rolled_dice_set(&value.dice_set, value.rolled_dices)?,
+            source: decode_roll_source(&value.roll_source)?,
+            proof: value.proof.map(TryInto::try_into).transpose()?,
+            commitment_id: decode_commitment_id(&value.commitment_id)?,
+            metadata: value.metadata.map(Into::into).unwrap_or_default(),
+        })
+    }
+}
+
+/// Encodes an instant as milliseconds since the Unix epoch, 0 meaning that it is not set.
+pub(crate) fn encode_unix_ms(time: Option<SystemTime>) -> u64 {
+    time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
+}
+
+/// Decodes the milliseconds since the Unix epoch of [`encode_unix_ms`].
+pub(crate) fn decode_unix_ms(unix_ms: u64) -> Option<SystemTime> {
+    (unix_ms != 0).then(|| UNIX_EPOCH + Duration::from_millis(unix_ms))
+}
+
+impl From<RollFilter> for v1::RollFilter {
+    fn from(value: RollFilter) -> Self {
+        Self {
+            since_unix_ms: encode_unix_ms(value.since),
+            until_unix_ms: encode_unix_ms(value.until),
+            dice: value.dice.map(|dice| dice.to_string()).unwrap_or_default(),
+            rolled_by: value.rolled_by.unwrap_or_default(),
+            session_id: value.session_id.unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<v1::RollFilter> for RollFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::RollFilter) -> Result<Self, Self::Error> {
+        let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+        let dice = non_empty(value.dice)
+            .map(|dice| Dice::try_from(dice.as_str()))
+            .transpose()
+            .context("Cannot parse the dice of the filter")?;
+        Ok(Self {
+            since: decode_unix_ms(value.since_unix_ms),
+            until: decode_unix_ms(value.until_unix_ms),
+            dice,
+            rolled_by: non_empty(value.rolled_by),
+            session_id: non_empty(value.session_id),
+            // the rolls are only left out for the caller by the service
+            visible_to: Caller::Trusted,
+        })
+    }
+}
+
+impl From<ListDiceRollsRequest> for v1::ListDiceRollsRequest {
+    fn from(value: ListDiceRollsRequest) -> Self {
+        Self {
+            filter: Some(value.filter.into()),
+            page_token: value.after.map(RollId::into_string).unwrap_or_default(),
+            page_size: value.page_size,
+        }
+    }
+}
+
+impl TryFrom<v1::ListDiceRollsRequest> for ListDiceRollsRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::ListDiceRollsRequest) -> Result<Self, Self::Error> {
+        let after = if value.page_token.is_empty() {
+            None
+        } else {
+            Some(RollId::parse(&value.page_token).context("Cannot parse the page token")?)
+        };
+        Ok(Self {
+            filter: value
+                .filter
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+            after,
+            page_size: value.page_size,
+        })
+    }
+}
+
+impl From<ListDiceRollsResponse> for v1::ListDiceRollsResponse {
+    fn from(value: ListDiceRollsResponse) -> Self {
+        Self {
+            rolls: value.rolls.into_iter().map(Into::into).collect(),
+            next_page_token: value
+                .next_cursor
+                .map(RollId::into_string)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<v1::ListDiceRollsResponse> for ListDiceRollsResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::ListDiceRollsResponse) -> Result<Self, Self::Error> {
+        let next_cursor = if value.next_page_token.is_empty() {
+            None
+        } else {
+            Some(RollId::parse(&value.next_page_token).context("Cannot parse the page token")?)
+        };
+        Ok(Self {
+            rolls: value
+                .rolls
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            next_cursor,
+        })
+    }
+}
+
+impl From<WatchDiceRollsRequest> for v1::WatchDiceRollsRequest {
+    fn from(value: WatchDiceRollsRequest) -> Self {
+        Self {
+            session_id: value.session_id.unwrap_or_default(),
+            last_seen_id: value.after.map(RollId::into_string).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<v1::WatchDiceRollsRequest> for WatchDiceRollsRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::WatchDiceRollsRequest) -> Result<Self, Self::Error> {
+        let after = if value.last_seen_id.is_empty() {
+            None
+        } else {
+            Some(RollId::parse(&value.last_seen_id).context("Cannot parse the last seen UUID")?)
+        };
+        Ok(Self {
+            session_id: (!value.session_id.is_empty()).then_some(value.session_id),
+            after,
+        })
+    }
+}
+
+impl From<DiceFairness> for v1::DiceFairness {
+    fn from(value: DiceFairness) -> Self {
+        Self {
+            dice: value.dice.to_string(),
+            face_counts: value.face_counts,
+            chi_squared: value.chi_squared,
+            p_value: value.p_value,
+            biased: value.biased,
+        }
+    }
+}
+
+impl TryFrom<v1::DiceFairness> for DiceFairness {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::DiceFairness) -> Result<Self, Self::Error> {
+        Ok(Self {
+            dice: Dice::try_from(value.dice.as_str()).context("Cannot parse the dice")?,
+            face_counts: value.face_counts,
+            chi_squared: value.chi_squared,
+            p_value: value.p_value,
+            biased: value.biased,
+        })
+    }
+}
+
+impl From<FairnessReport> for v1::GetFairnessReportResponse {
+    fn from(value: FairnessReport) -> Self {
+        Self {
+            dices: value.dices.into_iter().map(Into::into).collect(),
+            significance: value.significance,
+        }
+    }
+}
+
+impl TryFrom<v1::GetFairnessReportResponse> for FairnessReport {
+    type Error = anyhow::Error;
+
+    fn try_from(value: v1::GetFairnessReportResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            dices: value
+                .dices
+                .into_iter()
+                .map(DiceFairness::try_from)
+                .collect::<Result<_, _>>()?,
+            significance: value.significance,
         })
     }
 }
@@ -216,7 +1285,7 @@ impl TryFrom<v1::GetDiceRollResponse> for RollDicesResponse {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::model::dice::{Dice, DiceSet};
+    use crate::model::dice::{ChaChaRoller, DiceSet, Error as DiceError, FaceCounts};
     use crate::services::dice::{
         RollDicesRequest,
         implem::{in_memory::InMemoryDiceHistorySaver, noop::NoopMeter},
@@ -227,6 +1296,9 @@ mod test {
         let dice_set = DiceSet::new(vec![Dice::D100].into_iter());
         let req = RollDicesRequest {
             dice_set: dice_set.clone(),
+            fair_roll: None,
+            metadata: RollMetadata::default(),
+            idempotency_key: None,
         };
 
         let proto_req = v1::RollDicesRequest::from(req);
@@ -237,11 +1309,131 @@ mod test {
                 .dices()
                 .all(|d| d == pb::common::dice::v1::DiceType::DiceType100)
         );
+        assert!(proto_req.idempotency_key.is_empty());
 
         let initial_req = RollDicesRequest::try_from(proto_req);
         assert!(initial_req.is_ok());
 
-        assert_eq!(initial_req.unwrap().dice_set, dice_set);
+        let initial_req = initial_req.unwrap();
+        assert_eq!(initial_req.dice_set, dice_set);
+        assert_eq!(initial_req.idempotency_key, None);
+
+        let proto_req = v1::RollDicesRequest::from(RollDicesRequest {
+            idempotency_key: Some("attack-1".to_string()),
+            ..initial_req
+        });
+        assert_eq!(proto_req.idempotency_key, "attack-1");
+        assert_eq!(
+            RollDicesRequest::try_from(proto_req)
+                .unwrap()
+                .idempotency_key
+                .as_deref(),
+            Some("attack-1")
+        );
+    }
+
+    #[test]
+    fn can_encode_and_decode_dice_roll_requests_with_modifiers() {
+        let dice_set = DiceSet::from_str("2d6 + d4 - 1").unwrap();
+        let proto_req = v1::RollDicesRequest::from(RollDicesRequest {
+            dice_set: dice_set.clone(),
+            fair_roll: None,
+            metadata: RollMetadata::default(),
+            idempotency_key: None,
+        });
+        assert_eq!(proto_req.dice_set, "2d6 + d4 - 1");
+        assert_eq!(proto_req.dices.len(), 3);
+
+        let decoded_req = RollDicesRequest::try_from(proto_req).unwrap();
+        assert_eq!(decoded_req.dice_set, dice_set);
+
+        let special_dice_set = DiceSet::from_str("4dF + d7").unwrap();
+        let proto_req = v1::RollDicesRequest::from(RollDicesRequest {
+            dice_set: special_dice_set.clone(),
+            fair_roll: None,
+            metadata: RollMetadata::default(),
+            idempotency_key: None,
+        });
+        assert!(proto_req.dices.is_empty());
+        let decoded_req = RollDicesRequest::try_from(proto_req).unwrap();
+        assert_eq!(decoded_req.dice_set, special_dice_set);
+
+        let invalid_req = RollDicesRequest::try_from(v1::RollDicesRequest {
+            dices: vec![],
+            dice_set: "2d6 +".to_string(),
+            ..Default::default()
+        });
+        assert!(invalid_req.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_encode_and_decode_dice_roll_response_with_modifiers() {
+        let svc = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+
+        let roll_dice_resp = svc
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("d20 - 30").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata::default(),
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let proto_roll_resp = v1::RollDicesResponse::from(roll_dice_resp.clone());
+        assert_eq!(proto_roll_resp.dice_set, "d20 - 30");
+        assert_eq!(
+            proto_roll_resp.total,
+            roll_dice_resp.rolled_dice_set.total()
+        );
+        assert!(proto_roll_resp.total < 0);
+
+        let decoded_resp = RollDicesResponse::try_from(proto_roll_resp).unwrap();
+        assert_eq!(decoded_resp.rolled_dice_set, roll_dice_resp.rolled_dice_set);
+    }
+
+    #[tokio::test]
+    async fn can_encode_and_decode_dice_roll_source() {
+        let svc = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter)
+            .with_roller(ChaChaRoller::from_seed([9; 32]));
+
+        let roll_dice_resp = svc
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("3d6").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata::default(),
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let audited_roll_resp = encode_audited_roll(roll_dice_resp.clone());
+        assert!(audited_roll_resp.roll_source.starts_with("chacha20:0909"));
+        let decoded_resp = RollDicesResponse::try_from(audited_roll_resp).unwrap();
+        assert_eq!(decoded_resp.source, roll_dice_resp.source);
+
+        // the seed of the generator is withheld as it would allow to predict the next rolls
+        let mut proto_roll_resp = v1::RollDicesResponse::from(roll_dice_resp.clone());
+        assert_eq!(proto_roll_resp.roll_source, "chacha20");
+        let decoded_resp = RollDicesResponse::try_from(proto_roll_resp.clone()).unwrap();
+        assert_eq!(decoded_resp.source, RollSource::ChaCha20Withheld);
+        assert_eq!(
+            v1::GetDiceRollResponse::from(roll_dice_resp).roll_source,
+            "chacha20"
+        );
+
+        proto_roll_resp.roll_source = String::new();
+        let decoded_resp = RollDicesResponse::try_from(proto_roll_resp.clone()).unwrap();
+        assert_eq!(decoded_resp.source, RollSource::Thread);
+
+        proto_roll_resp.roll_source = "chacha20:0909".to_string();
+        assert!(RollDicesResponse::try_from(proto_roll_resp).is_err());
     }
 
     #[tokio::test]
@@ -249,9 +1441,15 @@ mod test {
         let svc = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
 
         let roll_dice_resp = svc
-            .roll_dices(&RollDicesRequest {
-                dice_set: DiceSet::new(vec![Dice::D100].into_iter()),
-            })
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::new(vec![Dice::D100].into_iter()),
+                    fair_roll: None,
+                    metadata: RollMetadata::default(),
+                    idempotency_key: None,
+                },
+            )
             .await
             .unwrap();
 
@@ -262,7 +1460,10 @@ mod test {
             pb::common::dice::v1::DiceType::DiceType100
         );
 
-        let get_rolled_dice_resp = svc.get_dice_roll(&roll_dice_resp.id).await.unwrap();
+        let get_rolled_dice_resp = svc
+            .get_dice_roll(&Caller::Trusted, &roll_dice_resp.id)
+            .await
+            .unwrap();
         assert_eq!(get_rolled_dice_resp.id, roll_dice_resp.id);
         assert_eq!(
             get_rolled_dice_resp.rolled_dice_set,
@@ -276,4 +1477,364 @@ mod test {
             pb::common::dice::v1::DiceType::DiceType100
         );
     }
+
+    #[tokio::test]
+    async fn can_encode_and_decode_fair_dice_rolls() {
+        let svc = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let RollCommitment { id, commitment } = svc.commit_dice_roll().await.unwrap();
+
+        let proto_req = v1::RollDicesRequest::from(RollDicesRequest {
+            dice_set: DiceSet::from_str("2d6").unwrap(),
+            fair_roll: Some(FairRollRequest {
+                commitment_id: id.clone(),
+                client_seed: b"seed".to_vec(),
+            }),
+            metadata: RollMetadata::default(),
+            idempotency_key: None,
+        });
+        assert_eq!(proto_req.commitment_id, id.to_string());
+        let req = RollDicesRequest::try_from(proto_req).unwrap();
+        let roll_dice_resp = svc.roll_dices(&Caller::Trusted, &req).await.unwrap();
+
+        let mut proto_roll_resp = v1::RollDicesResponse::from(roll_dice_resp.clone());
+        let proto_proof = proto_roll_resp.proof.clone().unwrap();
+        assert_eq!(proto_proof.commitment, commitment.to_vec());
+        assert_eq!(proto_proof.client_seed, b"seed".to_vec());
+        assert_eq!(proto_roll_resp.commitment_id, id.to_string());
+        assert_ne!(proto_roll_resp.id, id.to_string());
+
+        let decoded_resp = RollDicesResponse::try_from(proto_roll_resp.clone()).unwrap();
+        assert_eq!(decoded_resp.proof, roll_dice_resp.proof);
+        assert_eq!(decoded_resp.commitment_id, Some(id));
+
+        proto_roll_resp.proof.as_mut().unwrap().commitment = vec![0; 32];
+        assert!(RollDicesResponse::try_from(proto_roll_resp).is_err());
+    }
+
+    #[tokio::test]
+    async fn can_encode_and_decode_ability_tests() {
+        let test = AbilityTest::new(DiceSet::from_str("2d20kl1").unwrap(), -1, 12).unwrap();
+        let proto_req = v1::ResolveTestRequest::from(ResolveTestRequest {
+            test: test.clone(),
+            metadata: RollMetadata::default(),
+        });
+        assert_eq!(proto_req.dice_set, "2d20kl1");
+        assert_eq!(proto_req.critical_threshold, 20);
+
+        let decoded_req = ResolveTestRequest::try_from(v1::ResolveTestRequest {
+            critical_threshold: 0,
+            ..proto_req
+        })
+        .unwrap();
+        assert_eq!(decoded_req.test, test);
+
+        let invalid_req = ResolveTestRequest::try_from(v1::ResolveTestRequest {
+            dice_set: "3d6".to_string(),
+            ..Default::default()
+        });
+        assert!(invalid_req.is_err());
+
+        let svc = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let resp = svc
+            .resolve_test(&Caller::Trusted, &decoded_req)
+            .await
+            .unwrap();
+        let proto_resp = v1::ResolveTestResponse::from(resp.clone());
+        let proto_outcome = proto_resp.outcome.unwrap();
+        assert_eq!(proto_outcome.natural, resp.outcome.natural());
+        assert_eq!(proto_outcome.margin, resp.outcome.margin());
+        assert_eq!(proto_outcome.success, resp.outcome.is_success());
+
+        let decoded_roll = RollDicesResponse::try_from(proto_resp.roll.unwrap()).unwrap();
+        assert_eq!(
+            test.outcome(&decoded_roll.rolled_dice_set).unwrap(),
+            resp.outcome
+        );
+    }
+
+    #[test]
+    fn can_encode_and_decode_roll_metadata() {
+        let metadata = RollMetadata {
+            label: Some("Stealth".to_string()),
+            rolled_by: None,
+            session_id: Some("session-3".to_string()),
+            visibility: Visibility::Private,
+            tags: vec!["skill".to_string()],
+            revealed_at: Some(UNIX_EPOCH + Duration::from_millis(1_750_000_000_000)),
+        };
+        let proto_metadata = v1::RollMetadata::from(metadata.clone());
+        assert_eq!(proto_metadata.rolled_by, "");
+        assert_eq!(proto_metadata.revealed_at_unix_ms, 1_750_000_000_000);
+        assert_eq!(proto_metadata.visibility(), v1::Visibility::Private);
+        assert_eq!(RollMetadata::from(proto_metadata), metadata);
+
+        let decoded_req = RollDicesRequest::try_from(v1::RollDicesRequest {
+            dice_set: "d20".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(decoded_req.metadata, RollMetadata::default());
+    }
+
+    #[tokio::test]
+    async fn can_encode_and_decode_dice_roll_listings() {
+        let svc = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        for notation in ["d20", "d20 + 2", "2d6"] {
+            svc.roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str(notation).unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata::default(),
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let req = ListDiceRollsRequest {
+            filter: RollFilter {
+                since: Some(UNIX_EPOCH + Duration::from_millis(1_750_000_000_000)),
+                dice: Some(Dice::D20),
+                ..Default::default()
+            },
+            after: None,
+            page_size: 1,
+        };
+        let proto_req = v1::ListDiceRollsRequest::from(req.clone());
+        assert_eq!(proto_req.filter.as_ref().unwrap().dice, "d20");
+        let decoded_req = ListDiceRollsRequest::try_from(proto_req).unwrap();
+        assert_eq!(decoded_req.filter, req.filter);
+
+        let page = svc
+            .list_dice_rolls(&Caller::Trusted, &decoded_req)
+            .await
+            .unwrap();
+        assert!(page.next_cursor.is_some());
+        let proto_page = v1::ListDiceRollsResponse::from(page.clone());
+        assert_eq!(ListDiceRollsResponse::try_from(proto_page).unwrap(), page);
+
+        let invalid = v1::ListDiceRollsRequest {
+            page_token: "not-an-id".to_string(),
+            ..Default::default()
+        };
+        assert!(ListDiceRollsRequest::try_from(invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn can_encode_and_decode_dice_roll_batches() {
+        let svc = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let req = RollDicesBatchRequest {
+            items: vec![
+                RollDicesBatchItem {
+                    dice_set: DiceSet::from_str("d20 + 2").unwrap(),
+                    label: Some("Goblin 1".to_string()),
+                },
+                RollDicesBatchItem {
+                    dice_set: DiceSet::from_str("4dF").unwrap(),
+                    label: None,
+                },
+            ],
+            metadata: RollMetadata {
+                label: Some("Fireball".to_string()),
+                ..Default::default()
+            },
+        };
+        let proto_req = v1::RollDicesBatchRequest::from(req.clone());
+        assert_eq!(proto_req.items[1].dice_set, "4dF");
+        assert!(proto_req.items[1].label.is_empty());
+
+        let decoded_req = RollDicesBatchRequest::try_from(proto_req).unwrap();
+        assert_eq!(decoded_req.metadata, req.metadata);
+        for (decoded, item) in decoded_req.items.iter().zip(&req.items) {
+            assert_eq!(decoded.dice_set, item.dice_set);
+            assert_eq!(decoded.label, item.label);
+        }
+
+        let resp = svc
+            .roll_dices_batch(&Caller::Trusted, &decoded_req)
+            .await
+            .unwrap();
+        let proto_resp = v1::RollDicesBatchResponse::from(resp.clone());
+        assert_eq!(proto_resp.rolls.len(), 2);
+        assert_eq!(RollDicesBatchResponse::try_from(proto_resp).unwrap(), resp);
+
+        let invalid = v1::RollDicesBatchRequest {
+            items: vec![v1::RollDicesBatchItem {
+                dice_set: "2d6 +".to_string(),
+                label: String::new(),
+            }],
+            metadata: None,
+        };
+        assert!(RollDicesBatchRequest::try_from(invalid).is_err());
+
+        let status = Status::from(Error::InvalidBatchItem(
+            3,
+            Box::new(Error::FromModel(DiceError::WayTooManyDices)),
+        ));
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().contains("dice set 3"));
+    }
+
+    #[test]
+    fn can_encode_and_decode_watch_requests() {
+        let req = WatchDiceRollsRequest {
+            session_id: Some("table-1".to_string()),
+            after: Some(RollId::new()),
+        };
+        let decoded_req =
+            WatchDiceRollsRequest::try_from(v1::WatchDiceRollsRequest::from(req.clone())).unwrap();
+        assert_eq!(decoded_req.session_id, req.session_id);
+        assert_eq!(decoded_req.after, req.after);
+
+        let decoded_req =
+            WatchDiceRollsRequest::try_from(v1::WatchDiceRollsRequest::default()).unwrap();
+        assert!(decoded_req.session_id.is_none());
+        assert!(decoded_req.after.is_none());
+    }
+
+    #[test]
+    fn can_encode_and_decode_fairness_reports() {
+        let mut counts = FaceCounts::new(Dice::Fudge);
+        for result in [1, 3, 3, 2] {
+            counts.add(result).unwrap();
+        }
+        let report = FairnessReport::new([&counts], 0.01);
+
+        let proto_report = v1::GetFairnessReportResponse::from(report.clone());
+        assert_eq!(proto_report.dices[0].dice, "dF");
+        assert_eq!(proto_report.dices[0].face_counts, vec![1, 1, 2]);
+        assert_eq!(FairnessReport::try_from(proto_report).unwrap(), report);
+
+        let unknown_dice = v1::GetFairnessReportResponse {
+            dices: vec![v1::DiceFairness {
+                dice: "d0".to_string(),
+                ..Default::default()
+            }],
+            significance: 0.01,
+        };
+        assert!(FairnessReport::try_from(unknown_dice).is_err());
+    }
+
+    #[tokio::test]
+    async fn can_serve_authenticated_callers() {
+        use v1::dice_service_server::DiceService as _;
+
+        use crate::services::dice::Identity;
+
+        fn request_as<T>(subject: &str, message: T) -> Request<T> {
+            let mut request = Request::new(message);
+            request
+                .extensions_mut()
+                .insert(Caller::Authenticated(Identity::player(subject)));
+            request
+        }
+
+        let sut = DiceServiceWrapper {
+            svc: Service::new(InMemoryDiceHistorySaver::default(), NoopMeter),
+        };
+
+        let roll = sut
+            .roll_dices(request_as(
+                "aldric",
+                v1::RollDicesRequest::from(RollDicesRequest {
+                    dice_set: DiceSet::from_str("d20").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata {
+                        visibility: Visibility::Private,
+                        ..Default::default()
+                    },
+                    idempotency_key: None,
+                }),
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(roll.metadata.unwrap().rolled_by, "aldric");
+
+        let get = |subject| {
+            request_as(
+                subject,
+                v1::GetDiceRollRequest {
+                    id: roll.id.clone(),
+                },
+            )
+        };
+        assert!(sut.get_dice_roll(get("aldric")).await.is_ok());
+        let status = sut.get_dice_roll(get("brunhild")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let list = |subject| request_as(subject, v1::ListDiceRollsRequest::default());
+        let listed = sut.list_dice_rolls(list("brunhild")).await.unwrap();
+        assert!(listed.into_inner().rolls.is_empty());
+
+        let reveal = |subject| {
+            request_as(
+                subject,
+                v1::RevealDiceRollRequest {
+                    id: roll.id.clone(),
+                },
+            )
+        };
+        let status = sut.reveal_dice_roll(reveal("brunhild")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        let revealed = sut
+            .reveal_dice_roll(reveal("aldric"))
+            .await
+            .unwrap()
+            .into_inner()
+            .roll
+            .unwrap();
+        let metadata = revealed.metadata.unwrap();
+        assert_eq!(metadata.visibility(), v1::Visibility::Public);
+        assert_ne!(metadata.revealed_at_unix_ms, 0);
+        let listed = sut.list_dice_rolls(list("brunhild")).await.unwrap();
+        assert_eq!(listed.into_inner().rolls.len(), 1);
+    }
+
+    #[test]
+    fn can_send_bearer_tokens() {
+        let mut sut = ClientInterceptor {
+            authorization: Some("Bearer aldric-key".parse().unwrap()),
+        };
+        let request = sut.call(Request::new(())).unwrap();
+        assert_eq!(
+            request.metadata().get(AUTHORIZATION_KEY).unwrap(),
+            "Bearer aldric-key"
+        );
+
+        let request = ClientInterceptor::default().call(Request::new(())).unwrap();
+        assert!(request.metadata().get(AUTHORIZATION_KEY).is_none());
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[test]
+    fn can_propagate_trace_context_in_metadata() {
+        use opentelemetry::propagation::TextMapPropagator;
+        use opentelemetry::trace::{
+            SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+        };
+        use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let context = opentelemetry::Context::new().with_remote_span_context(span_context.clone());
+        let propagator = TraceContextPropagator::new();
+
+        let mut metadata = MetadataMap::new();
+        propagator.inject_context(&context, &mut MetadataInjector(&mut metadata));
+        assert_eq!(
+            metadata.get("traceparent").unwrap(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+
+        let extracted = propagator.extract(&MetadataExtractor(&metadata));
+        assert_eq!(extracted.span().span_context(), &span_context);
+    }
 }
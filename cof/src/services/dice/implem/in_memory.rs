@@ -2,30 +2,181 @@
 //! This adapter main use is for tests and protoyping and does not perform long-lasting storage.
 
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::time::SystemTime;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use crate::model::dice::RolledDiceSet;
 use crate::services::dice::service::DiceHistorySaver;
-use crate::services::dice::{Error, RollId};
+use crate::services::dice::{
+    Error, ListDiceRollsRequest, ListDiceRollsResponse, RollDicesResponse, RollId, Visibility,
+};
 
 #[derive(Debug, Default)]
 pub struct InMemoryDiceHistorySaver {
-    repo: RwLock<HashMap<Uuid, RolledDiceSet>>,
+    // ordered by id so that the history is listed in the order of the rolls
+    repo: RwLock<BTreeMap<Uuid, RollDicesResponse>>,
+    commitments: RwLock<HashMap<Uuid, [u8; 32]>>,
+    // the id of the roll saved with each idempotency key, along with when it has been saved
+    idempotency_keys: RwLock<HashMap<String, (Uuid, SystemTime)>>,
 }
 
 #[async_trait]
 impl DiceHistorySaver for InMemoryDiceHistorySaver {
-    async fn save_roll(&self, id: &RollId, rolled_dice_set: &RolledDiceSet) -> Result<(), Error> {
+    async fn save_roll(&self, roll: &RollDicesResponse) -> Result<(), Error> {
         let mut hm = self.repo.write().await;
-        hm.entry(id.0).insert_entry(rolled_dice_set.clone());
+        hm.insert(roll.id.0, roll.clone());
         Ok(())
     }
 
-    async fn get_dice_roll(&self, id: &RollId) -> Result<RolledDiceSet, Error> {
+    async fn save_rolls(&self, rolls: &[RollDicesResponse]) -> Result<(), Error> {
+        let mut hm = self.repo.write().await;
+        hm.extend(rolls.iter().map(|roll| (roll.id.0, roll.clone())));
+        Ok(())
+    }
+
+    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error> {
+        let hm = self.repo.read().await;
+        let roll = hm.get(&id.0).ok_or(Error::NonExistingDiceRoll)?;
+        Ok(roll.clone())
+    }
+
+    async fn save_commitment(&self, id: &RollId, server_seed: &[u8; 32]) -> Result<(), Error> {
+        let mut hm = self.commitments.write().await;
+        hm.entry(id.0).insert_entry(*server_seed);
+        Ok(())
+    }
+
+    async fn take_commitment(&self, id: &RollId) -> Result<[u8; 32], Error> {
+        let mut hm = self.commitments.write().await;
+        hm.remove(&id.0).ok_or(Error::NonExistingCommitment)
+    }
+
+    async fn list_dice_rolls(
+        &self,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error> {
+        let limit = req.limit();
+        let lower = req
+            .after
+            .as_ref()
+            .map_or(Bound::Unbounded, |after| Bound::Excluded(after.0));
+
+        let hm = self.repo.read().await;
+        let rolls = hm
+            .range((lower, Bound::Unbounded))
+            .map(|(_, roll)| roll)
+            .filter(|roll| req.filter.matches(roll))
+            .take(limit + 1)
+            .cloned()
+            .collect();
+        Ok(ListDiceRollsResponse::from_rolls(rolls, limit))
+    }
+
+    async fn get_idempotent_roll(
+        &self,
+        key: &str,
+        since: SystemTime,
+    ) -> Result<Option<RollDicesResponse>, Error> {
+        let keys = self.idempotency_keys.read().await;
+        let Some((id, _)) = keys.get(key).filter(|(_, saved_at)| *saved_at >= since) else {
+            return Ok(None);
+        };
         let hm = self.repo.read().await;
-        let rolled_dice_set = hm.get(&id.0).ok_or(Error::NonExistingDiceRoll)?;
-        Ok(rolled_dice_set.clone())
+        Ok(hm.get(id).cloned())
+    }
+
+    async fn save_idempotent_roll(
+        &self,
+        key: &str,
+        roll: &RollDicesResponse,
+        since: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        // the keys are locked until the roll is saved so that a single roll is saved per key
+        let mut keys = self.idempotency_keys.write().await;
+        let mut hm = self.repo.write().await;
+        if let Some((id, _)) = keys.get(key).filter(|(_, saved_at)| *saved_at >= since) {
+            return hm.get(id).cloned().ok_or(Error::NonExistingDiceRoll);
+        }
+
+        keys.insert(key.to_string(), (roll.id.0, SystemTime::now()));
+        hm.insert(roll.id.0, roll.clone());
+        Ok(roll.clone())
+    }
+
+    async fn reveal_roll(
+        &self,
+        id: &RollId,
+        revealed_at: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        let mut hm = self.repo.write().await;
+        let roll = hm.get_mut(&id.0).ok_or(Error::NonExistingDiceRoll)?;
+        if roll.metadata.visibility.is_hidden() {
+            roll.metadata.visibility = Visibility::Public;
+            roll.metadata.revealed_at = Some(revealed_at);
+        }
+        Ok(roll.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::model::dice::{DiceSet, RollSource};
+    use crate::services::dice::RollMetadata;
+
+    fn make_roll() -> RollDicesResponse {
+        RollDicesResponse {
+            id: RollId::new(),
+            rolled_dice_set: DiceSet::from_str("d20").unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn can_save_idempotent_rolls() {
+        let sut = InMemoryDiceHistorySaver::default();
+        let since = SystemTime::now() - Duration::from_secs(60);
+
+        let (first, retry) = (make_roll(), make_roll());
+        assert_eq!(
+            sut.save_idempotent_roll("key", &first, since)
+                .await
+                .unwrap(),
+            first
+        );
+        assert_eq!(
+            sut.save_idempotent_roll("key", &retry, since)
+                .await
+                .unwrap(),
+            first
+        );
+        assert_eq!(
+            sut.get_idempotent_roll("key", since).await.unwrap(),
+            Some(first.clone())
+        );
+        assert!(matches!(
+            sut.get_dice_roll(&retry.id).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+        assert_eq!(sut.get_idempotent_roll("other", since).await.unwrap(), None);
+
+        // the key is forgotten once it is older than the retention window
+        let expired = SystemTime::now() + Duration::from_secs(1);
+        assert_eq!(sut.get_idempotent_roll("key", expired).await.unwrap(), None);
+        assert_eq!(
+            sut.save_idempotent_roll("key", &retry, expired)
+                .await
+                .unwrap(),
+            retry
+        );
+        assert_eq!(sut.get_dice_roll(&first.id).await.unwrap(), first);
     }
 }
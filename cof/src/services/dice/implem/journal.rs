@@ -0,0 +1,1190 @@
+//! Module providing a file journal Adapter for the [`DiceHistorySaver`]: every roll is appended
+//! to a journal of segment files, so that the history survives restarts without running a
+//! database server.
+//!
+//! The records are length-delimited protobuf messages holding the
+//! [`v1::RollDicesResponse`] of the gRPC API, along with the seed of the fair rolls that is
+//! withheld from the clients. The position of every roll is indexed in memory
+//! when the journal is opened, rolls being read back from the segments when they are fetched.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use prost::Message;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::grpc::pb::dice_api::v1;
+use super::grpc::{decode_unix_ms, encode_audited_roll, encode_unix_ms};
+use crate::services::dice::{
+    DEFAULT_IDEMPOTENCY_RETENTION, DiceHistorySaver, Error, ListDiceRollsRequest,
+    ListDiceRollsResponse, RollDicesResponse, RollId, Visibility,
+};
+
+/// The size from which the active segment is sealed and a new one is started.
+pub const DEFAULT_MAX_SEGMENT_LEN: u64 = 64 * 1024 * 1024;
+
+/// The number of sealed segments from which they are compacted when another one is sealed.
+pub const DEFAULT_COMPACTION_THRESHOLD: usize = 8;
+
+const SEGMENT_EXTENSION: &str = "journal";
+const COMPACTING_EXTENSION: &str = "compacting";
+
+/// `Durability` tells when the appended records are flushed to the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+    /// Every record is synced before the write returns, no roll is lost on a power failure.
+    #[default]
+    Always,
+    /// The records are synced on the first write once the interval has elapsed since the last
+    /// sync, the rolls of the last interval may be lost on a power failure.
+    Interval(Duration),
+    /// The records are synced whenever the operating system decides to.
+    OsManaged,
+}
+
+/// The configuration of a [`JournalRepo`].
+#[derive(Debug, Clone)]
+pub struct JournalConfig {
+    dir: PathBuf,
+    durability: Durability,
+    max_segment_len: u64,
+    compaction_threshold: usize,
+    idempotency_retention: Duration,
+}
+
+impl JournalConfig {
+    /// Creates the configuration of a journal stored in the given directory.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            durability: Durability::default(),
+            max_segment_len: DEFAULT_MAX_SEGMENT_LEN,
+            compaction_threshold: DEFAULT_COMPACTION_THRESHOLD,
+            idempotency_retention: DEFAULT_IDEMPOTENCY_RETENTION,
+        }
+    }
+
+    /// Replaces the [`Durability`] of the appended records.
+    #[must_use]
+    pub fn with_durability(self, durability: Durability) -> Self {
+        Self { durability, ..self }
+    }
+
+    /// Replaces the size from which the active segment is sealed.
+    #[must_use]
+    pub fn with_max_segment_len(self, max_segment_len: u64) -> Self {
+        Self {
+            max_segment_len,
+            ..self
+        }
+    }
+
+    /// Replaces the number of sealed segments from which they are compacted when another one
+    /// is sealed.
+    #[must_use]
+    pub fn with_compaction_threshold(self, compaction_threshold: usize) -> Self {
+        Self {
+            compaction_threshold,
+            ..self
+        }
+    }
+
+    /// Replaces how long the idempotency keys are kept, the expired ones being dropped when
+    /// the journal is compacted.
+    #[must_use]
+    pub fn with_idempotency_retention(self, idempotency_retention: Duration) -> Self {
+        Self {
+            idempotency_retention,
+            ..self
+        }
+    }
+}
+
+/// A record of the journal: either a roll, a new commitment or the use of a commitment.
+#[derive(Clone, PartialEq, Message)]
+struct JournalRecord {
+    #[prost(message, optional, tag = "1")]
+    roll: Option<v1::RollDicesResponse>,
+    #[prost(string, tag = "2")]
+    commitment_id: String,
+    /// The server seed of a new commitment, empty when the commitment is used.
+    #[prost(bytes = "vec", tag = "3")]
+    server_seed: Vec<u8>,
+    /// The idempotency key the roll has been saved with, empty when there is none.
+    #[prost(string, tag = "4")]
+    idempotency_key: String,
+    /// When the roll has been saved with its idempotency key, in milliseconds since the Unix
+    /// epoch.
+    #[prost(uint64, tag = "5")]
+    saved_at_unix_ms: u64,
+}
+
+/// The position of a record in the journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+    segment: u64,
+    offset: u64,
+    len: usize,
+}
+
+/// The in-memory index of the journal, rebuilt from the segments when it is opened.
+#[derive(Debug, Default)]
+struct Index {
+    rolls: BTreeMap<Uuid, Location>,
+    commitments: HashMap<Uuid, [u8; 32]>,
+    idempotency_keys: HashMap<String, (Uuid, SystemTime)>,
+}
+
+impl Index {
+    fn apply(&mut self, record: &JournalRecord, location: Location) -> Result<(), anyhow::Error> {
+        if let Some(roll) = &record.roll {
+            let id = Uuid::parse_str(&roll.id).context("cannot decode the id of a roll")?;
+            self.rolls.insert(id, location);
+            if !record.idempotency_key.is_empty() {
+                let saved_at = decode_unix_ms(record.saved_at_unix_ms).unwrap_or(UNIX_EPOCH);
+                self.idempotency_keys
+                    .insert(record.idempotency_key.clone(), (id, saved_at));
+            }
+            return Ok(());
+        }
+
+        let id = Uuid::parse_str(&record.commitment_id)
+            .context("cannot decode the id of a commitment")?;
+        if record.server_seed.is_empty() {
+            self.commitments.remove(&id);
+        } else {
+            let server_seed = record
+                .server_seed
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow!("the server seed stored is not 32 bytes long"))?;
+            self.commitments.insert(id, server_seed);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Journal {
+    index: Index,
+    active: File,
+    active_segment: u64,
+    active_len: u64,
+    sealed_segments: usize,
+    /// Set when a failed append may have left a partial record after `active_len`, which must
+    /// be truncated before appending again.
+    torn: bool,
+    last_sync: Instant,
+}
+
+#[derive(Debug)]
+pub struct JournalRepo {
+    config: JournalConfig,
+    journal: RwLock<Journal>,
+}
+
+impl JournalRepo {
+    /// Opens the journal of the given configuration, creating it when it does not exist, and
+    /// indexes the rolls it contains.
+    ///
+    /// An incomplete record at the end of the last segment, left by a crash in the middle of a
+    /// write, is truncated.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Underlying`] if the journal cannot be read or if one of its records is corrupted.
+    pub async fn open(config: JournalConfig) -> Result<Self, Error> {
+        fs::create_dir_all(&config.dir)
+            .await
+            .context("cannot create the directory of the journal")?;
+
+        let (index, segments) = load_index(&config.dir, true).await?;
+        let active_segment = segments.last().copied().unwrap_or(1);
+        let active = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(segment_path(&config.dir, active_segment))
+            .await
+            .context("cannot open the active segment of the journal")?;
+        let active_len = active
+            .metadata()
+            .await
+            .context("cannot read the length of the active segment of the journal")?
+            .len();
+
+        Ok(Self {
+            config,
+            journal: RwLock::new(Journal {
+                index,
+                active,
+                active_segment,
+                active_len,
+                sealed_segments: segments.len().saturating_sub(1),
+                torn: false,
+                last_sync: Instant::now(),
+            }),
+        })
+    }
+
+    /// Rewrites the sealed segments into a single one that only keeps the rolls, the
+    /// commitments not used yet and the idempotency keys not expired, the active segment being
+    /// left untouched.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Underlying`] if the segments cannot be rewritten.
+    pub async fn compact(&self) -> Result<(), Error> {
+        let mut journal = self.journal.write().await;
+        self.compact_sealed(&mut journal).await
+    }
+
+    async fn compact_sealed(&self, journal: &mut Journal) -> Result<(), Error> {
+        let dir = &self.config.dir;
+        let expired_before = SystemTime::now()
+            .checked_sub(self.config.idempotency_retention)
+            .unwrap_or(UNIX_EPOCH);
+
+        let sealed = list_segments(dir)
+            .await?
+            .into_iter()
+            .filter(|segment| *segment < journal.active_segment)
+            .collect::<Vec<_>>();
+        let Some(&target) = sealed.last() else {
+            return Ok(());
+        };
+
+        let mut compacted = Vec::new();
+        for &segment in &sealed {
+            let bytes = fs::read(segment_path(dir, segment))
+                .await
+                .context("cannot read a segment of the journal")?;
+            let (records, _) = decode_segment(&bytes)?;
+            for (offset, mut record) in records {
+                let live = match &record.roll {
+                    Some(roll) => Uuid::parse_str(&roll.id).is_ok_and(|id| {
+                        journal.index.rolls.get(&id).is_some_and(|location| {
+                            location.segment == segment && location.offset == offset
+                        })
+                    }),
+                    None => {
+                        !record.server_seed.is_empty()
+                            && Uuid::parse_str(&record.commitment_id)
+                                .is_ok_and(|id| journal.index.commitments.contains_key(&id))
+                    }
+                };
+                if live {
+                    // the roll of an expired idempotency key is kept, only the key is dropped
+                    let saved_at = decode_unix_ms(record.saved_at_unix_ms).unwrap_or(UNIX_EPOCH);
+                    if !record.idempotency_key.is_empty() && saved_at < expired_before {
+                        record.idempotency_key.clear();
+                        record.saved_at_unix_ms = 0;
+                    }
+                    record
+                        .encode_length_delimited(&mut compacted)
+                        .context("cannot encode a record of the journal while compacting it")?;
+                }
+            }
+        }
+
+        // the compacted segment replaces the last sealed one at once, a crash before the
+        // older ones are removed only leaves records that are replayed twice
+        let compacting = dir.join(format!("{target:020}.{COMPACTING_EXTENSION}"));
+        let mut file = File::create(&compacting)
+            .await
+            .context("cannot create the compacted segment of the journal")?;
+        file.write_all(&compacted)
+            .await
+            .context("cannot write the compacted segment of the journal")?;
+        file.sync_all()
+            .await
+            .context("cannot sync the compacted segment of the journal")?;
+        fs::rename(&compacting, segment_path(dir, target))
+            .await
+            .context("cannot replace the segments of the journal")?;
+        sync_dir(dir).await?;
+        for &segment in &sealed[..sealed.len() - 1] {
+            fs::remove_file(segment_path(dir, segment))
+                .await
+                .context("cannot remove a compacted segment of the journal")?;
+        }
+
+        journal.index = load_index(dir, false).await?.0;
+        journal.sealed_segments = 1;
+        Ok(())
+    }
+
+    /// Reads the roll stored at the given location.
+    async fn read_roll(&self, location: Location) -> Result<RollDicesResponse, Error> {
+        SegmentReader::new(&self.config.dir)
+            .read_roll(location)
+            .await
+    }
+
+    /// Reads the record stored at the given location.
+    async fn read_record(&self, location: Location) -> Result<JournalRecord, Error> {
+        SegmentReader::new(&self.config.dir)
+            .read_record(location)
+            .await
+    }
+
+    /// Appends the record to the active segment, sealing it first when it is full.
+    async fn append(
+        &self,
+        journal: &mut Journal,
+        record: &JournalRecord,
+    ) -> Result<Location, Error> {
+        let mut locations = self
+            .append_all(journal, std::slice::from_ref(record))
+            .await?;
+        Ok(locations.remove(0))
+    }
+
+    /// Appends the records to the active segment with a single write, sealing it first when
+    /// they do not fit in it, and returns their locations.
+    ///
+    /// The sealed segments are compacted once there are enough of them, and a failed write is
+    /// truncated so that the records appended next do not follow a partial one.
+    async fn append_all(
+        &self,
+        journal: &mut Journal,
+        records: &[JournalRecord],
+    ) -> Result<Vec<Location>, Error> {
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::with_capacity(records.len());
+        for record in records {
+            let len = record.encoded_len();
+            record
+                .encode_length_delimited(&mut bytes)
+                .context("cannot encode a record of the journal")?;
+            offsets.push((bytes.len() - len, len));
+        }
+
+        if journal.torn {
+            truncate_torn_append(journal).await?;
+        }
+
+        if journal.active_len > 0
+            && journal.active_len + bytes.len() as u64 > self.config.max_segment_len
+        {
+            journal
+                .active
+                .sync_all()
+                .await
+                .context("cannot sync the sealed segment of the journal")?;
+            let active_segment = journal.active_segment + 1;
+            journal.active = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(segment_path(&self.config.dir, active_segment))
+                .await
+                .context("cannot create a new segment of the journal")?;
+            journal.active_segment = active_segment;
+            journal.active_len = 0;
+            journal.sealed_segments += 1;
+
+            if journal.sealed_segments >= self.config.compaction_threshold {
+                // the records are still appended when the compaction fails, it is retried when
+                // the next segment is sealed
+                if let Err(e) = self.compact_sealed(journal).await {
+                    log::warn!("Cannot compact the journal: {e}");
+                }
+            }
+        }
+
+        let sync = match self.config.durability {
+            Durability::Always => true,
+            Durability::Interval(interval) => journal.last_sync.elapsed() >= interval,
+            Durability::OsManaged => false,
+        };
+        if let Err(e) = write_records(&mut journal.active, &bytes, sync).await {
+            journal.torn = true;
+            if let Err(truncate_error) = truncate_torn_append(journal).await {
+                log::error!("Cannot truncate the failed append to the journal: {truncate_error}");
+            }
+            return Err(e);
+        }
+        if sync {
+            journal.last_sync = Instant::now();
+        }
+
+        let locations = offsets
+            .into_iter()
+            .map(|(offset, len)| Location {
+                segment: journal.active_segment,
+                offset: journal.active_len + offset as u64,
+                len,
+            })
+            .collect();
+        journal.active_len += bytes.len() as u64;
+        Ok(locations)
+    }
+}
+
+#[async_trait]
+impl DiceHistorySaver for JournalRepo {
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", roll_id = %roll.id))
+    )]
+    async fn save_roll(&self, roll: &RollDicesResponse) -> Result<(), Error> {
+        let record = JournalRecord {
+            roll: Some(encode_audited_roll(roll.clone())),
+            ..Default::default()
+        };
+
+        let mut journal = self.journal.write().await;
+        let location = self.append(&mut journal, &record).await?;
+        journal.index.rolls.insert(*roll.id.as_ref(), location);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", rows = rolls.len()))
+    )]
+    async fn save_rolls(&self, rolls: &[RollDicesResponse]) -> Result<(), Error> {
+        let records = rolls
+            .iter()
+            .map(|roll| JournalRecord {
+                roll: Some(encode_audited_roll(roll.clone())),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut journal = self.journal.write().await;
+        let locations = self.append_all(&mut journal, &records).await?;
+        for (roll, location) in rolls.iter().zip(locations) {
+            journal.index.rolls.insert(*roll.id.as_ref(), location);
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", roll_id = %id))
+    )]
+    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error> {
+        let journal = self.journal.read().await;
+        let location = *journal
+            .index
+            .rolls
+            .get(id.as_ref())
+            .ok_or(Error::NonExistingDiceRoll)?;
+        self.read_roll(location).await
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", roll_id = %id))
+    )]
+    async fn save_commitment(&self, id: &RollId, server_seed: &[u8; 32]) -> Result<(), Error> {
+        let record = JournalRecord {
+            commitment_id: id.to_string(),
+            server_seed: server_seed.to_vec(),
+            ..Default::default()
+        };
+
+        let mut journal = self.journal.write().await;
+        self.append(&mut journal, &record).await?;
+        journal.index.commitments.insert(*id.as_ref(), *server_seed);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", roll_id = %id))
+    )]
+    async fn take_commitment(&self, id: &RollId) -> Result<[u8; 32], Error> {
+        let mut journal = self.journal.write().await;
+        let server_seed = *journal
+            .index
+            .commitments
+            .get(id.as_ref())
+            .ok_or(Error::NonExistingCommitment)?;
+
+        let record = JournalRecord {
+            commitment_id: id.to_string(),
+            ..Default::default()
+        };
+        self.append(&mut journal, &record).await?;
+        journal.index.commitments.remove(id.as_ref());
+        Ok(server_seed)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", rows = tracing::field::Empty))
+    )]
+    async fn list_dice_rolls(
+        &self,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error> {
+        let limit = req.limit();
+        let (since, until) = req.filter.id_bounds();
+        let lower = match (req.after.as_ref().map(AsRef::as_ref), since) {
+            (Some(after), Some(since)) if since > *after => Bound::Included(since),
+            (Some(after), _) => Bound::Excluded(*after),
+            (None, Some(since)) => Bound::Included(since),
+            (None, None) => Bound::Unbounded,
+        };
+        let upper = until.map_or(Bound::Unbounded, Bound::Excluded);
+
+        let journal = self.journal.read().await;
+        let mut reader = SegmentReader::new(&self.config.dir);
+        let mut rolls = Vec::new();
+        for location in journal.index.rolls.range((lower, upper)).map(|(_, l)| l) {
+            let roll = reader.read_roll(*location).await?;
+            if req.filter.matches(&roll) {
+                rolls.push(roll);
+                if rolls.len() > limit {
+                    break;
+                }
+            }
+        }
+        record_in_span!("rows", rolls.len());
+        Ok(ListDiceRollsResponse::from_rolls(rolls, limit))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal"))
+    )]
+    async fn get_idempotent_roll(
+        &self,
+        key: &str,
+        since: SystemTime,
+    ) -> Result<Option<RollDicesResponse>, Error> {
+        let journal = self.journal.read().await;
+        let location = journal
+            .index
+            .idempotency_keys
+            .get(key)
+            .filter(|(_, saved_at)| *saved_at >= since)
+            .and_then(|(id, _)| journal.index.rolls.get(id));
+        match location {
+            Some(location) => Ok(Some(self.read_roll(*location).await?)),
+            None => Ok(None),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", roll_id = %roll.id))
+    )]
+    async fn save_idempotent_roll(
+        &self,
+        key: &str,
+        roll: &RollDicesResponse,
+        since: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        let mut journal = self.journal.write().await;
+        if let Some((id, _)) = journal
+            .index
+            .idempotency_keys
+            .get(key)
+            .filter(|(_, saved_at)| *saved_at >= since)
+        {
+            let location = *journal
+                .index
+                .rolls
+                .get(id)
+                .ok_or(Error::NonExistingDiceRoll)?;
+            return self.read_roll(location).await;
+        }
+
+        let saved_at = SystemTime::now();
+        let record = JournalRecord {
+            roll: Some(encode_audited_roll(roll.clone())),
+            idempotency_key: key.to_string(),
+            saved_at_unix_ms: encode_unix_ms(Some(saved_at)),
+            ..Default::default()
+        };
+        let location = self.append(&mut journal, &record).await?;
+        journal.index.rolls.insert(*roll.id.as_ref(), location);
+        journal
+            .index
+            .idempotency_keys
+            .insert(key.to_string(), (*roll.id.as_ref(), saved_at));
+        Ok(roll.clone())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "journal", roll_id = %id))
+    )]
+    async fn reveal_roll(
+        &self,
+        id: &RollId,
+        revealed_at: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        let mut journal = self.journal.write().await;
+        let location = *journal
+            .index
+            .rolls
+            .get(id.as_ref())
+            .ok_or(Error::NonExistingDiceRoll)?;
+        let mut record = self.read_record(location).await?;
+        let mut roll = RollDicesResponse::try_from(
+            record
+                .roll
+                .take()
+                .context("the record of the journal is not a roll")?,
+        )
+        .context("cannot decode a roll of the journal")?;
+        if !roll.metadata.visibility.is_hidden() {
+            return Ok(roll);
+        }
+
+        // the revealed roll is appended again along with its idempotency key, superseding the
+        // hidden one
+        roll.metadata.visibility = Visibility::Public;
+        roll.metadata.revealed_at = Some(revealed_at);
+        record.roll = Some(encode_audited_roll(roll.clone()));
+        let location = self.append(&mut journal, &record).await?;
+        journal.index.rolls.insert(*id.as_ref(), location);
+        Ok(roll)
+    }
+
+    async fn check_health(&self) -> Result<(), Error> {
+        fs::metadata(&self.config.dir)
+            .await
+            .context("cannot reach the directory of the journal")?;
+        Ok(())
+    }
+
+    async fn close(&self) {
+        // the records not synced yet under the durability policy are synced before leaving
+        let journal = self.journal.read().await;
+        if let Err(e) = journal.active.sync_all().await {
+            log::error!("Cannot sync the journal when closing it: {e}");
+        }
+    }
+}
+
+/// Reads the records of the journal, keeping a handle on each segment it reads from so that
+/// reading many records does not open their segment every time.
+struct SegmentReader<'a> {
+    dir: &'a Path,
+    segments: HashMap<u64, File>,
+}
+
+impl<'a> SegmentReader<'a> {
+    fn new(dir: &'a Path) -> Self {
+        Self {
+            dir,
+            segments: HashMap::new(),
+        }
+    }
+
+    /// Reads the roll stored at the given location.
+    async fn read_roll(&mut self, location: Location) -> Result<RollDicesResponse, Error> {
+        let roll = self
+            .read_record(location)
+            .await?
+            .roll
+            .context("the record of the journal is not a roll")?;
+        Ok(RollDicesResponse::try_from(roll).context("cannot decode a roll of the journal")?)
+    }
+
+    /// Reads the record stored at the given location.
+    async fn read_record(&mut self, location: Location) -> Result<JournalRecord, Error> {
+        let file = match self.segments.entry(location.segment) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(
+                File::open(segment_path(self.dir, location.segment))
+                    .await
+                    .context("cannot open a segment of the journal")?,
+            ),
+        };
+        file.seek(SeekFrom::Start(location.offset))
+            .await
+            .context("cannot seek a record of the journal")?;
+        let mut bytes = vec![0; location.len];
+        file.read_exact(&mut bytes)
+            .await
+            .context("cannot read a record of the journal")?;
+
+        Ok(JournalRecord::decode(bytes.as_slice())
+            .context("cannot decode a record of the journal")?)
+    }
+}
+
+/// Writes the records at the end of the active segment, syncing them when `sync` is set.
+async fn write_records(active: &mut File, bytes: &[u8], sync: bool) -> Result<(), Error> {
+    active
+        .write_all(bytes)
+        .await
+        .context("cannot append a record to the journal")?;
+    active
+        .flush()
+        .await
+        .context("cannot append a record to the journal")?;
+    if sync {
+        active
+            .sync_data()
+            .await
+            .context("cannot sync the journal")?;
+    }
+    Ok(())
+}
+
+/// Truncates what a failed append may have written after the last complete record of the
+/// active segment.
+async fn truncate_torn_append(journal: &mut Journal) -> Result<(), Error> {
+    journal
+        .active
+        .set_len(journal.active_len)
+        .await
+        .context("cannot truncate a failed append to the journal")?;
+    journal.torn = false;
+    Ok(())
+}
+
+fn segment_path(dir: &Path, segment: u64) -> PathBuf {
+    dir.join(format!("{segment:020}.{SEGMENT_EXTENSION}"))
+}
+
+/// Returns the segments of the journal in the order they have been written.
+async fn list_segments(dir: &Path) -> Result<Vec<u64>, Error> {
+    let mut entries = fs::read_dir(dir)
+        .await
+        .context("cannot list the segments of the journal")?;
+    let mut segments = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .context("cannot list the segments of the journal")?
+    {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION) {
+            if let Some(segment) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            {
+                segments.push(segment);
+            }
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+/// Indexes the records of all the segments of the journal, truncating the incomplete record
+/// of the last segment when `repair` is set.
+async fn load_index(dir: &Path, repair: bool) -> Result<(Index, Vec<u64>), Error> {
+    let segments = list_segments(dir).await?;
+    let mut index = Index::default();
+    for (i, &segment) in segments.iter().enumerate() {
+        let path = segment_path(dir, segment);
+        let bytes = fs::read(&path)
+            .await
+            .context("cannot read a segment of the journal")?;
+        let (records, valid_len) = decode_segment(&bytes)?;
+        for (offset, record) in &records {
+            let location = Location {
+                segment,
+                offset: *offset,
+                len: record.encoded_len(),
+            };
+            index.apply(record, location)?;
+        }
+
+        if valid_len < bytes.len() {
+            if !repair || i + 1 < segments.len() {
+                return Err(
+                    anyhow!("the segment {} of the journal is truncated", path.display()).into(),
+                );
+            }
+            log::warn!(
+                "Truncating the incomplete record at the end of the journal {}",
+                path.display()
+            );
+            let file = OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .await
+                .context("cannot open the last segment of the journal")?;
+            file.set_len(valid_len as u64)
+                .await
+                .context("cannot truncate the last segment of the journal")?;
+            file.sync_all()
+                .await
+                .context("cannot sync the last segment of the journal")?;
+        }
+    }
+    Ok((index, segments))
+}
+
+/// Decodes the records of a segment along with the offset of each one, and returns the length
+/// of the complete records, the segment ending with an incomplete one when it is shorter than
+/// the segment.
+fn decode_segment(bytes: &[u8]) -> Result<(Vec<(u64, JournalRecord)>, usize), anyhow::Error> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let mut buf = &bytes[offset..];
+        let Ok(len) = prost::encoding::decode_varint(&mut buf) else {
+            break;
+        };
+        let prefix_len = bytes.len() - offset - buf.len();
+        let Some(message) = usize::try_from(len).ok().and_then(|len| buf.get(..len)) else {
+            break;
+        };
+
+        let record =
+            JournalRecord::decode(message).context("cannot decode a record of the journal")?;
+        records.push(((offset + prefix_len) as u64, record));
+        offset += prefix_len + message.len();
+    }
+    Ok((records, offset))
+}
+
+/// Syncs the directory so that the files created or renamed in it survive a power failure.
+async fn sync_dir(dir: &Path) -> Result<(), Error> {
+    let dir = File::open(dir)
+        .await
+        .context("cannot open the directory of the journal")?;
+    dir.sync_all()
+        .await
+        .context("cannot sync the directory of the journal")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use crate::model::dice::{DiceSet, FairnessProof, RollSource, Roller};
+    use crate::services::dice::{RollFilter, RollMetadata};
+
+    fn make_roll(notation: &str, session_id: &str) -> RollDicesResponse {
+        RollDicesResponse {
+            id: RollId::new(),
+            rolled_dice_set: DiceSet::from_str(notation).unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata {
+                session_id: Some(session_id.to_string()),
+                visibility: Visibility::GameMaster,
+                tags: vec![notation.to_string()],
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn open_journal(dir: &Path, max_segment_len: u64) -> JournalRepo {
+        let config = JournalConfig::new(dir)
+            .with_durability(Durability::OsManaged)
+            .with_max_segment_len(max_segment_len);
+        JournalRepo::open(config)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot open the journal: {e}"))
+    }
+
+    #[tokio::test]
+    async fn can_reopen_journal() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let sut = open_journal(dir.path(), DEFAULT_MAX_SEGMENT_LEN).await;
+        let rolls = [
+            make_roll("d20 + 4", "session-1"),
+            make_roll("(d8r1 + 2) * 2 - 2d4!", "session-2"),
+            make_roll("2d20kl1 + 4dF + d00", "session-1"),
+        ];
+        for roll in &rolls {
+            sut.save_roll(roll).await.unwrap();
+        }
+        let (used, unused) = (RollId::new(), RollId::new());
+        sut.save_commitment(&used, &[1; 32]).await.unwrap();
+        sut.save_commitment(&unused, &[2; 32]).await.unwrap();
+        assert_eq!(sut.take_commitment(&used).await.unwrap(), [1; 32]);
+        drop(sut);
+
+        let sut = open_journal(dir.path(), DEFAULT_MAX_SEGMENT_LEN).await;
+        for roll in &rolls {
+            assert_eq!(&sut.get_dice_roll(&roll.id).await.unwrap(), roll);
+        }
+        assert!(matches!(
+            sut.get_dice_roll(&RollId::new()).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+        assert!(matches!(
+            sut.take_commitment(&used).await,
+            Err(Error::NonExistingCommitment)
+        ));
+        assert_eq!(sut.take_commitment(&unused).await.unwrap(), [2; 32]);
+
+        let req = ListDiceRollsRequest {
+            filter: RollFilter {
+                session_id: Some("session-1".to_string()),
+                ..Default::default()
+            },
+            after: None,
+            page_size: 1,
+        };
+        let first_page = sut.list_dice_rolls(&req).await.unwrap();
+        assert_eq!(first_page.rolls, [rolls[0].clone()]);
+        let req = ListDiceRollsRequest {
+            after: first_page.next_cursor,
+            ..req
+        };
+        let last_page = sut.list_dice_rolls(&req).await.unwrap();
+        assert_eq!(last_page.rolls, [rolls[2].clone()]);
+        assert_eq!(last_page.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn can_save_batches_of_rolls() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let sut = open_journal(dir.path(), 1).await;
+        let roll = make_roll("d20", "session-1");
+        sut.save_roll(&roll).await.unwrap();
+        let batch = [
+            make_roll("d20 + 2", "session-1"),
+            make_roll("d20 + 2", "session-1"),
+            make_roll("3d6", "session-1"),
+        ];
+        sut.save_rolls(&batch).await.unwrap();
+        drop(sut);
+
+        // the batch is written at once, in a segment of its own
+        assert_eq!(list_segments(dir.path()).await.unwrap().len(), 2);
+        let sut = open_journal(dir.path(), 1).await;
+        let listed = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.rolls[0], roll);
+        assert_eq!(listed.rolls[1..], batch);
+    }
+
+    #[tokio::test]
+    async fn can_rotate_and_compact_segments() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // every roll is written to a segment of its own
+        let sut = open_journal(dir.path(), 1).await;
+        let commitment_id = RollId::new();
+        let proof = FairnessProof::new([3; 32], b"client".to_vec()).unwrap();
+        let mut roller = proof.roller();
+        let fair_roll = RollDicesResponse {
+            id: RollId::new(),
+            source: roller.source(),
+            rolled_dice_set: DiceSet::from_str("3d6")
+                .unwrap()
+                .roll_with(&mut roller)
+                .unwrap(),
+            proof: Some(proof),
+            commitment_id: Some(commitment_id.clone()),
+            metadata: RollMetadata::default(),
+        };
+        sut.save_commitment(&commitment_id, &[3; 32]).await.unwrap();
+        sut.take_commitment(&commitment_id).await.unwrap();
+        sut.save_roll(&fair_roll).await.unwrap();
+        let unused = RollId::new();
+        sut.save_commitment(&unused, &[4; 32]).await.unwrap();
+        let roll = make_roll("d6", "session-1");
+        sut.save_roll(&roll).await.unwrap();
+        assert_eq!(list_segments(dir.path()).await.unwrap().len(), 5);
+
+        sut.compact().await.unwrap();
+        assert_eq!(list_segments(dir.path()).await.unwrap(), [4, 5]);
+        assert_eq!(sut.get_dice_roll(&fair_roll.id).await.unwrap(), fair_roll);
+        assert_eq!(sut.get_dice_roll(&roll.id).await.unwrap(), roll);
+        drop(sut);
+
+        let sut = open_journal(dir.path(), 1).await;
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, [fair_roll, roll]);
+        assert_eq!(sut.take_commitment(&unused).await.unwrap(), [4; 32]);
+    }
+
+    #[tokio::test]
+    async fn can_compact_segments_as_they_are_sealed() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // every roll is written to a segment of its own, and the idempotency keys expire at once
+        let config = JournalConfig::new(dir.path())
+            .with_durability(Durability::OsManaged)
+            .with_max_segment_len(1)
+            .with_compaction_threshold(2)
+            .with_idempotency_retention(Duration::ZERO);
+        let sut = JournalRepo::open(config.clone()).await.unwrap();
+        let first = make_roll("d20", "session-1");
+        sut.save_idempotent_roll("key", &first, UNIX_EPOCH)
+            .await
+            .unwrap();
+        let mut rolls = vec![first];
+        for notation in ["d4", "d6", "d8"] {
+            let roll = make_roll(notation, "session-1");
+            sut.save_roll(&roll).await.unwrap();
+            rolls.push(roll);
+        }
+        assert_eq!(list_segments(dir.path()).await.unwrap(), [3, 4]);
+        assert_eq!(
+            sut.get_idempotent_roll("key", UNIX_EPOCH).await.unwrap(),
+            None
+        );
+        drop(sut);
+
+        let sut = JournalRepo::open(config).await.unwrap();
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, rolls);
+        assert_eq!(
+            sut.get_idempotent_roll("key", UNIX_EPOCH).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn can_reveal_rolls() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let sut = open_journal(dir.path(), 1).await;
+        let roll = make_roll("d20", "session-1");
+        sut.save_roll(&roll).await.unwrap();
+        let revealed_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+        let revealed = sut.reveal_roll(&roll.id, revealed_at).await.unwrap();
+        assert_eq!(revealed.metadata.visibility, Visibility::Public);
+        assert_eq!(revealed.metadata.revealed_at, Some(revealed_at));
+        assert_eq!(
+            sut.reveal_roll(&roll.id, SystemTime::now()).await.unwrap(),
+            revealed
+        );
+        assert!(matches!(
+            sut.reveal_roll(&RollId::new(), revealed_at).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+
+        sut.compact().await.unwrap();
+        drop(sut);
+        let sut = open_journal(dir.path(), 1).await;
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, [revealed]);
+    }
+
+    #[tokio::test]
+    async fn can_reopen_journal_with_idempotency_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let since = SystemTime::now() - Duration::from_secs(60);
+
+        let sut = open_journal(dir.path(), 1).await;
+        let (first, retry) = (make_roll("d20", "session-1"), make_roll("d20", "session-1"));
+        assert_eq!(
+            sut.save_idempotent_roll("key", &first, since)
+                .await
+                .unwrap(),
+            first
+        );
+        assert_eq!(
+            sut.save_idempotent_roll("key", &retry, since)
+                .await
+                .unwrap(),
+            first
+        );
+        let revealed_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+        let revealed = sut.reveal_roll(&first.id, revealed_at).await.unwrap();
+        sut.compact().await.unwrap();
+        drop(sut);
+
+        let sut = open_journal(dir.path(), 1).await;
+        assert_eq!(
+            sut.get_idempotent_roll("key", since).await.unwrap(),
+            Some(revealed)
+        );
+        let expired = SystemTime::now() + Duration::from_secs(1);
+        assert_eq!(sut.get_idempotent_roll("key", expired).await.unwrap(), None);
+        assert!(matches!(
+            sut.get_dice_roll(&retry.id).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_recover_from_torn_write() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let sut = open_journal(dir.path(), DEFAULT_MAX_SEGMENT_LEN).await;
+        let roll = make_roll("2d6 + 3", "session-1");
+        sut.save_roll(&roll).await.unwrap();
+        sut.save_roll(&make_roll("d20", "session-1")).await.unwrap();
+        drop(sut);
+
+        let path = segment_path(dir.path(), 1);
+        let len = fs::metadata(&path).await.unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).await.unwrap();
+        file.set_len(len - 3).await.unwrap();
+        drop(file);
+
+        let sut = open_journal(dir.path(), DEFAULT_MAX_SEGMENT_LEN).await;
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, [roll.clone()]);
+
+        let next_roll = make_roll("d12", "session-1");
+        sut.save_roll(&next_roll).await.unwrap();
+        drop(sut);
+        let sut = open_journal(dir.path(), DEFAULT_MAX_SEGMENT_LEN).await;
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, [roll, next_roll]);
+    }
+
+    #[tokio::test]
+    async fn can_recover_from_failed_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = segment_path(dir.path(), 1);
+
+        let sut = open_journal(dir.path(), DEFAULT_MAX_SEGMENT_LEN).await;
+        let roll = make_roll("2d6 + 3", "session-1");
+        sut.save_roll(&roll).await.unwrap();
+
+        // the write fails and leaves a partial record that cannot be truncated right away
+        sut.journal.write().await.active = File::open(&path).await.unwrap();
+        let failed_roll = make_roll("d20", "session-1");
+        assert!(sut.save_roll(&failed_roll).await.is_err());
+        assert!(sut.journal.read().await.torn);
+        let mut file = OpenOptions::new().append(true).open(&path).await.unwrap();
+        file.write_all(&[0x7f, 0x0a]).await.unwrap();
+        sut.journal.write().await.active = file;
+
+        let next_roll = make_roll("d12", "session-1");
+        sut.save_roll(&next_roll).await.unwrap();
+        assert!(!sut.journal.read().await.torn);
+        drop(sut);
+
+        let sut = open_journal(dir.path(), DEFAULT_MAX_SEGMENT_LEN).await;
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, [roll, next_roll]);
+    }
+}
@@ -1,44 +1,371 @@
 //! This module implements the trait [`DiceMeter`] with an OpenTelemetry meter.
 
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+
 use async_trait::async_trait;
 use opentelemetry::{
     KeyValue,
-    metrics::{Histogram, Meter},
+    metrics::{Counter, Histogram, Meter},
 };
 
-use crate::{model::dice::RolledDiceSet, services::dice::DiceMeter};
+use super::fairness::FairnessMonitor;
+use crate::model::dice::{RolledDiceSet, TestOutcome};
+use crate::services::dice::{DiceFairness, DiceMeter, Error};
+
+const ROLLS_COUNTER_NAME: &str = "cof.dice.rolls";
+const ROLLED_DICE_COUNTER_NAME: &str = "cof.dice.rolled";
+const RESULT_HISTOGRAM_NAME: &str = "cof.dice.result";
+const CRITICALS_COUNTER_NAME: &str = "cof.dice.test.criticals";
+const FUMBLES_COUNTER_NAME: &str = "cof.dice.test.fumbles";
+const REQUEST_DURATION_HISTOGRAM_NAME: &str = "cof.dice.request.duration";
+const REQUEST_ERRORS_COUNTER_NAME: &str = "cof.dice.request.errors";
+const FAIRNESS_CHI_SQUARED_GAUGE_NAME: &str = "cof.dice.fairness.chi_squared";
+const FAIRNESS_P_VALUE_GAUGE_NAME: &str = "cof.dice.fairness.p_value";
+
+/// The kind of the dice rolled, e.g. `d20` or `dF`.
+const DICE_TYPE_ATTRIBUTE_KEY: &str = "cof.dice.type";
+/// Whether the rolled dice has been discarded by a modifier of its term.
+const DICE_DISCARDED_ATTRIBUTE_KEY: &str = "cof.dice.discarded";
+/// The gRPC method of the call of the service.
+const RPC_METHOD_ATTRIBUTE_KEY: &str = "rpc.method";
+/// The kind of error a call of the service failed with.
+const ERROR_TYPE_ATTRIBUTE_KEY: &str = "error.type";
+
+/// The highest face with a bucket of its own in the result histogram, the one of the d100.
+const MAX_RESULT_BUCKET: u32 = 100;
 
-const ROLLED_DICE_HISTOGRAM_NAME: &str = "roll_result";
-const DICE_ATTRIBUTE_KEY: &str = "";
+/// The boundaries of the request duration buckets in seconds, from a cached roll to a slow
+/// database.
+const REQUEST_DURATION_BOUNDARIES: [f64; 10] =
+    [0.001, 0.002, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
 
 #[derive(Debug, Clone)]
 pub struct OpenTelemetryMeter {
-    roll_hist: Histogram<u64>,
+    meter: Meter,
+    results: Histogram<u64>,
+    rolls: Counter<u64>,
+    rolled_dices: Counter<u64>,
+    criticals: Counter<u64>,
+    fumbles: Counter<u64>,
+    request_duration: Histogram<f64>,
+    request_errors: Counter<u64>,
 }
 
 impl OpenTelemetryMeter {
     #[must_use]
     pub fn new(meter: &Meter) -> Self {
-        let roll_hist = meter
-            .u64_histogram(ROLLED_DICE_HISTOGRAM_NAME)
-            .with_description("")
+        // the bucket `(face - 1, face]` holds the rolls of the face, the faces of the custom
+        // dices larger than a d100 sharing the overflow bucket
+        let results = meter
+            .u64_histogram(RESULT_HISTOGRAM_NAME)
+            .with_description("The faces rolled on each kind of dice, numbered from 1")
+            .with_unit("{face}")
+            .with_boundaries((1..=MAX_RESULT_BUCKET).map(f64::from).collect())
+            .build();
+        let rolls = meter
+            .u64_counter(ROLLS_COUNTER_NAME)
+            .with_description("The number of dice sets rolled")
+            .with_unit("{roll}")
+            .build();
+        let rolled_dices = meter
+            .u64_counter(ROLLED_DICE_COUNTER_NAME)
+            .with_description("The number of dices rolled, rerolls and explosions included")
+            .with_unit("{dice}")
+            .build();
+        let criticals = meter
+            .u64_counter(CRITICALS_COUNTER_NAME)
+            .with_description("The number of ability tests resolved as critical successes")
+            .with_unit("{test}")
+            .build();
+        let fumbles = meter
+            .u64_counter(FUMBLES_COUNTER_NAME)
+            .with_description("The number of ability tests resolved as fumbles")
+            .with_unit("{test}")
+            .build();
+        let request_duration = meter
+            .f64_histogram(REQUEST_DURATION_HISTOGRAM_NAME)
+            .with_description("The duration of the calls of the dice service")
+            .with_unit("s")
+            .with_boundaries(REQUEST_DURATION_BOUNDARIES.to_vec())
+            .build();
+        let request_errors = meter
+            .u64_counter(REQUEST_ERRORS_COUNTER_NAME)
+            .with_description("The number of calls of the dice service that failed")
+            .with_unit("{request}")
             .build();
 
-        Self { roll_hist }
+        Self {
+            meter: meter.clone(),
+            results,
+            rolls,
+            rolled_dices,
+            criticals,
+            fumbles,
+            request_duration,
+            request_errors,
+        }
+    }
+
+    /// Exposes the chi-squared statistic and the p-value of each dice, as of the last periodic
+    /// check of the monitor, as gauges.
+    pub fn observe_fairness<M: DiceMeter>(&self, monitor: &FairnessMonitor<M>) {
+        let observe =
+            |name: &'static str, description: &'static str, value: fn(&DiceFairness) -> f64| {
+                let latest = Arc::clone(&monitor.latest);
+                self.meter
+                    .f64_observable_gauge(name)
+                    .with_description(description)
+                    .with_callback(move |observer| {
+                        let report = latest.lock().unwrap_or_else(PoisonError::into_inner);
+                        for fairness in &report.dices {
+                            observer.observe(
+                                value(fairness),
+                                &[KeyValue::new(
+                                    DICE_TYPE_ATTRIBUTE_KEY,
+                                    fairness.dice.to_string(),
+                                )],
+                            );
+                        }
+                    })
+                    .build()
+            };
+        observe(
+            FAIRNESS_CHI_SQUARED_GAUGE_NAME,
+            "The chi-squared statistic of the faces rolled against a fair dice",
+            |fairness| fairness.chi_squared,
+        );
+        observe(
+            FAIRNESS_P_VALUE_GAUGE_NAME,
+            "The probability for a fair dice to deviate as much from an even distribution",
+            |fairness| fairness.p_value,
+        );
+    }
+}
+
+/// Returns the kind of the error, used as the `error.type` of the failed calls.
+fn error_type(error: &Error) -> &'static str {
+    match error {
+        Error::NonExistingDiceRoll => "non_existing_dice_roll",
+        Error::RollIdParseError => "roll_id_parse_error",
+        Error::NonExistingCommitment => "non_existing_commitment",
+        Error::InvalidMetadata(_) => "invalid_metadata",
+        Error::RollFeedLagged => "roll_feed_lagged",
+        Error::FairnessNotMonitored => "fairness_not_monitored",
+        Error::InvalidIdempotencyKey(_) => "invalid_idempotency_key",
+        Error::IdempotencyKeyReused => "idempotency_key_reused",
+        Error::InvalidBatch(_) => "invalid_batch",
+        Error::InvalidBatchItem(..) => "invalid_batch_item",
+        Error::FromModel(_) => "invalid_dice_roll",
+        Error::Underlying(_) => "internal",
     }
 }
 
 #[async_trait]
 impl DiceMeter for OpenTelemetryMeter {
     async fn register_roll(&self, rolled_dice_set: &RolledDiceSet) {
+        self.rolls.add(1, &[]);
         for rolled_dice in rolled_dice_set.iter() {
-            self.roll_hist.record(
-                u64::from(rolled_dice.result()),
-                &[KeyValue::new(
-                    DICE_ATTRIBUTE_KEY,
-                    rolled_dice.dice().to_string(),
-                )],
-            );
+            let dice = rolled_dice.dice();
+            let attributes = [
+                KeyValue::new(DICE_TYPE_ATTRIBUTE_KEY, dice.to_string()),
+                KeyValue::new(DICE_DISCARDED_ATTRIBUTE_KEY, rolled_dice.is_discarded()),
+            ];
+            self.rolled_dices.add(1, &attributes);
+            self.results
+                .record(u64::from(rolled_dice.result()), &attributes);
+        }
+    }
+
+    async fn register_test(&self, outcome: &TestOutcome) {
+        if outcome.is_critical() {
+            self.criticals.add(1, &[]);
+        }
+        if outcome.is_fumble() {
+            self.fumbles.add(1, &[]);
+        }
+    }
+
+    async fn register_request(
+        &self,
+        method: &'static str,
+        duration: Duration,
+        error: Option<&Error>,
+    ) {
+        let mut attributes = vec![KeyValue::new(RPC_METHOD_ATTRIBUTE_KEY, method)];
+        if let Some(error) = error {
+            attributes.push(KeyValue::new(ERROR_TYPE_ATTRIBUTE_KEY, error_type(error)));
+            self.request_errors.add(1, &attributes);
+        }
+        self.request_duration
+            .record(duration.as_secs_f64(), &attributes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::{
+        InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+        data::{
+            AggregatedMetrics, HistogramDataPoint, MetricData, ResourceMetrics, ScopeMetrics,
+            SumDataPoint,
+        },
+    };
+
+    use super::*;
+    use crate::model::dice::{AbilityTest, DiceSet, ScriptedRoller};
+
+    fn setup() -> (SdkMeterProvider, InMemoryMetricExporter, OpenTelemetryMeter) {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let meter = OpenTelemetryMeter::new(&provider.meter("dice_service"));
+        (provider, exporter, meter)
+    }
+
+    fn collect(provider: &SdkMeterProvider, exporter: &InMemoryMetricExporter) -> ResourceMetrics {
+        provider.force_flush().unwrap();
+        exporter.get_finished_metrics().unwrap().pop().unwrap()
+    }
+
+    fn u64_data<'a>(metrics: &'a ResourceMetrics, name: &str) -> &'a MetricData<u64> {
+        let metric = metrics
+            .scope_metrics()
+            .flat_map(ScopeMetrics::metrics)
+            .find(|metric| metric.name() == name)
+            .unwrap_or_else(|| panic!("no metric named {name}"));
+        match metric.data() {
+            AggregatedMetrics::U64(data) => data,
+            _ => panic!("{name} does not hold u64 data"),
+        }
+    }
+
+    fn sum_points<'a>(metrics: &'a ResourceMetrics, name: &str) -> Vec<&'a SumDataPoint<u64>> {
+        match u64_data(metrics, name) {
+            MetricData::Sum(sum) => sum.data_points().collect(),
+            _ => panic!("{name} is not a sum"),
+        }
+    }
+
+    fn histogram_points<'a>(
+        metrics: &'a ResourceMetrics,
+        name: &str,
+    ) -> Vec<&'a HistogramDataPoint<u64>> {
+        match u64_data(metrics, name) {
+            MetricData::Histogram(histogram) => histogram.data_points().collect(),
+            _ => panic!("{name} is not a histogram"),
         }
     }
+
+    #[tokio::test]
+    async fn can_register_rolls() {
+        let (provider, exporter, sut) = setup();
+        let rolled = DiceSet::from_str("2d20kh1 + d4")
+            .unwrap()
+            .roll_with(&mut ScriptedRoller::new([3, 20, 4]))
+            .unwrap();
+        sut.register_roll(&rolled).await;
+
+        let metrics = collect(&provider, &exporter);
+        let rolls = sum_points(&metrics, ROLLS_COUNTER_NAME);
+        assert_eq!(rolls.iter().map(|p| p.value()).sum::<u64>(), 1);
+        let rolled_dices = sum_points(&metrics, ROLLED_DICE_COUNTER_NAME);
+        assert_eq!(rolled_dices.iter().map(|p| p.value()).sum::<u64>(), 3);
+        assert!(rolled_dices.iter().all(|p| {
+            p.attributes()
+                .any(|kv| kv.key.as_str() == DICE_TYPE_ATTRIBUTE_KEY)
+        }));
+
+        // a single histogram holds the faces of every kind of dice, told apart by their type
+        let results = histogram_points(&metrics, RESULT_HISTOGRAM_NAME);
+        assert_eq!(results.iter().map(|p| p.count()).sum::<u64>(), 3);
+        let faces_of = |dice: &str| {
+            let mut faces = vec![0; MAX_RESULT_BUCKET as usize + 1];
+            for point in results.iter().filter(|p| {
+                p.attributes()
+                    .any(|kv| *kv == KeyValue::new(DICE_TYPE_ATTRIBUTE_KEY, dice.to_string()))
+            }) {
+                assert_eq!(point.bounds().count(), MAX_RESULT_BUCKET as usize);
+                for (face, count) in point.bucket_counts().enumerate() {
+                    faces[face] += count;
+                }
+            }
+            faces
+        };
+        let d20_faces = faces_of("d20");
+        assert_eq!(d20_faces.iter().sum::<u64>(), 2);
+        assert_eq!(d20_faces[2], 1);
+        assert_eq!(d20_faces[19], 1);
+        let d4_faces = faces_of("d4");
+        assert_eq!(d4_faces.iter().sum::<u64>(), 1);
+        assert_eq!(d4_faces[3], 1);
+    }
+
+    #[tokio::test]
+    async fn can_observe_fairness() {
+        let (provider, exporter, sut) = setup();
+        let monitor = FairnessMonitor::new(sut.clone());
+        sut.observe_fairness(&monitor);
+        for _ in 0..10 {
+            let rolled = DiceSet::from_str("d4")
+                .unwrap()
+                .roll_with(&mut ScriptedRoller::new([4]))
+                .unwrap();
+            monitor.register_roll(&rolled).await;
+        }
+        monitor.check();
+
+        let metrics = collect(&provider, &exporter);
+        let metric = metrics
+            .scope_metrics()
+            .flat_map(ScopeMetrics::metrics)
+            .find(|metric| metric.name() == FAIRNESS_P_VALUE_GAUGE_NAME)
+            .unwrap();
+        let AggregatedMetrics::F64(MetricData::Gauge(gauge)) = metric.data() else {
+            panic!("{FAIRNESS_P_VALUE_GAUGE_NAME} is not a f64 gauge");
+        };
+        let points = gauge.data_points().collect::<Vec<_>>();
+        assert_eq!(points.len(), 1);
+        assert!(points[0].value() < 0.001);
+        assert!(
+            points[0]
+                .attributes()
+                .any(|kv| *kv == KeyValue::new(DICE_TYPE_ATTRIBUTE_KEY, "d4"))
+        );
+    }
+
+    #[tokio::test]
+    async fn can_register_tests_and_requests() {
+        let (provider, exporter, sut) = setup();
+        let test = AbilityTest::new(DiceSet::from_str("d20").unwrap(), 0, 10).unwrap();
+        for natural in [20, 1, 1, 12] {
+            let (_, outcome) = test.roll_with(&mut ScriptedRoller::new([natural])).unwrap();
+            sut.register_test(&outcome).await;
+        }
+        sut.register_request("RollDices", Duration::from_millis(3), None)
+            .await;
+        sut.register_request(
+            "GetDiceRoll",
+            Duration::from_millis(1),
+            Some(&Error::NonExistingDiceRoll),
+        )
+        .await;
+
+        let metrics = collect(&provider, &exporter);
+        assert_eq!(sum_points(&metrics, CRITICALS_COUNTER_NAME)[0].value(), 1);
+        assert_eq!(sum_points(&metrics, FUMBLES_COUNTER_NAME)[0].value(), 2);
+
+        let errors = sum_points(&metrics, REQUEST_ERRORS_COUNTER_NAME);
+        assert_eq!(errors.len(), 1);
+        let attributes = errors[0].attributes().collect::<Vec<_>>();
+        assert!(attributes.contains(&&KeyValue::new(RPC_METHOD_ATTRIBUTE_KEY, "GetDiceRoll")));
+        assert!(attributes.contains(&&KeyValue::new(
+            ERROR_TYPE_ATTRIBUTE_KEY,
+            "non_existing_dice_roll"
+        )));
+    }
 }
@@ -1,10 +1,15 @@
 use anyhow::{Context, anyhow};
-use sqlx::{PgPool, prelude::*};
-use std::sync::Arc;
+use sqlx::types::chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
 use tonic::async_trait;
+use uuid::Uuid;
 
-use crate::model::dice::{Dice, RolledDice, RolledDiceSet};
-use crate::services::dice::{DiceHistorySaver, Error, RollId};
+use super::sql::{DiceSetDbEntry, RolledDiceDbEntry};
+use crate::model::dice::{FairnessProof, RolledDice};
+use crate::services::dice::{
+    DiceHistorySaver, Error, ListDiceRollsRequest, ListDiceRollsResponse, RollDicesResponse, RollId,
+};
 
 #[derive(Debug)]
 pub struct PostgresRepo {
@@ -29,97 +34,437 @@ impl PostgresRepo {
     }
 }
 
-#[derive(FromRow)]
-struct RolledDiceDbEntry {
-    dice: String,
-    result: i64,
-}
+#[async_trait]
+impl DiceHistorySaver for PostgresRepo {
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", roll_id = %roll.id, rows = tracing::field::Empty)
+        )
+    )]
+    async fn save_roll(&self, roll: &RollDicesResponse) -> Result<(), Error> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("error starting a transaction on the database")?;
 
-impl TryFrom<RolledDiceDbEntry> for RolledDice {
-    type Error = anyhow::Error;
+        let rows_affected = insert_roll(&mut tx, roll).await?;
+        record_in_span!("rows", rows_affected);
 
-    fn try_from(value: RolledDiceDbEntry) -> Result<Self, Self::Error> {
-        let dice = Dice::try_from(value.dice.as_str())
-            .context("cannot decode the value of the Dice stored in the database")?;
-        let result = u32::try_from(value.result)
-            .context("cannot decode the value of the result of the roll stored in the database")?;
+        tx.commit()
+            .await
+            .context("error committing the dice roll into the database")?;
 
-        Ok(Self::new(dice, result))
+        Ok(())
     }
-}
-
-#[async_trait]
-impl DiceHistorySaver for PostgresRepo {
-    async fn save_roll(&self, id: &RollId, rolled_dice_set: &RolledDiceSet) -> Result<(), Error> {
-        let roll_ids = rolled_dice_set
-            .iter()
-            .map(|_| *id.as_ref())
-            .collect::<Vec<_>>();
-        let dices = rolled_dice_set
-            .iter()
-            .map(|rds| rds.dice().to_string())
-            .collect::<Vec<_>>();
-        let results = rolled_dice_set
-            .iter()
-            .map(|rds| i64::from(rds.result()))
-            .collect::<Vec<_>>();
 
-        let rows_affected = sqlx::query!(
-            r#"INSERT INTO dice_rolls (roll_id, dice, result) SELECT * FROM UNNEST(
-                $1::uuid[],
-                $2::VARCHAR(5)[],
-                $3::BIGINT[]
-            )"#,
-            &roll_ids,
-            &dices,
-            &results,
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", rows = tracing::field::Empty)
         )
-        .execute(&*self.pool)
-        .await
-        .context("error inserting entries into the database")?
-        .rows_affected();
+    )]
+    async fn save_rolls(&self, rolls: &[RollDicesResponse]) -> Result<(), Error> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("error starting a transaction on the database")?;
 
-        if rows_affected != (roll_ids.len() as u64) {
-            return Err(Error::Underlying(anyhow!(
-                "Postgres transaction error detected not all rolls have been persisted",
-            )));
+        let mut rows_affected = 0;
+        for roll in rolls {
+            rows_affected += insert_roll(&mut tx, roll).await?;
         }
+        record_in_span!("rows", rows_affected);
+
+        tx.commit()
+            .await
+            .context("error committing the dice rolls into the database")?;
 
         Ok(())
     }
 
-    async fn get_dice_roll(&self, id: &RollId) -> Result<RolledDiceSet, Error> {
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", roll_id = %id, rows = tracing::field::Empty)
+        )
+    )]
+    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error> {
         let roll_id = *id.as_ref();
 
+        let dice_set_entry = sqlx::query_as!(
+            DiceSetDbEntry,
+            r#"SELECT roll_id, notation, roll_source, server_seed, client_seed,
+                commitment_id, label, rolled_by, session_id, visibility, tags, revealed_at
+            FROM dice_sets WHERE roll_id = $1"#,
+            roll_id
+        )
+        .fetch_optional(&*self.pool)
+        .await
+        .context("error reading dice set from postgres database")?
+        .ok_or(Error::NonExistingDiceRoll)?;
+
         let rolled_dices = sqlx::query_as!(
             RolledDiceDbEntry,
-            r#"SELECT dice, result FROM dice_rolls WHERE roll_id = $1"#,
+            r#"SELECT roll_id, dice, result, discarded, origin FROM dice_rolls WHERE roll_id = $1 ORDER BY id"#,
             roll_id
         )
         .fetch_all(&*self.pool)
         .await
         .context("error reading dice rolls from postgres database")?;
 
-        let rolled_dices = rolled_dices
+        record_in_span!("rows", rolled_dices.len());
+
+        Ok(dice_set_entry.into_roll(rolled_dices)?)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", roll_id = %id)
+        )
+    )]
+    async fn save_commitment(&self, id: &RollId, server_seed: &[u8; 32]) -> Result<(), Error> {
+        sqlx::query!(
+            r#"INSERT INTO dice_commitments (roll_id, server_seed) VALUES ($1, $2)"#,
+            id.as_ref(),
+            server_seed.as_slice(),
+        )
+        .execute(&*self.pool)
+        .await
+        .context("error inserting the commitment into the database")?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", roll_id = %id)
+        )
+    )]
+    async fn take_commitment(&self, id: &RollId) -> Result<[u8; 32], Error> {
+        let server_seed = sqlx::query_scalar!(
+            r#"UPDATE dice_commitments SET used = TRUE WHERE roll_id = $1 AND NOT used RETURNING server_seed"#,
+            id.as_ref(),
+        )
+        .fetch_optional(&*self.pool)
+        .await
+        .context("error reading the commitment from postgres database")?
+        .ok_or(Error::NonExistingCommitment)?;
+
+        Ok(server_seed
+            .try_into()
+            .map_err(|_| anyhow!("the server seed stored is not 32 bytes long"))?)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", rows = tracing::field::Empty)
+        )
+    )]
+    async fn list_dice_rolls(
+        &self,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error> {
+        let limit = req.limit();
+        let (since, until) = req.filter.id_bounds();
+        let viewer = req.filter.viewer();
+
+        let dice_set_entries = sqlx::query_as!(
+            DiceSetDbEntry,
+            r#"SELECT roll_id, notation, roll_source, server_seed, client_seed,
+                commitment_id, label, rolled_by, session_id, visibility, tags, revealed_at
+            FROM dice_sets
+            WHERE ($1::uuid IS NULL OR roll_id > $1)
+                AND ($2::uuid IS NULL OR roll_id >= $2)
+                AND ($3::uuid IS NULL OR roll_id < $3)
+                AND ($4::TEXT IS NULL OR rolled_by = $4)
+                AND ($5::TEXT IS NULL OR session_id = $5)
+                AND ($6::VARCHAR(16) IS NULL OR EXISTS (
+                    SELECT 1 FROM dice_rolls
+                    WHERE dice_rolls.roll_id = dice_sets.roll_id AND dice_rolls.dice = $6
+                ))
+                AND ($8::TEXT IS NULL
+                    OR visibility = 'public'
+                    OR (visibility = 'gm' AND $9::BOOLEAN)
+                    OR (visibility = 'private' AND rolled_by = $8))
+            ORDER BY roll_id
+            LIMIT $7"#,
+            req.after.as_ref().map(AsRef::as_ref),
+            since,
+            until,
+            req.filter.rolled_by,
+            req.filter.session_id,
+            req.filter.dice.map(|dice| dice.to_string()),
+            i64::try_from(limit + 1).context("the page size is too large")?,
+            viewer.map(|(subject, _)| subject),
+            viewer.is_some_and(|(_, game_master)| game_master),
+        )
+        .fetch_all(&*self.pool)
+        .await
+        .context("error reading dice sets from postgres database")?;
+
+        record_in_span!("rows", dice_set_entries.len());
+        let roll_ids = dice_set_entries
+            .iter()
+            .map(|entry| entry.roll_id)
+            .collect::<Vec<_>>();
+        let mut rolled_dices = HashMap::<Uuid, Vec<RolledDiceDbEntry>>::new();
+        for entry in sqlx::query_as!(
+            RolledDiceDbEntry,
+            r#"SELECT roll_id, dice, result, discarded, origin FROM dice_rolls
+            WHERE roll_id = ANY($1) ORDER BY id"#,
+            &roll_ids,
+        )
+        .fetch_all(&*self.pool)
+        .await
+        .context("error reading dice rolls from postgres database")?
+        {
+            rolled_dices.entry(entry.roll_id).or_default().push(entry);
+        }
+
+        let rolls = dice_set_entries
             .into_iter()
-            .map(RolledDice::try_from)
+            .map(|entry| {
+                let dices = rolled_dices.remove(&entry.roll_id).unwrap_or_default();
+                entry.into_roll(dices)
+            })
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(ListDiceRollsResponse::from_rolls(rolls, limit))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "postgresql"))
+    )]
+    async fn get_idempotent_roll(
+        &self,
+        key: &str,
+        since: SystemTime,
+    ) -> Result<Option<RollDicesResponse>, Error> {
+        let roll_id = sqlx::query_scalar!(
+            r#"SELECT roll_id FROM idempotency_keys WHERE idempotency_key = $1 AND saved_at >= $2"#,
+            key,
+            DateTime::<Utc>::from(since),
+        )
+        .fetch_optional(&*self.pool)
+        .await
+        .context("error reading the idempotency key from postgres database")?;
+
+        match roll_id {
+            Some(roll_id) => Ok(Some(self.get_dice_roll(&RollId::from(roll_id)).await?)),
+            None => Ok(None),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", roll_id = %roll.id, rows = tracing::field::Empty)
+        )
+    )]
+    async fn save_idempotent_roll(
+        &self,
+        key: &str,
+        roll: &RollDicesResponse,
+        since: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("error starting a transaction on the database")?;
+
+        // the unique constraint on the key lets a single roll claim it, a concurrent claim
+        // waiting for this transaction to end, and an expired key being claimed again
+        let claimed = sqlx::query!(
+            r#"INSERT INTO idempotency_keys (idempotency_key, roll_id, saved_at) VALUES ($1, $2, NOW())
+            ON CONFLICT (idempotency_key) DO UPDATE
+                SET roll_id = excluded.roll_id, saved_at = excluded.saved_at
+                WHERE idempotency_keys.saved_at < $3"#,
+            key,
+            roll.id.as_ref(),
+            DateTime::<Utc>::from(since),
+        )
+        .execute(&mut *tx)
+        .await
+        .context("error inserting the idempotency key into the database")?
+        .rows_affected();
+
+        if claimed == 0 {
+            let roll_id = sqlx::query_scalar!(
+                r#"SELECT roll_id FROM idempotency_keys WHERE idempotency_key = $1"#,
+                key,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .context("error reading the idempotency key from postgres database")?;
+            tx.rollback()
+                .await
+                .context("error rolling back the transaction on the database")?;
+            return self.get_dice_roll(&RollId::from(roll_id)).await;
+        }
+
+        let rows_affected = insert_roll(&mut tx, roll).await?;
+        record_in_span!("rows", rows_affected);
+
+        tx.commit()
+            .await
+            .context("error committing the dice roll into the database")?;
+
+        Ok(roll.clone())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "postgresql", roll_id = %id)
+        )
+    )]
+    async fn reveal_roll(
+        &self,
+        id: &RollId,
+        revealed_at: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        sqlx::query!(
+            r#"UPDATE dice_sets SET visibility = 'public', revealed_at = $2
+            WHERE roll_id = $1 AND visibility <> 'public'"#,
+            id.as_ref(),
+            DateTime::<Utc>::from(revealed_at),
+        )
+        .execute(&*self.pool)
+        .await
+        .context("error revealing the dice set in the database")?;
+
+        self.get_dice_roll(id).await
+    }
+
+    async fn check_health(&self) -> Result<(), Error> {
+        sqlx::query("SELECT 1")
+            .execute(&*self.pool)
+            .await
+            .context("Cannot reach the Postgres database")?;
+        Ok(())
+    }
 
-        Ok(RolledDiceSet::new(rolled_dices.into_iter()))
+    async fn close(&self) {
+        self.pool.close().await;
     }
 }
 
+/// Inserts the roll and its dices in the transaction, returning the number of dices inserted.
+async fn insert_roll(conn: &mut PgConnection, roll: &RollDicesResponse) -> Result<u64, Error> {
+    let RollDicesResponse {
+        id,
+        rolled_dice_set,
+        source,
+        proof,
+        commitment_id,
+        metadata,
+    } = roll;
+    let roll_ids = rolled_dice_set
+        .iter()
+        .map(|_| *id.as_ref())
+        .collect::<Vec<_>>();
+    let dices = rolled_dice_set
+        .iter()
+        .map(|rds| rds.dice().to_string())
+        .collect::<Vec<_>>();
+    let results = rolled_dice_set
+        .iter()
+        .map(|rds| i64::from(rds.result()))
+        .collect::<Vec<_>>();
+    let discarded = rolled_dice_set
+        .iter()
+        .map(RolledDice::is_discarded)
+        .collect::<Vec<_>>();
+    let origins = rolled_dice_set
+        .iter()
+        .map(|rds| rds.origin().to_string())
+        .collect::<Vec<_>>();
+
+    sqlx::query!(
+        r#"INSERT INTO dice_sets (roll_id, notation, roll_source, server_seed, client_seed,
+            commitment_id, label, rolled_by, session_id, visibility, tags, revealed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#,
+        id.as_ref(),
+        rolled_dice_set.dice_set().to_string(),
+        source.to_string(),
+        proof.as_ref().map(|p| p.server_seed().as_slice()),
+        proof.as_ref().map(FairnessProof::client_seed),
+        commitment_id.as_ref().map(RollId::as_ref),
+        metadata.label,
+        metadata.rolled_by,
+        metadata.session_id,
+        metadata.visibility.to_string(),
+        &metadata.tags,
+        metadata.revealed_at.map(DateTime::<Utc>::from),
+    )
+    .execute(&mut *conn)
+    .await
+    .context("error inserting the dice set into the database")?;
+
+    let rows_affected = sqlx::query!(
+        r#"INSERT INTO dice_rolls (roll_id, dice, result, discarded, origin) SELECT * FROM UNNEST(
+            $1::uuid[],
+            $2::VARCHAR(16)[],
+            $3::BIGINT[],
+            $4::BOOLEAN[],
+            $5::VARCHAR(16)[]
+        )"#,
+        &roll_ids,
+        &dices,
+        &results,
+        &discarded,
+        &origins,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("error inserting entries into the database")?
+    .rows_affected();
+
+    if rows_affected != (roll_ids.len() as u64) {
+        return Err(Error::Underlying(anyhow!(
+            "Postgres transaction error detected not all rolls have been persisted",
+        )));
+    }
+
+    Ok(rows_affected)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use sqlx::PgPool;
+    use std::str::FromStr;
+    use std::time::{Duration, UNIX_EPOCH};
     use testcontainers::ContainerAsync;
     use testcontainers_modules::postgres::Postgres;
     use testcontainers_modules::testcontainers::runners::AsyncRunner;
-    use uuid::Uuid;
 
-    use crate::model::dice::{Dice, DiceSet};
+    use crate::model::dice::{ChaChaRoller, Dice, DiceSet, RollSource, Roller};
+    use crate::services::dice::{Caller, Identity, RollFilter, RollMetadata, Visibility};
 
     async fn make_postgres_pool() -> (ContainerAsync<Postgres>, PgPool) {
         // startup the module
@@ -147,16 +492,356 @@ mod tests {
             .await
             .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
 
-        let id = RollId::from(Uuid::now_v7());
-        let rolled_dice_set = DiceSet::new([Dice::D100, Dice::D10].iter().copied())
-            .roll()
-            .unwrap();
+        let roll = RollDicesResponse {
+            id: RollId::from(Uuid::now_v7()),
+            rolled_dice_set: DiceSet::new([Dice::D100, Dice::D10].iter().copied())
+                .roll()
+                .unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata::default(),
+        };
 
-        let save_result = sut.save_roll(&id, &rolled_dice_set).await;
+        let save_result = sut.save_roll(&roll).await;
         assert!(save_result.is_ok());
 
-        let get_rolled_dice_result = sut.get_dice_roll(&id).await;
+        let get_rolled_dice_result = sut.get_dice_roll(&roll.id).await;
         assert!(get_rolled_dice_result.is_ok());
-        assert_eq!(get_rolled_dice_result.unwrap(), rolled_dice_set);
+        assert_eq!(get_rolled_dice_result.unwrap(), roll);
+    }
+
+    #[tokio::test]
+    async fn can_save_and_get_dice_rolls_with_modifiers() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = PostgresRepo::new(pg_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
+
+        let mut roller = ChaChaRoller::from_seed([5; 32]);
+        let source = roller.source();
+        let roll = RollDicesResponse {
+            id: RollId::from(Uuid::now_v7()),
+            rolled_dice_set: DiceSet::from_str("(d8r1 + 2) * 2 - 2d4! + 2d20kl1 + 4d6rr1!!dl1")
+                .unwrap()
+                .roll_with(&mut roller)
+                .unwrap(),
+            source,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata::default(),
+        };
+
+        let save_result = sut.save_roll(&roll).await;
+        assert!(save_result.is_ok());
+
+        let fetched_roll = sut.get_dice_roll(&roll.id).await.unwrap();
+        assert_eq!(fetched_roll, roll);
+        assert_eq!(
+            fetched_roll.rolled_dice_set.total(),
+            roll.rolled_dice_set.total()
+        );
+
+        let missing_roll = sut.get_dice_roll(&RollId::from(Uuid::now_v7())).await;
+        assert!(matches!(missing_roll, Err(Error::NonExistingDiceRoll)));
+    }
+
+    #[tokio::test]
+    async fn can_save_and_get_fair_dice_rolls() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = PostgresRepo::new(pg_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
+
+        let id = RollId::from(Uuid::now_v7());
+        sut.save_commitment(&id, &[7; 32]).await.unwrap();
+        let server_seed = sut.take_commitment(&id).await.unwrap();
+        assert_eq!(server_seed, [7; 32]);
+        assert!(matches!(
+            sut.take_commitment(&id).await,
+            Err(Error::NonExistingCommitment)
+        ));
+
+        let proof = FairnessProof::new(server_seed, b"client".to_vec()).unwrap();
+        let mut roller = proof.roller();
+        let roll = RollDicesResponse {
+            id: RollId::from(Uuid::now_v7()),
+            source: roller.source(),
+            rolled_dice_set: DiceSet::from_str("3d6")
+                .unwrap()
+                .roll_with(&mut roller)
+                .unwrap(),
+            proof: Some(proof),
+            commitment_id: Some(id),
+            metadata: RollMetadata::default(),
+        };
+        sut.save_roll(&roll).await.unwrap();
+        assert_eq!(sut.get_dice_roll(&roll.id).await.unwrap(), roll);
+    }
+
+    #[tokio::test]
+    async fn can_save_and_get_special_dice_rolls() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = PostgresRepo::new(pg_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
+
+        let roll = RollDicesResponse {
+            id: RollId::from(Uuid::now_v7()),
+            rolled_dice_set: DiceSet::from_str("4dF + d00 + d10 + 2d1000!")
+                .unwrap()
+                .roll()
+                .unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata::default(),
+        };
+        sut.save_roll(&roll).await.unwrap();
+        assert_eq!(sut.get_dice_roll(&roll.id).await.unwrap(), roll);
+    }
+
+    #[tokio::test]
+    async fn can_save_and_get_dice_rolls_with_metadata() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = PostgresRepo::new(pg_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
+
+        let roll = RollDicesResponse {
+            id: RollId::from(Uuid::now_v7()),
+            rolled_dice_set: DiceSet::from_str("d20 + 5").unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata {
+                label: Some("Attack with longsword".to_string()),
+                rolled_by: Some("Aldric".to_string()),
+                session_id: None,
+                visibility: Visibility::GameMaster,
+                tags: vec!["combat".to_string(), "attack".to_string()],
+                revealed_at: None,
+            },
+        };
+        sut.save_roll(&roll).await.unwrap();
+        assert_eq!(sut.get_dice_roll(&roll.id).await.unwrap(), roll);
+    }
+
+    #[tokio::test]
+    async fn can_list_dice_rolls() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = PostgresRepo::new(pg_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
+
+        let mut rolls = Vec::new();
+        for (notation, session_id) in [
+            ("d20", "session-1"),
+            ("2d6", "session-1"),
+            ("d20 + 4", "session-2"),
+            ("d20! + d4", "session-1"),
+            ("4dF", "session-1"),
+        ] {
+            let roll = RollDicesResponse {
+                id: RollId::from(Uuid::now_v7()),
+                rolled_dice_set: DiceSet::from_str(notation).unwrap().roll().unwrap(),
+                source: RollSource::Thread,
+                proof: None,
+                commitment_id: None,
+                metadata: RollMetadata {
+                    session_id: Some(session_id.to_string()),
+                    ..Default::default()
+                },
+            };
+            sut.save_roll(&roll).await.unwrap();
+            rolls.push(roll);
+        }
+
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, rolls);
+        assert_eq!(all.next_cursor, None);
+
+        let mut req = ListDiceRollsRequest {
+            filter: RollFilter {
+                dice: Some(Dice::D20),
+                session_id: Some("session-1".to_string()),
+                ..Default::default()
+            },
+            after: None,
+            page_size: 1,
+        };
+        let first_page = sut.list_dice_rolls(&req).await.unwrap();
+        assert_eq!(first_page.rolls, vec![rolls[0].clone()]);
+        assert_eq!(first_page.next_cursor, Some(rolls[0].id.clone()));
+
+        req.after = first_page.next_cursor;
+        let last_page = sut.list_dice_rolls(&req).await.unwrap();
+        assert_eq!(last_page.rolls, vec![rolls[3].clone()]);
+        assert_eq!(last_page.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn can_list_and_reveal_hidden_dice_rolls() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = PostgresRepo::new(pg_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
+
+        let mut rolls = Vec::new();
+        for (rolled_by, visibility) in [
+            ("gm", Visibility::GameMaster),
+            ("aldric", Visibility::Private),
+            ("brunhild", Visibility::Private),
+            ("gm", Visibility::Public),
+        ] {
+            let roll = RollDicesResponse {
+                id: RollId::from(Uuid::now_v7()),
+                rolled_dice_set: DiceSet::from_str("d20").unwrap().roll().unwrap(),
+                source: RollSource::Thread,
+                proof: None,
+                commitment_id: None,
+                metadata: RollMetadata {
+                    rolled_by: Some(rolled_by.to_string()),
+                    visibility,
+                    ..Default::default()
+                },
+            };
+            sut.save_roll(&roll).await.unwrap();
+            rolls.push(roll);
+        }
+
+        let listed_by = async |identity| {
+            let req = ListDiceRollsRequest {
+                filter: RollFilter {
+                    visible_to: Caller::Authenticated(identity),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            sut.list_dice_rolls(&req).await.unwrap().rolls
+        };
+        assert_eq!(
+            listed_by(Identity::player("aldric")).await,
+            [rolls[1].clone(), rolls[3].clone()]
+        );
+        assert_eq!(
+            listed_by(Identity::game_master("gm")).await,
+            [rolls[0].clone(), rolls[3].clone()]
+        );
+
+        let revealed_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+        let revealed = sut.reveal_roll(&rolls[0].id, revealed_at).await.unwrap();
+        assert_eq!(revealed.metadata.visibility, Visibility::Public);
+        assert_eq!(revealed.metadata.revealed_at, Some(revealed_at));
+        assert_eq!(
+            sut.reveal_roll(&rolls[0].id, SystemTime::now())
+                .await
+                .unwrap(),
+            revealed
+        );
+        assert_eq!(
+            listed_by(Identity::player("brunhild")).await,
+            [revealed, rolls[2].clone(), rolls[3].clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn can_save_idempotent_rolls() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = Arc::new(
+            PostgresRepo::new(pg_pool)
+                .await
+                .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}")),
+        );
+        let since = SystemTime::now() - Duration::from_secs(60);
+        let make_roll = || RollDicesResponse {
+            id: RollId::from(Uuid::now_v7()),
+            rolled_dice_set: DiceSet::from_str("d20").unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata::default(),
+        };
+
+        // concurrent requests with the same key save a single roll
+        let rolls = (0..8).map(|_| make_roll()).collect::<Vec<_>>();
+        let handles = rolls.iter().cloned().map(|roll| {
+            let sut = sut.clone();
+            tokio::spawn(async move { sut.save_idempotent_roll("key", &roll, since).await })
+        });
+        let mut saved = Vec::new();
+        for handle in handles {
+            saved.push(handle.await.unwrap().unwrap());
+        }
+        let first = saved[0].clone();
+        assert!(rolls.contains(&first));
+        assert!(saved.iter().all(|roll| *roll == first));
+        for roll in rolls.iter().filter(|roll| roll.id != first.id) {
+            assert!(matches!(
+                sut.get_dice_roll(&roll.id).await,
+                Err(Error::NonExistingDiceRoll)
+            ));
+        }
+        assert_eq!(
+            sut.get_idempotent_roll("key", since).await.unwrap(),
+            Some(first.clone())
+        );
+        assert_eq!(sut.get_idempotent_roll("other", since).await.unwrap(), None);
+
+        // the key is claimed again once it is older than the retention window
+        let expired = SystemTime::now() + Duration::from_secs(1);
+        assert_eq!(sut.get_idempotent_roll("key", expired).await.unwrap(), None);
+        let retry = make_roll();
+        assert_eq!(
+            sut.save_idempotent_roll("key", &retry, expired)
+                .await
+                .unwrap(),
+            retry
+        );
+        assert_eq!(sut.get_dice_roll(&first.id).await.unwrap(), first);
+    }
+
+    #[tokio::test]
+    async fn can_save_batches_of_rolls() {
+        let (_node, pg_pool) = make_postgres_pool().await;
+        let sut = PostgresRepo::new(pg_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate Postgres Repo: {e}"));
+        let rolls = ["d20 + 2", "d20 + 2", "3d6"].map(|notation| RollDicesResponse {
+            id: RollId::new(),
+            rolled_dice_set: DiceSet::from_str(notation).unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata {
+                label: Some(format!("Goblin {notation}")),
+                tags: vec!["fireball".to_string()],
+                ..Default::default()
+            },
+        });
+        sut.save_rolls(&rolls).await.unwrap();
+
+        let listed = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.rolls, rolls);
+
+        // none of the rolls is saved when one of them cannot be
+        let duplicate = [
+            RollDicesResponse {
+                id: RollId::new(),
+                ..rolls[0].clone()
+            },
+            rolls[1].clone(),
+        ];
+        assert!(sut.save_rolls(&duplicate).await.is_err());
+        assert!(matches!(
+            sut.get_dice_roll(&duplicate[0].id).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
     }
 }
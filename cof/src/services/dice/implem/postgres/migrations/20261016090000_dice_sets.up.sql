@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS dice_sets (
+  roll_id uuid PRIMARY KEY,
+  notation TEXT NOT NULL
+);
+
+-- Rolls persisted before this migration were plain sums of dices
+INSERT INTO dice_sets (roll_id, notation)
+SELECT roll_id, string_agg(dice, ' + ' ORDER BY id)
+FROM dice_rolls
+GROUP BY roll_id
+ON CONFLICT DO NOTHING;
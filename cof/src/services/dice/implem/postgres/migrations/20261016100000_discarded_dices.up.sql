@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE dice_rolls ADD COLUMN IF NOT EXISTS discarded BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE dice_rolls DROP COLUMN origin;
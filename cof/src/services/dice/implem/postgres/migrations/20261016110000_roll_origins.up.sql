@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE dice_rolls ADD COLUMN IF NOT EXISTS origin VARCHAR(16) NOT NULL DEFAULT 'initial';
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE dice_sets ADD COLUMN IF NOT EXISTS roll_source TEXT NOT NULL DEFAULT 'thread';
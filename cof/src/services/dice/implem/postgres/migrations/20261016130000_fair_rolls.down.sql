@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE dice_sets DROP COLUMN commitment_id;
+ALTER TABLE dice_sets DROP COLUMN client_seed;
+ALTER TABLE dice_sets DROP COLUMN server_seed;
+DROP TABLE dice_commitments;
@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS dice_commitments (
+  roll_id uuid PRIMARY KEY,
+  server_seed BYTEA NOT NULL,
+  used BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+ALTER TABLE dice_sets ADD COLUMN IF NOT EXISTS server_seed BYTEA;
+ALTER TABLE dice_sets ADD COLUMN IF NOT EXISTS client_seed BYTEA;
+ALTER TABLE dice_sets ADD COLUMN IF NOT EXISTS commitment_id uuid;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE dice_rolls ALTER COLUMN dice TYPE VARCHAR(16);
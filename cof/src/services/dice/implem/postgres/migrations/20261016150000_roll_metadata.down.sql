@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE dice_sets
+  DROP COLUMN tags,
+  DROP COLUMN visibility,
+  DROP COLUMN session_id,
+  DROP COLUMN rolled_by,
+  DROP COLUMN label;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+ALTER TABLE dice_sets
+  ADD COLUMN label TEXT,
+  ADD COLUMN rolled_by TEXT,
+  ADD COLUMN session_id TEXT,
+  ADD COLUMN visibility VARCHAR(16) NOT NULL DEFAULT 'public',
+  ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
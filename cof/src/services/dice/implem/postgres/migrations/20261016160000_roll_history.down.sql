@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS dice_sets_rolled_by_roll_id_idx;
+DROP INDEX IF EXISTS dice_sets_session_id_roll_id_idx;
+DROP INDEX IF EXISTS dice_rolls_dice_roll_id_idx;
+DROP INDEX IF EXISTS dice_rolls_roll_id_idx;
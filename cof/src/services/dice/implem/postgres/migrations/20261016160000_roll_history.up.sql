@@ -0,0 +1,5 @@
+-- Add up migration script here
+CREATE INDEX IF NOT EXISTS dice_rolls_roll_id_idx ON dice_rolls (roll_id);
+CREATE INDEX IF NOT EXISTS dice_rolls_dice_roll_id_idx ON dice_rolls (dice, roll_id);
+CREATE INDEX IF NOT EXISTS dice_sets_session_id_roll_id_idx ON dice_sets (session_id, roll_id);
+CREATE INDEX IF NOT EXISTS dice_sets_rolled_by_roll_id_idx ON dice_sets (rolled_by, roll_id);
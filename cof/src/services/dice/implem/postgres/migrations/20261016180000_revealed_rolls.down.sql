@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE dice_sets DROP COLUMN revealed_at;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE dice_sets ADD COLUMN revealed_at TIMESTAMPTZ;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+  idempotency_key TEXT PRIMARY KEY,
+  roll_id uuid NOT NULL,
+  saved_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,112 @@
+//! Module containing the database entries shared by the SQL adapters of the
+//! [`crate::services::dice::service::DiceHistorySaver`] and their decoding into rolls.
+
+use anyhow::{Context, anyhow};
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Utc};
+use std::str::FromStr;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+use crate::model::dice::{
+    Dice, DiceSet, FairnessProof, RollOrigin, RollSource, RolledDice, RolledDiceSet,
+};
+use crate::services::dice::{RollDicesResponse, RollId, RollMetadata, Visibility};
+
+#[derive(FromRow)]
+pub(super) struct DiceSetDbEntry {
+    pub(super) roll_id: Uuid,
+    pub(super) notation: String,
+    pub(super) roll_source: String,
+    pub(super) server_seed: Option<Vec<u8>>,
+    pub(super) client_seed: Option<Vec<u8>>,
+    pub(super) commitment_id: Option<Uuid>,
+    pub(super) label: Option<String>,
+    pub(super) rolled_by: Option<String>,
+    pub(super) session_id: Option<String>,
+    pub(super) visibility: String,
+    pub(super) revealed_at: Option<DateTime<Utc>>,
+    // filled by the adapters that store the tags of a roll in their own table
+    #[sqlx(skip)]
+    pub(super) tags: Vec<String>,
+}
+
+impl DiceSetDbEntry {
+    /// Decodes the roll out of the dice set and the dices rolled for it.
+    pub(super) fn into_roll(
+        self,
+        rolled_dices: Vec<RolledDiceDbEntry>,
+    ) -> Result<RollDicesResponse, anyhow::Error> {
+        let dice_set = DiceSet::from_str(&self.notation)
+            .context("cannot decode the dice set stored in the database")?;
+        let source = RollSource::from_str(&self.roll_source)
+            .context("cannot decode the source of the roll stored in the database")?;
+        let proof = self
+            .server_seed
+            .map(|server_seed| -> Result<_, anyhow::Error> {
+                let server_seed = server_seed
+                    .try_into()
+                    .map_err(|_| anyhow!("the server seed stored is not 32 bytes long"))?;
+                Ok(FairnessProof::new(
+                    server_seed,
+                    self.client_seed.unwrap_or_default(),
+                )?)
+            })
+            .transpose()
+            .context("cannot decode the fairness proof stored in the database")?;
+        let metadata = RollMetadata {
+            label: self.label,
+            rolled_by: self.rolled_by,
+            session_id: self.session_id,
+            visibility: Visibility::from_str(&self.visibility)
+                .context("cannot decode the visibility of the roll stored in the database")?,
+            tags: self.tags,
+            revealed_at: self.revealed_at.map(SystemTime::from),
+        };
+
+        let rolled_dices = rolled_dices
+            .into_iter()
+            .map(RolledDice::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let rolled_dice_set = RolledDiceSet::try_from_parts(&dice_set, rolled_dices)
+            .context("the dice rolls stored in the database do not match their dice set")?;
+
+        Ok(RollDicesResponse {
+            id: RollId::from(self.roll_id),
+            rolled_dice_set,
+            source,
+            proof,
+            commitment_id: self.commitment_id.map(RollId::from),
+            metadata,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct RolledDiceDbEntry {
+    pub(super) roll_id: Uuid,
+    pub(super) dice: String,
+    pub(super) result: i64,
+    pub(super) discarded: bool,
+    pub(super) origin: String,
+}
+
+impl TryFrom<RolledDiceDbEntry> for RolledDice {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RolledDiceDbEntry) -> Result<Self, Self::Error> {
+        let dice = Dice::try_from(value.dice.as_str())
+            .context("cannot decode the value of the Dice stored in the database")?;
+        let result = u32::try_from(value.result)
+            .context("cannot decode the value of the result of the roll stored in the database")?;
+
+        let origin = RollOrigin::try_from(value.origin.as_str())
+            .context("cannot decode the origin of the roll stored in the database")?;
+
+        if value.discarded {
+            Ok(Self::new_discarded(dice, result).with_origin(origin))
+        } else {
+            Ok(Self::new(dice, result).with_origin(origin))
+        }
+    }
+}
@@ -0,0 +1,783 @@
+//! Module providing the `SQLite` Adapter for the [`DiceHistorySaver`], for the deployments on a
+//! single machine where running a Postgres server is not worth it.
+//!
+//! The queries are checked at runtime: the `sqlx::query!` macros check all the queries of the
+//! crate against the single database of `DATABASE_URL`, which is the Postgres one.
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use sqlx::types::chrono::{DateTime, Utc};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use super::sql::{DiceSetDbEntry, RolledDiceDbEntry};
+use crate::model::dice::FairnessProof;
+use crate::services::dice::{
+    DiceHistorySaver, Error, ListDiceRollsRequest, ListDiceRollsResponse, RollDicesResponse, RollId,
+};
+
+/// The number of dices inserted per statement, keeping the bound parameters of each statement
+/// well below the limit of `SQLite`.
+const INSERT_CHUNK_LEN: usize = 1000;
+
+#[derive(Debug)]
+pub struct SqliteRepo {
+    pool: Arc<SqlitePool>,
+}
+
+impl SqliteRepo {
+    /// Create a `SQLite` Repository that implements the [`DiceHistorySaver`]
+    ///
+    /// # Errors
+    ///
+    /// The Error cans be a `sqlx::MigrateError` if the migration fails.
+    pub async fn new(sqlite_pool: SqlitePool) -> Result<Self, Error> {
+        sqlx::migrate!("./src/services/dice/implem/sqlite/migrations")
+            .run(&sqlite_pool)
+            .await
+            .context("Failed to run the SQLite migration when starting the repository")?;
+
+        Ok(Self {
+            pool: Arc::new(sqlite_pool),
+        })
+    }
+
+    /// Reads the dices and the tags of the given dice sets to decode their rolls.
+    async fn read_rolls(
+        &self,
+        dice_set_entries: Vec<DiceSetDbEntry>,
+    ) -> Result<Vec<RollDicesResponse>, Error> {
+        if dice_set_entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut rolled_dices_query = QueryBuilder::<Sqlite>::new(
+            "SELECT roll_id, dice, result, discarded, origin FROM dice_rolls WHERE roll_id IN (",
+        );
+        let mut roll_ids = rolled_dices_query.separated(", ");
+        for entry in &dice_set_entries {
+            roll_ids.push_bind(entry.roll_id);
+        }
+        rolled_dices_query.push(") ORDER BY id");
+
+        let mut rolled_dices = HashMap::<Uuid, Vec<RolledDiceDbEntry>>::new();
+        for entry in rolled_dices_query
+            .build_query_as::<RolledDiceDbEntry>()
+            .fetch_all(&*self.pool)
+            .await
+            .context("error reading dice rolls from sqlite database")?
+        {
+            rolled_dices.entry(entry.roll_id).or_default().push(entry);
+        }
+
+        let mut tags_query = QueryBuilder::<Sqlite>::new(
+            "SELECT roll_id, tag FROM dice_set_tags WHERE roll_id IN (",
+        );
+        let mut roll_ids = tags_query.separated(", ");
+        for entry in &dice_set_entries {
+            roll_ids.push_bind(entry.roll_id);
+        }
+        tags_query.push(") ORDER BY roll_id, position");
+
+        let mut tags = HashMap::<Uuid, Vec<String>>::new();
+        for (roll_id, tag) in tags_query
+            .build_query_as::<(Uuid, String)>()
+            .fetch_all(&*self.pool)
+            .await
+            .context("error reading roll tags from sqlite database")?
+        {
+            tags.entry(roll_id).or_default().push(tag);
+        }
+
+        Ok(dice_set_entries
+            .into_iter()
+            .map(|mut entry| {
+                entry.tags = tags.remove(&entry.roll_id).unwrap_or_default();
+                let dices = rolled_dices.remove(&entry.roll_id).unwrap_or_default();
+                entry.into_roll(dices)
+            })
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+#[async_trait]
+impl DiceHistorySaver for SqliteRepo {
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", roll_id = %roll.id, rows = tracing::field::Empty)
+        )
+    )]
+    async fn save_roll(&self, roll: &RollDicesResponse) -> Result<(), Error> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("error starting a transaction on the database")?;
+
+        let rows_affected = insert_roll(&mut tx, roll).await?;
+        record_in_span!("rows", rows_affected);
+
+        tx.commit()
+            .await
+            .context("error committing the dice roll into the database")?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", rows = tracing::field::Empty)
+        )
+    )]
+    async fn save_rolls(&self, rolls: &[RollDicesResponse]) -> Result<(), Error> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("error starting a transaction on the database")?;
+
+        let mut rows_affected = 0;
+        for roll in rolls {
+            rows_affected += insert_roll(&mut tx, roll).await?;
+        }
+        record_in_span!("rows", rows_affected);
+
+        tx.commit()
+            .await
+            .context("error committing the dice rolls into the database")?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", roll_id = %id, rows = tracing::field::Empty)
+        )
+    )]
+    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error> {
+        let dice_set_entry = sqlx::query_as::<_, DiceSetDbEntry>(
+            r"SELECT roll_id, notation, roll_source, server_seed, client_seed,
+                commitment_id, label, rolled_by, session_id, visibility, revealed_at
+            FROM dice_sets WHERE roll_id = ?",
+        )
+        .bind(id.as_ref())
+        .fetch_optional(&*self.pool)
+        .await
+        .context("error reading dice set from sqlite database")?
+        .ok_or(Error::NonExistingDiceRoll)?;
+
+        let roll = self
+            .read_rolls(vec![dice_set_entry])
+            .await?
+            .pop()
+            .ok_or(Error::NonExistingDiceRoll)?;
+        record_in_span!("rows", roll.rolled_dice_set.iter().count());
+        Ok(roll)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", roll_id = %id)
+        )
+    )]
+    async fn save_commitment(&self, id: &RollId, server_seed: &[u8; 32]) -> Result<(), Error> {
+        sqlx::query(r"INSERT INTO dice_commitments (roll_id, server_seed) VALUES (?, ?)")
+            .bind(id.as_ref())
+            .bind(server_seed.as_slice())
+            .execute(&*self.pool)
+            .await
+            .context("error inserting the commitment into the database")?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", roll_id = %id)
+        )
+    )]
+    async fn take_commitment(&self, id: &RollId) -> Result<[u8; 32], Error> {
+        let server_seed = sqlx::query_scalar::<_, Vec<u8>>(
+            r"UPDATE dice_commitments SET used = TRUE WHERE roll_id = ? AND NOT used RETURNING server_seed",
+        )
+        .bind(id.as_ref())
+        .fetch_optional(&*self.pool)
+        .await
+        .context("error reading the commitment from sqlite database")?
+        .ok_or(Error::NonExistingCommitment)?;
+
+        Ok(server_seed
+            .try_into()
+            .map_err(|_| anyhow!("the server seed stored is not 32 bytes long"))?)
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", rows = tracing::field::Empty)
+        )
+    )]
+    async fn list_dice_rolls(
+        &self,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error> {
+        let limit = req.limit();
+        let (since, until) = req.filter.id_bounds();
+        let viewer = req.filter.viewer();
+
+        let dice_set_entries = sqlx::query_as::<_, DiceSetDbEntry>(
+            r"SELECT roll_id, notation, roll_source, server_seed, client_seed,
+                commitment_id, label, rolled_by, session_id, visibility, revealed_at
+            FROM dice_sets
+            WHERE (?1 IS NULL OR roll_id > ?1)
+                AND (?2 IS NULL OR roll_id >= ?2)
+                AND (?3 IS NULL OR roll_id < ?3)
+                AND (?4 IS NULL OR rolled_by = ?4)
+                AND (?5 IS NULL OR session_id = ?5)
+                AND (?6 IS NULL OR EXISTS (
+                    SELECT 1 FROM dice_rolls
+                    WHERE dice_rolls.roll_id = dice_sets.roll_id AND dice_rolls.dice = ?6
+                ))
+                AND (?8 IS NULL
+                    OR visibility = 'public'
+                    OR (visibility = 'gm' AND ?9)
+                    OR (visibility = 'private' AND rolled_by = ?8))
+            ORDER BY roll_id
+            LIMIT ?7",
+        )
+        .bind(req.after.as_ref().map(AsRef::<Uuid>::as_ref))
+        .bind(since)
+        .bind(until)
+        .bind(&req.filter.rolled_by)
+        .bind(&req.filter.session_id)
+        .bind(req.filter.dice.map(|dice| dice.to_string()))
+        .bind(i64::try_from(limit + 1).context("the page size is too large")?)
+        .bind(viewer.map(|(subject, _)| subject))
+        .bind(viewer.is_some_and(|(_, game_master)| game_master))
+        .fetch_all(&*self.pool)
+        .await
+        .context("error reading dice sets from sqlite database")?;
+
+        record_in_span!("rows", dice_set_entries.len());
+        let rolls = self.read_rolls(dice_set_entries).await?;
+        Ok(ListDiceRollsResponse::from_rolls(rolls, limit))
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(db.system = "sqlite"))
+    )]
+    async fn get_idempotent_roll(
+        &self,
+        key: &str,
+        since: SystemTime,
+    ) -> Result<Option<RollDicesResponse>, Error> {
+        let roll_id = sqlx::query_scalar::<_, Uuid>(
+            r"SELECT roll_id FROM idempotency_keys WHERE idempotency_key = ? AND saved_at >= ?",
+        )
+        .bind(key)
+        .bind(unix_ms(since)?)
+        .fetch_optional(&*self.pool)
+        .await
+        .context("error reading the idempotency key from sqlite database")?;
+
+        match roll_id {
+            Some(roll_id) => Ok(Some(self.get_dice_roll(&RollId::from(roll_id)).await?)),
+            None => Ok(None),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", roll_id = %roll.id, rows = tracing::field::Empty)
+        )
+    )]
+    async fn save_idempotent_roll(
+        &self,
+        key: &str,
+        roll: &RollDicesResponse,
+        since: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("error starting a transaction on the database")?;
+
+        // the primary key lets a single roll claim the key, an expired key being claimed again
+        let claimed = sqlx::query(
+            r"INSERT INTO idempotency_keys (idempotency_key, roll_id, saved_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT (idempotency_key) DO UPDATE
+                SET roll_id = excluded.roll_id, saved_at = excluded.saved_at
+                WHERE idempotency_keys.saved_at < ?4",
+        )
+        .bind(key)
+        .bind(roll.id.as_ref())
+        .bind(unix_ms(SystemTime::now())?)
+        .bind(unix_ms(since)?)
+        .execute(&mut *tx)
+        .await
+        .context("error inserting the idempotency key into the database")?
+        .rows_affected();
+
+        if claimed == 0 {
+            let roll_id = sqlx::query_scalar::<_, Uuid>(
+                r"SELECT roll_id FROM idempotency_keys WHERE idempotency_key = ?",
+            )
+            .bind(key)
+            .fetch_one(&mut *tx)
+            .await
+            .context("error reading the idempotency key from sqlite database")?;
+            tx.rollback()
+                .await
+                .context("error rolling back the transaction on the database")?;
+            return self.get_dice_roll(&RollId::from(roll_id)).await;
+        }
+
+        let rows_affected = insert_roll(&mut tx, roll).await?;
+        record_in_span!("rows", rows_affected);
+
+        tx.commit()
+            .await
+            .context("error committing the dice roll into the database")?;
+
+        Ok(roll.clone())
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(db.system = "sqlite", roll_id = %id)
+        )
+    )]
+    async fn reveal_roll(
+        &self,
+        id: &RollId,
+        revealed_at: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        sqlx::query(
+            r"UPDATE dice_sets SET visibility = 'public', revealed_at = ?
+            WHERE roll_id = ? AND visibility <> 'public'",
+        )
+        .bind(DateTime::<Utc>::from(revealed_at))
+        .bind(id.as_ref())
+        .execute(&*self.pool)
+        .await
+        .context("error revealing the dice set in the database")?;
+
+        self.get_dice_roll(id).await
+    }
+
+    async fn check_health(&self) -> Result<(), Error> {
+        sqlx::query("SELECT 1")
+            .execute(&*self.pool)
+            .await
+            .context("Cannot reach the SQLite database")?;
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+}
+
+/// Inserts the roll, its tags and its dices in the transaction, returning the number of dices
+/// inserted.
+async fn insert_roll(conn: &mut SqliteConnection, roll: &RollDicesResponse) -> Result<u64, Error> {
+    let RollDicesResponse {
+        id,
+        rolled_dice_set,
+        source,
+        proof,
+        commitment_id,
+        metadata,
+    } = roll;
+
+    sqlx::query(
+        r"INSERT INTO dice_sets (roll_id, notation, roll_source, server_seed, client_seed,
+            commitment_id, label, rolled_by, session_id, visibility, revealed_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(id.as_ref())
+    .bind(rolled_dice_set.dice_set().to_string())
+    .bind(source.to_string())
+    .bind(proof.as_ref().map(|p| p.server_seed().as_slice()))
+    .bind(proof.as_ref().map(FairnessProof::client_seed))
+    .bind(commitment_id.as_ref().map(RollId::as_ref))
+    .bind(&metadata.label)
+    .bind(&metadata.rolled_by)
+    .bind(&metadata.session_id)
+    .bind(metadata.visibility.to_string())
+    .bind(metadata.revealed_at.map(DateTime::<Utc>::from))
+    .execute(&mut *conn)
+    .await
+    .context("error inserting the dice set into the database")?;
+
+    if !metadata.tags.is_empty() {
+        QueryBuilder::<Sqlite>::new("INSERT INTO dice_set_tags (roll_id, position, tag) ")
+            .push_values((0_i64..).zip(&metadata.tags), |mut row, (position, tag)| {
+                row.push_bind(id.as_ref())
+                    .push_bind(position)
+                    .push_bind(tag);
+            })
+            .build()
+            .execute(&mut *conn)
+            .await
+            .context("error inserting the tags of the roll into the database")?;
+    }
+
+    let rolled_dices = rolled_dice_set.iter().collect::<Vec<_>>();
+    let mut rows_affected = 0;
+    for chunk in rolled_dices.chunks(INSERT_CHUNK_LEN) {
+        rows_affected += QueryBuilder::<Sqlite>::new(
+            "INSERT INTO dice_rolls (roll_id, dice, result, discarded, origin) ",
+        )
+        .push_values(chunk, |mut row, rd| {
+            row.push_bind(id.as_ref())
+                .push_bind(rd.dice().to_string())
+                .push_bind(i64::from(rd.result()))
+                .push_bind(rd.is_discarded())
+                .push_bind(rd.origin().to_string());
+        })
+        .build()
+        .execute(&mut *conn)
+        .await
+        .context("error inserting entries into the database")?
+        .rows_affected();
+    }
+
+    if rows_affected != (rolled_dices.len() as u64) {
+        return Err(Error::Underlying(anyhow!(
+            "SQLite transaction error detected not all rolls have been persisted",
+        )));
+    }
+
+    Ok(rows_affected)
+}
+
+/// Returns the milliseconds elapsed since the Unix epoch, the instants of the idempotency keys
+/// being stored as integers so that they are compared as such.
+fn unix_ms(time: SystemTime) -> Result<i64, Error> {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(i64::try_from(elapsed.as_millis()).context("the instant is too far in the future")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use crate::model::dice::{ChaChaRoller, Dice, DiceSet, RollSource, Roller};
+    use crate::services::dice::{Caller, Identity, RollFilter, RollMetadata, Visibility};
+
+    async fn make_sqlite_repo() -> SqliteRepo {
+        // every connection to an in-memory database opens a new database
+        let sqlite_pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Cannot Create SqlitePool");
+
+        SqliteRepo::new(sqlite_pool)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot instanciate SQLite Repo: {e}"))
+    }
+
+    #[tokio::test]
+    async fn can_save_and_get_dice_rolls_with_modifiers() {
+        let sut = make_sqlite_repo().await;
+
+        let mut roller = ChaChaRoller::from_seed([5; 32]);
+        let source = roller.source();
+        let roll = RollDicesResponse {
+            id: RollId::new(),
+            rolled_dice_set: DiceSet::from_str("(d8r1 + 2) * 2 - 2d4! + 2d20kl1 + 4dF + d00")
+                .unwrap()
+                .roll_with(&mut roller)
+                .unwrap(),
+            source,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata::default(),
+        };
+        sut.save_roll(&roll).await.unwrap();
+        assert_eq!(sut.get_dice_roll(&roll.id).await.unwrap(), roll);
+
+        let missing_roll = sut.get_dice_roll(&RollId::new()).await;
+        assert!(matches!(missing_roll, Err(Error::NonExistingDiceRoll)));
+    }
+
+    #[tokio::test]
+    async fn can_save_and_get_fair_dice_rolls_with_metadata() {
+        let sut = make_sqlite_repo().await;
+
+        let id = RollId::new();
+        sut.save_commitment(&id, &[7; 32]).await.unwrap();
+        let server_seed = sut.take_commitment(&id).await.unwrap();
+        assert_eq!(server_seed, [7; 32]);
+        assert!(matches!(
+            sut.take_commitment(&id).await,
+            Err(Error::NonExistingCommitment)
+        ));
+
+        let proof = FairnessProof::new(server_seed, b"client".to_vec()).unwrap();
+        let mut roller = proof.roller();
+        let roll = RollDicesResponse {
+            id: RollId::new(),
+            source: roller.source(),
+            rolled_dice_set: DiceSet::from_str("3d6")
+                .unwrap()
+                .roll_with(&mut roller)
+                .unwrap(),
+            proof: Some(proof),
+            commitment_id: Some(id),
+            metadata: RollMetadata {
+                label: Some("Damage".to_string()),
+                rolled_by: Some("Aldric".to_string()),
+                session_id: Some("session-12".to_string()),
+                visibility: Visibility::Private,
+                tags: vec!["combat".to_string(), "damage".to_string()],
+                revealed_at: None,
+            },
+        };
+        sut.save_roll(&roll).await.unwrap();
+        assert_eq!(sut.get_dice_roll(&roll.id).await.unwrap(), roll);
+    }
+
+    #[tokio::test]
+    async fn can_list_dice_rolls() {
+        let sut = make_sqlite_repo().await;
+
+        let mut rolls = Vec::new();
+        for (notation, session_id) in [
+            ("d20", "session-1"),
+            ("2d6", "session-1"),
+            ("d20 + 4", "session-2"),
+            ("d20! + d4", "session-1"),
+            ("4dF", "session-1"),
+        ] {
+            let roll = RollDicesResponse {
+                id: RollId::new(),
+                rolled_dice_set: DiceSet::from_str(notation).unwrap().roll().unwrap(),
+                source: RollSource::Thread,
+                proof: None,
+                commitment_id: None,
+                metadata: RollMetadata {
+                    session_id: Some(session_id.to_string()),
+                    tags: vec![notation.to_string()],
+                    ..Default::default()
+                },
+            };
+            sut.save_roll(&roll).await.unwrap();
+            rolls.push(roll);
+        }
+
+        let all = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(all.rolls, rolls);
+        assert_eq!(all.next_cursor, None);
+
+        let mut req = ListDiceRollsRequest {
+            filter: RollFilter {
+                dice: Some(Dice::D20),
+                session_id: Some("session-1".to_string()),
+                ..Default::default()
+            },
+            after: None,
+            page_size: 1,
+        };
+        let first_page = sut.list_dice_rolls(&req).await.unwrap();
+        assert_eq!(first_page.rolls, vec![rolls[0].clone()]);
+        assert_eq!(first_page.next_cursor, Some(rolls[0].id.clone()));
+
+        req.after = first_page.next_cursor;
+        let last_page = sut.list_dice_rolls(&req).await.unwrap();
+        assert_eq!(last_page.rolls, vec![rolls[3].clone()]);
+        assert_eq!(last_page.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn can_list_and_reveal_hidden_dice_rolls() {
+        let sut = make_sqlite_repo().await;
+
+        let mut rolls = Vec::new();
+        for (rolled_by, visibility) in [
+            ("gm", Visibility::GameMaster),
+            ("aldric", Visibility::Private),
+            ("brunhild", Visibility::Private),
+            ("gm", Visibility::Public),
+        ] {
+            let roll = RollDicesResponse {
+                id: RollId::new(),
+                rolled_dice_set: DiceSet::from_str("d20").unwrap().roll().unwrap(),
+                source: RollSource::Thread,
+                proof: None,
+                commitment_id: None,
+                metadata: RollMetadata {
+                    rolled_by: Some(rolled_by.to_string()),
+                    visibility,
+                    ..Default::default()
+                },
+            };
+            sut.save_roll(&roll).await.unwrap();
+            rolls.push(roll);
+        }
+
+        let listed_by = async |identity| {
+            let req = ListDiceRollsRequest {
+                filter: RollFilter {
+                    visible_to: Caller::Authenticated(identity),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            sut.list_dice_rolls(&req).await.unwrap().rolls
+        };
+        assert_eq!(
+            listed_by(Identity::player("aldric")).await,
+            [rolls[1].clone(), rolls[3].clone()]
+        );
+        assert_eq!(
+            listed_by(Identity::game_master("gm")).await,
+            [rolls[0].clone(), rolls[3].clone()]
+        );
+
+        let revealed_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+        let revealed = sut.reveal_roll(&rolls[0].id, revealed_at).await.unwrap();
+        assert_eq!(revealed.metadata.visibility, Visibility::Public);
+        assert_eq!(revealed.metadata.revealed_at, Some(revealed_at));
+        assert_eq!(sut.get_dice_roll(&rolls[0].id).await.unwrap(), revealed);
+        assert_eq!(
+            sut.reveal_roll(&rolls[0].id, SystemTime::now())
+                .await
+                .unwrap(),
+            revealed
+        );
+        assert_eq!(
+            listed_by(Identity::player("brunhild")).await,
+            [revealed, rolls[2].clone(), rolls[3].clone()]
+        );
+        assert!(matches!(
+            sut.reveal_roll(&RollId::new(), revealed_at).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_save_idempotent_rolls() {
+        let sut = make_sqlite_repo().await;
+        let since = SystemTime::now() - Duration::from_secs(60);
+        let make_roll = || RollDicesResponse {
+            id: RollId::new(),
+            rolled_dice_set: DiceSet::from_str("d20").unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata::default(),
+        };
+
+        let (first, retry) = (make_roll(), make_roll());
+        assert_eq!(
+            sut.save_idempotent_roll("key", &first, since)
+                .await
+                .unwrap(),
+            first
+        );
+        assert_eq!(
+            sut.save_idempotent_roll("key", &retry, since)
+                .await
+                .unwrap(),
+            first
+        );
+        assert_eq!(
+            sut.get_idempotent_roll("key", since).await.unwrap(),
+            Some(first.clone())
+        );
+        assert!(matches!(
+            sut.get_dice_roll(&retry.id).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+        assert_eq!(sut.get_idempotent_roll("other", since).await.unwrap(), None);
+
+        // the key is claimed again once it is older than the retention window
+        let expired = SystemTime::now() + Duration::from_secs(1);
+        assert_eq!(sut.get_idempotent_roll("key", expired).await.unwrap(), None);
+        assert_eq!(
+            sut.save_idempotent_roll("key", &retry, expired)
+                .await
+                .unwrap(),
+            retry
+        );
+        assert_eq!(sut.get_dice_roll(&first.id).await.unwrap(), first);
+    }
+
+    #[tokio::test]
+    async fn can_save_batches_of_rolls() {
+        let sut = make_sqlite_repo().await;
+        let rolls = ["d20 + 2", "d20 + 2", "3d6"].map(|notation| RollDicesResponse {
+            id: RollId::new(),
+            rolled_dice_set: DiceSet::from_str(notation).unwrap().roll().unwrap(),
+            source: RollSource::Thread,
+            proof: None,
+            commitment_id: None,
+            metadata: RollMetadata {
+                label: Some(format!("Goblin {notation}")),
+                tags: vec!["fireball".to_string()],
+                ..Default::default()
+            },
+        });
+        sut.save_rolls(&rolls).await.unwrap();
+
+        let listed = sut
+            .list_dice_rolls(&ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.rolls, rolls);
+
+        // none of the rolls is saved when one of them cannot be
+        let duplicate = [
+            RollDicesResponse {
+                id: RollId::new(),
+                ..rolls[0].clone()
+            },
+            rolls[1].clone(),
+        ];
+        assert!(sut.save_rolls(&duplicate).await.is_err());
+        assert!(matches!(
+            sut.get_dice_roll(&duplicate[0].id).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+    }
+}
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS dice_commitments;
+DROP TABLE IF EXISTS dice_rolls;
+DROP TABLE IF EXISTS dice_set_tags;
+DROP TABLE IF EXISTS dice_sets;
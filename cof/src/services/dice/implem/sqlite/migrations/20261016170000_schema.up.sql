@@ -0,0 +1,41 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS dice_sets (
+  roll_id BLOB PRIMARY KEY,
+  notation TEXT NOT NULL,
+  roll_source TEXT NOT NULL,
+  server_seed BLOB,
+  client_seed BLOB,
+  commitment_id BLOB,
+  label TEXT,
+  rolled_by TEXT,
+  session_id TEXT,
+  visibility TEXT NOT NULL DEFAULT 'public'
+);
+
+-- SQLite has no arrays, the tags of a roll are kept in order in their own table
+CREATE TABLE IF NOT EXISTS dice_set_tags (
+  roll_id BLOB NOT NULL REFERENCES dice_sets (roll_id),
+  position INTEGER NOT NULL,
+  tag TEXT NOT NULL,
+  PRIMARY KEY (roll_id, position)
+);
+
+CREATE TABLE IF NOT EXISTS dice_rolls (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  roll_id BLOB NOT NULL REFERENCES dice_sets (roll_id),
+  dice TEXT NOT NULL,
+  result INTEGER NOT NULL,
+  discarded BOOLEAN NOT NULL DEFAULT FALSE,
+  origin TEXT NOT NULL DEFAULT 'initial'
+);
+
+CREATE TABLE IF NOT EXISTS dice_commitments (
+  roll_id BLOB PRIMARY KEY,
+  server_seed BLOB NOT NULL,
+  used BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS dice_rolls_roll_id_idx ON dice_rolls (roll_id);
+CREATE INDEX IF NOT EXISTS dice_rolls_dice_roll_id_idx ON dice_rolls (dice, roll_id);
+CREATE INDEX IF NOT EXISTS dice_sets_session_id_roll_id_idx ON dice_sets (session_id, roll_id);
+CREATE INDEX IF NOT EXISTS dice_sets_rolled_by_roll_id_idx ON dice_sets (rolled_by, roll_id);
@@ -0,0 +1,7 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+  idempotency_key TEXT PRIMARY KEY,
+  roll_id BLOB NOT NULL,
+  -- the milliseconds elapsed since the Unix epoch
+  saved_at INTEGER NOT NULL
+);
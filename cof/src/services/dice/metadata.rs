@@ -0,0 +1,171 @@
+//! Module that contains the metadata attached to the dice rolls, giving them their context in
+//! the history of the table.
+
+use std::{fmt::Display, str::FromStr, time::SystemTime};
+
+use super::Error;
+
+/// The maximum length in bytes of each text field of the [`RollMetadata`].
+pub const MAX_METADATA_LEN: usize = 256;
+
+/// The maximum number of tags of a roll.
+pub const MAX_TAGS: usize = 16;
+
+/// `Visibility` tells who is meant to see a dice roll.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// Everyone at the table sees the roll.
+    #[default]
+    Public,
+    /// Only the Game Master sees the roll, e.g. a roll behind the screen.
+    GameMaster,
+    /// Only the player who rolled sees the roll.
+    Private,
+}
+
+impl Visibility {
+    /// Tells whether the roll is hidden to some of the table, so that it can be revealed.
+    #[must_use]
+    pub fn is_hidden(self) -> bool {
+        self != Visibility::Public
+    }
+}
+
+impl Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Visibility::Public => write!(f, "public"),
+            Visibility::GameMaster => write!(f, "gm"),
+            Visibility::Private => write!(f, "private"),
+        }
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(Self::Public),
+            "gm" => Ok(Self::GameMaster),
+            "private" => Ok(Self::Private),
+            _ => Err(Error::InvalidMetadata(format!("unknown visibility {s}"))),
+        }
+    }
+}
+
+/// `RollMetadata` gives the context of a dice roll: who rolled, why and at which table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollMetadata {
+    /// What the roll is for (e.g. "Attack with longsword").
+    pub label: Option<String>,
+
+    /// The identity of the player or Game Master who rolled.
+    pub rolled_by: Option<String>,
+
+    /// The id of the campaign session the roll belongs to.
+    pub session_id: Option<String>,
+
+    /// Who is meant to see the roll.
+    pub visibility: Visibility,
+
+    /// Free-form tags (e.g. "combat" or "initiative").
+    pub tags: Vec<String>,
+
+    /// When the roll, hidden when it was made, has been revealed to the whole table.
+    pub revealed_at: Option<SystemTime>,
+}
+
+impl RollMetadata {
+    /// Checks that the metadata can be saved along with the roll.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidMetadata`] if a text field is longer than [`MAX_METADATA_LEN`] bytes, if
+    /// a tag is empty or if there are more than [`MAX_TAGS`] tags.
+    pub fn validate(&self) -> Result<(), Error> {
+        let fields = [
+            ("label", self.label.as_deref()),
+            ("rolled_by", self.rolled_by.as_deref()),
+            ("session_id", self.session_id.as_deref()),
+        ];
+        for (name, value) in fields {
+            if value.is_some_and(|v| v.len() > MAX_METADATA_LEN) {
+                return Err(Error::InvalidMetadata(format!(
+                    "{name} is longer than {MAX_METADATA_LEN} bytes"
+                )));
+            }
+        }
+
+        if self.tags.len() > MAX_TAGS {
+            return Err(Error::InvalidMetadata(format!(
+                "a roll cannot have more than {MAX_TAGS} tags"
+            )));
+        }
+        if self
+            .tags
+            .iter()
+            .any(|tag| tag.is_empty() || tag.len() > MAX_METADATA_LEN)
+        {
+            return Err(Error::InvalidMetadata(format!(
+                "tags must be between 1 and {MAX_METADATA_LEN} bytes long"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_encode_and_decode_visibilities() {
+        for visibility in [
+            Visibility::Public,
+            Visibility::GameMaster,
+            Visibility::Private,
+        ] {
+            assert_eq!(
+                Visibility::from_str(&visibility.to_string()).unwrap(),
+                visibility
+            );
+        }
+        assert!(Visibility::from_str("everyone").is_err());
+    }
+
+    #[test]
+    fn can_validate_metadata() {
+        let metadata = RollMetadata {
+            label: Some("Attack with longsword".to_string()),
+            rolled_by: Some("Aldric".to_string()),
+            session_id: Some("session-12".to_string()),
+            visibility: Visibility::Public,
+            tags: vec!["combat".to_string()],
+            revealed_at: None,
+        };
+        assert!(metadata.validate().is_ok());
+        assert!(RollMetadata::default().validate().is_ok());
+
+        let invalid_cases = [
+            RollMetadata {
+                label: Some("a".repeat(MAX_METADATA_LEN + 1)),
+                ..metadata.clone()
+            },
+            RollMetadata {
+                tags: vec![String::new()],
+                ..metadata.clone()
+            },
+            RollMetadata {
+                tags: vec!["combat".to_string(); MAX_TAGS + 1],
+                ..metadata.clone()
+            },
+        ];
+        for metadata in invalid_cases {
+            assert!(matches!(
+                metadata.validate(),
+                Err(Error::InvalidMetadata(_))
+            ));
+        }
+    }
+}
@@ -1,31 +1,196 @@
 //! Module that contains the logic of the Dice Service API.
 
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
 use async_trait::async_trait;
+use tokio::sync::{broadcast, mpsc};
 
-use super::{DiceService, Error, RollDicesRequest, RollDicesResponse, RollId};
-use crate::model::dice::RolledDiceSet;
+use super::{
+    Caller, DiceService, Error, FairRollRequest, FairnessReport, ListDiceRollsRequest,
+    ListDiceRollsResponse, MAX_PAGE_SIZE, ResolveTestRequest, ResolveTestResponse, RollCommitment,
+    RollDicesBatchRequest, RollDicesBatchResponse, RollDicesRequest, RollDicesResponse, RollFeed,
+    RollFilter, RollId, RollMetadata, WatchDiceRollsRequest,
+};
+use crate::model::dice::{
+    Error as DiceError, FairnessProof, MAX_CLIENT_SEED_LEN, RolledDiceSet, Roller, TestOutcome,
+    ThreadRoller, commit, generate_server_seed,
+};
 
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait DiceHistorySaver: Send + Sync + 'static {
-    async fn save_roll(&self, id: &RollId, rolled_dice_set: &RolledDiceSet) -> Result<(), Error>;
+    async fn save_roll(&self, roll: &RollDicesResponse) -> Result<(), Error>;
+
+    /// Saves the rolls of a batch at once, none of them being saved when one cannot be.
+    async fn save_rolls(&self, rolls: &[RollDicesResponse]) -> Result<(), Error>;
+
+    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error>;
+
+    /// Saves the server seed of an upcoming fair roll.
+    async fn save_commitment(&self, id: &RollId, server_seed: &[u8; 32]) -> Result<(), Error>;
+
+    /// Returns the server seed of an upcoming fair roll, it cannot be taken twice.
+    async fn take_commitment(&self, id: &RollId) -> Result<[u8; 32], Error>;
+
+    /// Returns the page of the rolls matching the request, ordered by their ids.
+    async fn list_dice_rolls(
+        &self,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error>;
+
+    /// Returns the roll saved with the idempotency key since the given instant, if any.
+    async fn get_idempotent_roll(
+        &self,
+        key: &str,
+        since: SystemTime,
+    ) -> Result<Option<RollDicesResponse>, Error>;
+
+    /// Saves the roll along with its idempotency key, unless a roll has been saved with the key
+    /// since the given instant: that roll is returned instead and the given one is not saved.
+    /// Concurrent saves with the same key are atomic, only one of the rolls being saved.
+    async fn save_idempotent_roll(
+        &self,
+        key: &str,
+        roll: &RollDicesResponse,
+        since: SystemTime,
+    ) -> Result<RollDicesResponse, Error>;
 
-    async fn get_dice_roll(&self, id: &RollId) -> Result<RolledDiceSet, Error>;
+    /// Makes the roll public if it is hidden, recording when it has been revealed, and returns
+    /// it. A roll that is already public is left as it is.
+    async fn reveal_roll(
+        &self,
+        id: &RollId,
+        revealed_at: SystemTime,
+    ) -> Result<RollDicesResponse, Error>;
+
+    /// Checks that the history can be reached, e.g. that its database is up.
+    async fn check_health(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Releases the resources of the history (e.g. the connections to its database) once the
+    /// service has been shut down.
+    async fn close(&self) {}
+}
+
+/// A shared history, so that it can still be checked and closed once handed to the service.
+#[async_trait]
+impl<R: DiceHistorySaver> DiceHistorySaver for Arc<R> {
+    async fn save_roll(&self, roll: &RollDicesResponse) -> Result<(), Error> {
+        self.as_ref().save_roll(roll).await
+    }
+
+    async fn save_rolls(&self, rolls: &[RollDicesResponse]) -> Result<(), Error> {
+        self.as_ref().save_rolls(rolls).await
+    }
+
+    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error> {
+        self.as_ref().get_dice_roll(id).await
+    }
+
+    async fn save_commitment(&self, id: &RollId, server_seed: &[u8; 32]) -> Result<(), Error> {
+        self.as_ref().save_commitment(id, server_seed).await
+    }
+
+    async fn take_commitment(&self, id: &RollId) -> Result<[u8; 32], Error> {
+        self.as_ref().take_commitment(id).await
+    }
+
+    async fn list_dice_rolls(
+        &self,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error> {
+        self.as_ref().list_dice_rolls(req).await
+    }
+
+    async fn get_idempotent_roll(
+        &self,
+        key: &str,
+        since: SystemTime,
+    ) -> Result<Option<RollDicesResponse>, Error> {
+        self.as_ref().get_idempotent_roll(key, since).await
+    }
+
+    async fn save_idempotent_roll(
+        &self,
+        key: &str,
+        roll: &RollDicesResponse,
+        since: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        self.as_ref().save_idempotent_roll(key, roll, since).await
+    }
+
+    async fn reveal_roll(
+        &self,
+        id: &RollId,
+        revealed_at: SystemTime,
+    ) -> Result<RollDicesResponse, Error> {
+        self.as_ref().reveal_roll(id, revealed_at).await
+    }
+
+    async fn check_health(&self) -> Result<(), Error> {
+        self.as_ref().check_health().await
+    }
+
+    async fn close(&self) {
+        self.as_ref().close().await;
+    }
 }
 
 #[async_trait]
 pub trait DiceMeter: Send + Sync + 'static {
     async fn register_roll(&self, rolled_dice_set: &RolledDiceSet);
+
+    /// Registers the outcome of a resolved ability test, e.g. whether it is a critical.
+    async fn register_test(&self, _outcome: &TestOutcome) {}
+
+    /// Registers a call of the service, named after its gRPC method, with how long it took and
+    /// the error it failed with.
+    async fn register_request(
+        &self,
+        _method: &'static str,
+        _duration: Duration,
+        _error: Option<&Error>,
+    ) {
+    }
+
+    /// Returns the report of the fairness of the dices rolled, when the meter monitors it.
+    async fn fairness_report(&self) -> Option<FairnessReport> {
+        None
+    }
 }
 
+/// The number of rolls kept for the watchers that are behind, a watcher lagging further
+/// behind is disconnected.
+const FEED_CAPACITY: usize = 1024;
+
+/// The number of rolls buffered for each watcher.
+pub(crate) const WATCHER_CAPACITY: usize = 64;
+
+/// How long the idempotency key of a roll request is remembered when the service is not given
+/// another retention.
+pub const DEFAULT_IDEMPOTENCY_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The maximum length in bytes of an idempotency key.
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 256;
+
+/// The maximum number of dice sets rolled by a batch.
+pub const MAX_BATCH_LEN: usize = 100;
+
 #[derive(Debug)]
-pub struct Service<R, M>
+pub struct Service<R, M, G = ThreadRoller>
 where
     R: DiceHistorySaver,
     M: DiceMeter,
+    G: Roller + Send + 'static,
 {
-    repo: R,
+    repo: Arc<R>,
     meter: M,
+    roller: Mutex<G>,
+    feed: broadcast::Sender<RollDicesResponse>,
+    idempotency_retention: Duration,
 }
 
 impl<R, M> Service<R, M>
@@ -34,43 +199,491 @@ where
     M: DiceMeter,
 {
     pub fn new(repo: R, meter: M) -> Self {
-        Self { repo, meter }
+        Self {
+            repo: Arc::new(repo),
+            meter,
+            roller: Mutex::new(ThreadRoller),
+            feed: broadcast::Sender::new(FEED_CAPACITY),
+            idempotency_retention: DEFAULT_IDEMPOTENCY_RETENTION,
+        }
+    }
+}
+
+impl<R, M, G> Service<R, M, G>
+where
+    R: DiceHistorySaver,
+    M: DiceMeter,
+    G: Roller + Send + 'static,
+{
+    /// Replaces the [`Roller`] used to roll the dices, e.g. with a seeded one.
+    pub fn with_roller<H>(self, roller: H) -> Service<R, M, H>
+    where
+        H: Roller + Send + 'static,
+    {
+        Service {
+            repo: self.repo,
+            meter: self.meter,
+            roller: Mutex::new(roller),
+            feed: self.feed,
+            idempotency_retention: self.idempotency_retention,
+        }
+    }
+
+    /// Replaces how long the idempotency keys of the roll requests are remembered.
+    #[must_use]
+    pub fn with_idempotency_retention(self, idempotency_retention: Duration) -> Self {
+        Self {
+            idempotency_retention,
+            ..self
+        }
     }
 }
 
 #[async_trait]
-impl<R, M> DiceService for Service<R, M>
+impl<R, M, G> DiceService for Service<R, M, G>
 where
     R: DiceHistorySaver,
     M: DiceMeter,
+    G: Roller + Send + 'static,
 {
-    async fn roll_dices(&self, req: &RollDicesRequest) -> Result<RollDicesResponse, Error> {
-        let rolled_dice_set = req.dice_set.clone().roll()?;
-        let id = RollId::new();
-        self.meter.register_roll(&rolled_dice_set).await;
-        self.repo.save_roll(&id, &rolled_dice_set).await?;
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(caller = %caller, dice_set = %req.dice_set, roll_id = tracing::field::Empty))
+    )]
+    async fn roll_dices(
+        &self,
+        caller: &Caller,
+        req: &RollDicesRequest,
+    ) -> Result<RollDicesResponse, Error> {
+        self.metered("RollDices", self.roll(caller, req)).await
+    }
 
-        Ok(RollDicesResponse {
-            id,
-            rolled_dice_set,
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(caller = %caller, batch_len = req.items.len()))
+    )]
+    async fn roll_dices_batch(
+        &self,
+        caller: &Caller,
+        req: &RollDicesBatchRequest,
+    ) -> Result<RollDicesBatchResponse, Error> {
+        self.metered("RollDicesBatch", async {
+            if req.items.is_empty() || req.items.len() > MAX_BATCH_LEN {
+                return Err(Error::InvalidBatch(format!(
+                    "the batch must hold between 1 and {MAX_BATCH_LEN} dice sets"
+                )));
+            }
+            let metadata = RollMetadata {
+                revealed_at: None,
+                ..caller.attribute(&req.metadata)
+            };
+
+            // every dice set is checked before any of them is rolled
+            let items = req
+                .items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let metadata = RollMetadata {
+                        label: item.label.clone().or_else(|| metadata.label.clone()),
+                        ..metadata.clone()
+                    };
+                    item.dice_set
+                        .validate()
+                        .map_err(Error::from)
+                        .and_then(|()| metadata.validate())
+                        .map_err(|e| Error::InvalidBatchItem(index, Box::new(e)))?;
+                    Ok((&item.dice_set, metadata))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let rolls = {
+                let mut roller = self.roller.lock().unwrap_or_else(PoisonError::into_inner);
+                items
+                    .into_iter()
+                    .map(|(dice_set, metadata)| {
+                        Ok(RollDicesResponse {
+                            id: RollId::new(),
+                            source: roller.source(),
+                            rolled_dice_set: dice_set.clone().roll_with(&mut *roller)?,
+                            proof: None,
+                            commitment_id: None,
+                            metadata,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?
+            };
+            self.repo.save_rolls(&rolls).await?;
+            for roll in &rolls {
+                self.meter.register_roll(&roll.rolled_dice_set).await;
+                // the roll is not fed when nobody watches
+                let _ = self.feed.send(roll.clone());
+            }
+
+            Ok(RollDicesBatchResponse { rolls })
         })
+        .await
     }
 
-    async fn get_dice_roll(&self, id: &RollId) -> Result<RollDicesResponse, Error> {
-        self.repo
-            .get_dice_roll(id)
-            .await
-            .map(|rolled_dice_set| RollDicesResponse {
-                id: id.clone(),
-                rolled_dice_set,
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(caller = %caller, roll_id = %id))
+    )]
+    async fn get_dice_roll(
+        &self,
+        caller: &Caller,
+        id: &RollId,
+    ) -> Result<RollDicesResponse, Error> {
+        self.metered("GetDiceRoll", async {
+            let roll = self.repo.get_dice_roll(id).await?;
+            // a hidden roll is reported as missing so that its existence is not disclosed
+            if !caller.can_see(&roll) {
+                return Err(Error::NonExistingDiceRoll);
+            }
+            Ok(roll)
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(roll_id = tracing::field::Empty))
+    )]
+    async fn commit_dice_roll(&self) -> Result<RollCommitment, Error> {
+        self.metered("CommitDiceRoll", async {
+            let id = RollId::new();
+            record_in_span!("roll_id", &id);
+            let server_seed = generate_server_seed();
+            self.repo.save_commitment(&id, &server_seed).await?;
+
+            Ok(RollCommitment {
+                id,
+                commitment: commit(&server_seed),
             })
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(caller = %caller, dice_set = %req.test.dice_set()))
+    )]
+    async fn resolve_test(
+        &self,
+        caller: &Caller,
+        req: &ResolveTestRequest,
+    ) -> Result<ResolveTestResponse, Error> {
+        self.metered("ResolveTest", async {
+            let roll = self
+                .roll(
+                    caller,
+                    &RollDicesRequest {
+                        dice_set: req.test.dice_set().clone(),
+                        fair_roll: None,
+                        metadata: req.metadata.clone(),
+                        idempotency_key: None,
+                    },
+                )
+                .await?;
+            let outcome = req.test.outcome(&roll.rolled_dice_set)?;
+            self.meter.register_test(&outcome).await;
+
+            Ok(ResolveTestResponse { roll, outcome })
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(caller = %caller, page_size = req.limit(), rows = tracing::field::Empty))
+    )]
+    async fn list_dice_rolls(
+        &self,
+        caller: &Caller,
+        req: &ListDiceRollsRequest,
+    ) -> Result<ListDiceRollsResponse, Error> {
+        self.metered("ListDiceRolls", async {
+            let req = ListDiceRollsRequest {
+                filter: caller.restrict(&req.filter),
+                ..req.clone()
+            };
+            let page = self.repo.list_dice_rolls(&req).await?;
+            record_in_span!("rows", page.rolls.len());
+            Ok(page)
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(caller = %caller, session_id = req.session_id.as_deref()))
+    )]
+    async fn watch_dice_rolls(
+        &self,
+        caller: &Caller,
+        req: &WatchDiceRollsRequest,
+    ) -> Result<RollFeed, Error> {
+        self.metered("WatchDiceRolls", async {
+            let filter = caller.restrict(&RollFilter {
+                session_id: req.session_id.clone(),
+                ..Default::default()
+            });
+            // subscribed before replaying so that no roll is missed in between
+            let live = self.feed.subscribe();
+            let (tx, feed) = mpsc::channel(WATCHER_CAPACITY);
+            tokio::spawn(feed_rolls(
+                Arc::clone(&self.repo),
+                live,
+                filter,
+                req.after.clone(),
+                tx,
+            ));
+            Ok(feed)
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "opentelemetry",
+        tracing::instrument(skip_all, err, fields(caller = %caller, roll_id = %id))
+    )]
+    async fn reveal_dice_roll(
+        &self,
+        caller: &Caller,
+        id: &RollId,
+    ) -> Result<RollDicesResponse, Error> {
+        self.metered("RevealDiceRoll", async {
+            let roll = self.repo.get_dice_roll(id).await?;
+            if !roll.metadata.visibility.is_hidden() {
+                return Ok(roll);
+            }
+            // the rolls the caller may not reveal are reported as missing, as hidden ones are
+            if !caller.can_reveal(&roll) {
+                return Err(Error::NonExistingDiceRoll);
+            }
+
+            let roll = self.repo.reveal_roll(id, now_in_millis()).await?;
+            // the watchers who could not see the roll see it as it is revealed
+            let _ = self.feed.send(roll.clone());
+            Ok(roll)
+        })
+        .await
+    }
+
+    #[cfg_attr(feature = "opentelemetry", tracing::instrument(skip_all, err))]
+    async fn get_fairness_report(&self) -> Result<FairnessReport, Error> {
+        self.metered("GetFairnessReport", async {
+            self.meter
+                .fairness_report()
+                .await
+                .ok_or(Error::FairnessNotMonitored)
+        })
+        .await
+    }
+}
+
+impl<R, M, G> Service<R, M, G>
+where
+    R: DiceHistorySaver,
+    M: DiceMeter,
+    G: Roller + Send + 'static,
+{
+    /// Rolls the dices of the request on behalf of the caller and saves the roll, shared by the
+    /// calls of the service rolling dices so that only the call itself is registered by the
+    /// meter.
+    async fn roll(
+        &self,
+        caller: &Caller,
+        req: &RollDicesRequest,
+    ) -> Result<RollDicesResponse, Error> {
+        let metadata = RollMetadata {
+            // a roll is only revealed once it has been made
+            revealed_at: None,
+            ..caller.attribute(&req.metadata)
+        };
+        metadata.validate()?;
+
+        // a repeated request is answered before the commitment of a fair roll is used again
+        let idempotency = match &req.idempotency_key {
+            Some(key) => {
+                if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+                    return Err(Error::InvalidIdempotencyKey(format!(
+                        "the key must be between 1 and {MAX_IDEMPOTENCY_KEY_LEN} bytes long"
+                    )));
+                }
+                let since = SystemTime::now()
+                    .checked_sub(self.idempotency_retention)
+                    .unwrap_or(UNIX_EPOCH);
+                if let Some(roll) = self.repo.get_idempotent_roll(key, since).await? {
+                    return replay(roll, req, &metadata);
+                }
+                Some((key, since))
+            }
+            None => None,
+        };
+
+        let roll = match &req.fair_roll {
+            None => {
+                let mut roller = self.roller.lock().unwrap_or_else(PoisonError::into_inner);
+                RollDicesResponse {
+                    id: RollId::new(),
+                    source: roller.source(),
+                    rolled_dice_set: req.dice_set.clone().roll_with(&mut *roller)?,
+                    proof: None,
+                    commitment_id: None,
+                    metadata,
+                }
+            }
+            Some(FairRollRequest {
+                commitment_id,
+                client_seed,
+            }) => {
+                // checked before the commitment is used so that it is not wasted
+                if client_seed.len() > MAX_CLIENT_SEED_LEN {
+                    return Err(DiceError::ClientSeedTooLong.into());
+                }
+                let server_seed = self.repo.take_commitment(commitment_id).await?;
+                let proof = FairnessProof::new(server_seed, client_seed.clone())?;
+                let mut roller = proof.roller();
+                RollDicesResponse {
+                    // the roll is timestamped when it is made, not when it was committed to
+                    id: RollId::new(),
+                    source: roller.source(),
+                    rolled_dice_set: req.dice_set.clone().roll_with(&mut roller)?,
+                    proof: Some(proof),
+                    commitment_id: Some(commitment_id.clone()),
+                    metadata,
+                }
+            }
+        };
+        record_in_span!("roll_id", &roll.id);
+        if let Some((key, since)) = idempotency {
+            let saved = self.repo.save_idempotent_roll(key, &roll, since).await?;
+            // a concurrent request with the same key has saved its roll first
+            if saved.id != roll.id {
+                return replay(saved, req, &roll.metadata);
+            }
+        } else {
+            self.repo.save_roll(&roll).await?;
+        }
+        self.meter.register_roll(&roll.rolled_dice_set).await;
+        // the roll is not fed when nobody watches
+        let _ = self.feed.send(roll.clone());
+
+        Ok(roll)
+    }
+
+    /// Registers how long the call took and whether it failed in the meter.
+    async fn metered<T>(
+        &self,
+        method: &'static str,
+        call: impl Future<Output = Result<T, Error>> + Send,
+    ) -> Result<T, Error> {
+        let start = Instant::now();
+        let result = call.await;
+        self.meter
+            .register_request(method, start.elapsed(), result.as_ref().err())
+            .await;
+        result
+    }
+}
+
+/// Returns the roll saved for a previous request with the same idempotency key, once checked
+/// that the request is the same.
+fn replay(
+    roll: RollDicesResponse,
+    req: &RollDicesRequest,
+    metadata: &RollMetadata,
+) -> Result<RollDicesResponse, Error> {
+    if roll.rolled_dice_set.dice_set() != req.dice_set
+        || roll.metadata.rolled_by != metadata.rolled_by
+    {
+        return Err(Error::IdempotencyKeyReused);
+    }
+    record_in_span!("roll_id", &roll.id);
+    Ok(roll)
+}
+
+/// Returns the current instant, truncated to the millisecond like the instants of the gRPC API
+/// so that they are saved and read back as they are.
+fn now_in_millis() -> SystemTime {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    UNIX_EPOCH + Duration::from_millis(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// Feeds a watcher with the rolls matching the filter made after its last seen one, replayed
+/// from the history, and then with the rolls as they are made or revealed, until it stops
+/// watching.
+async fn feed_rolls<R: DiceHistorySaver>(
+    repo: Arc<R>,
+    mut live: broadcast::Receiver<RollDicesResponse>,
+    filter: RollFilter,
+    after: Option<RollId>,
+    tx: mpsc::Sender<Result<RollDicesResponse, Error>>,
+) {
+    // the rolls made or revealed while replaying are both in the history and in the live feed
+    let mut replayed = HashSet::new();
+    let mut page = ListDiceRollsRequest {
+        filter: filter.clone(),
+        after,
+        page_size: MAX_PAGE_SIZE,
+    };
+    while page.after.is_some() {
+        let rolls = match repo.list_dice_rolls(&page).await {
+            Ok(ListDiceRollsResponse { rolls, next_cursor }) => {
+                page.after = next_cursor;
+                rolls
+            }
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        };
+        for roll in rolls {
+            replayed.insert((roll.id.clone(), roll.metadata.revealed_at));
+            if tx.send(Ok(roll)).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    loop {
+        let roll = tokio::select! {
+            () = tx.closed() => return,
+            roll = live.recv() => roll,
+        };
+        match roll {
+            Ok(roll)
+                if filter.matches(&roll)
+                    && !replayed.contains(&(roll.id.clone(), roll.metadata.revealed_at)) =>
+            {
+                if tx.send(Ok(roll)).await.is_err() {
+                    return;
+                }
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                let _ = tx.send(Err(Error::RollFeedLagged)).await;
+                return;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::model::dice::{Dice, DiceSet};
+    use std::str::FromStr;
+
+    use crate::model::dice::{
+        AbilityTest, ChaChaRoller, Dice, DiceSet, RollSource, ScriptedRoller, verify_fair_roll,
+    };
     use crate::services::dice::implem::{in_memory::InMemoryDiceHistorySaver, noop::NoopMeter};
+    use crate::services::dice::{
+        Identity, MAX_METADATA_LEN, RollDicesBatchItem, RollFilter, RollMetadata, Visibility,
+    };
 
     use super::*;
 
@@ -79,9 +692,15 @@ mod tests {
         let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
 
         let roll_result = sut
-            .roll_dices(&RollDicesRequest {
-                dice_set: DiceSet::new(vec![Dice::D20].into_iter()),
-            })
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::new(vec![Dice::D20].into_iter()),
+                    fair_roll: None,
+                    metadata: RollMetadata::default(),
+                    idempotency_key: None,
+                },
+            )
             .await;
 
         assert!(roll_result.is_ok());
@@ -89,10 +708,728 @@ mod tests {
         let RollDicesResponse {
             id,
             rolled_dice_set,
+            source,
+            proof,
+            commitment_id,
+            metadata,
         } = roll_result.unwrap();
+        assert_eq!(source, RollSource::Thread);
+        assert!(proof.is_none());
+        assert!(commitment_id.is_none());
+        assert_eq!(metadata, RollMetadata::default());
 
-        let query_result = sut.get_dice_roll(&id).await;
+        let query_result = sut.get_dice_roll(&Caller::Trusted, &id).await;
         assert!(query_result.is_ok());
         assert_eq!(query_result.unwrap().rolled_dice_set, rolled_dice_set);
     }
+
+    #[tokio::test]
+    async fn can_roll_dices_with_a_roller() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter)
+            .with_roller(ScriptedRoller::new([3, 18]));
+
+        let roll = sut
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("2d20kh1 + 2").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata::default(),
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(roll.rolled_dice_set.total(), 20);
+        assert_eq!(roll.source, RollSource::Scripted);
+        assert_eq!(
+            sut.get_dice_roll(&Caller::Trusted, &roll.id)
+                .await
+                .unwrap()
+                .source,
+            RollSource::Scripted
+        );
+    }
+
+    #[tokio::test]
+    async fn can_replay_seeded_dice_rolls() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter)
+            .with_roller(ChaChaRoller::from_seed([1; 32]));
+        let dice_set = DiceSet::from_str("4d6dl1 + d8!").unwrap();
+
+        let mut rolls = Vec::new();
+        for _ in 0..3 {
+            let req = RollDicesRequest {
+                dice_set: dice_set.clone(),
+                fair_roll: None,
+                metadata: RollMetadata::default(),
+                idempotency_key: None,
+            };
+            rolls.push(sut.roll_dices(&Caller::Trusted, &req).await.unwrap());
+        }
+
+        for roll in rolls {
+            let mut replayed = ChaChaRoller::resume(roll.source).unwrap();
+            let replayed_dice_set = dice_set.clone().roll_with(&mut replayed).unwrap();
+            assert_eq!(replayed_dice_set, roll.rolled_dice_set);
+        }
+    }
+
+    #[tokio::test]
+    async fn can_roll_provably_fair_dices() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let RollCommitment { id, commitment } = sut.commit_dice_roll().await.unwrap();
+
+        let req = RollDicesRequest {
+            dice_set: DiceSet::from_str("2d20kh1 + 5").unwrap(),
+            fair_roll: Some(FairRollRequest {
+                commitment_id: id.clone(),
+                client_seed: b"lucky charm".to_vec(),
+            }),
+            metadata: RollMetadata::default(),
+            idempotency_key: None,
+        };
+        let roll = sut.roll_dices(&Caller::Trusted, &req).await.unwrap();
+        assert_ne!(roll.id, id);
+        assert_eq!(roll.commitment_id, Some(id));
+
+        let proof = roll.proof.unwrap();
+        assert_eq!(proof.client_seed(), b"lucky charm");
+        assert!(verify_fair_roll(&roll.rolled_dice_set, &commitment, &proof).is_ok());
+        assert_eq!(
+            sut.get_dice_roll(&Caller::Trusted, &roll.id)
+                .await
+                .unwrap()
+                .proof,
+            Some(proof)
+        );
+
+        assert!(matches!(
+            sut.roll_dices(&Caller::Trusted, &req).await,
+            Err(Error::NonExistingCommitment)
+        ));
+    }
+
+    #[tokio::test]
+    async fn cannot_roll_fair_dices_without_commitment() {
+        let mut repo = MockDiceHistorySaver::new();
+        repo.expect_take_commitment().never();
+        let sut = Service::new(repo, NoopMeter);
+
+        let req = RollDicesRequest {
+            dice_set: DiceSet::from_str("d20").unwrap(),
+            fair_roll: Some(FairRollRequest {
+                commitment_id: RollId::new(),
+                client_seed: vec![0; MAX_CLIENT_SEED_LEN + 1],
+            }),
+            metadata: RollMetadata::default(),
+            idempotency_key: None,
+        };
+        assert!(matches!(
+            sut.roll_dices(&Caller::Trusted, &req).await,
+            Err(Error::FromModel(DiceError::ClientSeedTooLong))
+        ));
+
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let req = RollDicesRequest {
+            dice_set: DiceSet::from_str("d20").unwrap(),
+            fair_roll: Some(FairRollRequest {
+                commitment_id: RollId::new(),
+                client_seed: Vec::new(),
+            }),
+            metadata: RollMetadata::default(),
+            idempotency_key: None,
+        };
+        assert!(matches!(
+            sut.roll_dices(&Caller::Trusted, &req).await,
+            Err(Error::NonExistingCommitment)
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_retry_roll_requests_with_idempotency_key() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let RollCommitment { id, .. } = sut.commit_dice_roll().await.unwrap();
+
+        let mut req = RollDicesRequest {
+            dice_set: DiceSet::from_str("2d20kh1 + 5").unwrap(),
+            fair_roll: Some(FairRollRequest {
+                commitment_id: id.clone(),
+                client_seed: b"lucky charm".to_vec(),
+            }),
+            metadata: RollMetadata::default(),
+            idempotency_key: Some("attack-1".to_string()),
+        };
+        let roll = sut.roll_dices(&aldric, &req).await.unwrap();
+        assert_eq!(roll.commitment_id, Some(id));
+        // the commitment has been used, the roll it made is returned again
+        assert_eq!(sut.roll_dices(&aldric, &req).await.unwrap(), roll);
+
+        let brunhild = Caller::Authenticated(Identity::player("brunhild"));
+        assert!(matches!(
+            sut.roll_dices(&brunhild, &req).await,
+            Err(Error::IdempotencyKeyReused)
+        ));
+        req.dice_set = DiceSet::from_str("d20").unwrap();
+        assert!(matches!(
+            sut.roll_dices(&aldric, &req).await,
+            Err(Error::IdempotencyKeyReused)
+        ));
+
+        let listed = sut
+            .list_dice_rolls(&Caller::Trusted, &ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.rolls, vec![roll]);
+    }
+
+    #[tokio::test]
+    async fn can_roll_again_once_idempotency_key_expired() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter)
+            .with_idempotency_retention(Duration::ZERO);
+
+        let req = RollDicesRequest {
+            dice_set: DiceSet::from_str("d20").unwrap(),
+            fair_roll: None,
+            metadata: RollMetadata::default(),
+            idempotency_key: Some("attack-1".to_string()),
+        };
+        let roll = sut.roll_dices(&Caller::Trusted, &req).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let rolled_again = sut.roll_dices(&Caller::Trusted, &req).await.unwrap();
+        assert_ne!(rolled_again.id, roll.id);
+    }
+
+    #[tokio::test]
+    async fn cannot_roll_dices_with_invalid_idempotency_key() {
+        let mut repo = MockDiceHistorySaver::new();
+        repo.expect_get_idempotent_roll().never();
+        repo.expect_save_idempotent_roll().never();
+        let sut = Service::new(repo, NoopMeter);
+
+        for key in [String::new(), "k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1)] {
+            let req = RollDicesRequest {
+                dice_set: DiceSet::from_str("d20").unwrap(),
+                fair_roll: None,
+                metadata: RollMetadata::default(),
+                idempotency_key: Some(key),
+            };
+            assert!(matches!(
+                sut.roll_dices(&Caller::Trusted, &req).await,
+                Err(Error::InvalidIdempotencyKey(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn can_roll_dice_batches() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter)
+            .with_roller(ScriptedRoller::new([4, 17, 2, 6, 3]));
+        let gm = Caller::Authenticated(Identity::game_master("gm"));
+        let mut feed = sut
+            .watch_dice_rolls(&Caller::Trusted, &WatchDiceRollsRequest::default())
+            .await
+            .unwrap();
+
+        let req = RollDicesBatchRequest {
+            items: vec![
+                RollDicesBatchItem {
+                    dice_set: DiceSet::from_str("d20 + 2").unwrap(),
+                    label: Some("Goblin 1".to_string()),
+                },
+                RollDicesBatchItem {
+                    dice_set: DiceSet::from_str("d20 + 2").unwrap(),
+                    label: Some("Goblin 2".to_string()),
+                },
+                RollDicesBatchItem {
+                    dice_set: DiceSet::from_str("3d6").unwrap(),
+                    label: None,
+                },
+            ],
+            metadata: RollMetadata {
+                label: Some("Fireball".to_string()),
+                session_id: Some("session-1".to_string()),
+                ..Default::default()
+            },
+        };
+        let RollDicesBatchResponse { rolls } = sut.roll_dices_batch(&gm, &req).await.unwrap();
+
+        let totals = rolls
+            .iter()
+            .map(|roll| roll.rolled_dice_set.total())
+            .collect::<Vec<_>>();
+        assert_eq!(totals, [6, 19, 11]);
+        let labels = rolls
+            .iter()
+            .map(|roll| roll.metadata.label.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["Goblin 1", "Goblin 2", "Fireball"]);
+        for roll in &rolls {
+            assert_eq!(roll.metadata.rolled_by.as_deref(), Some("gm"));
+            assert_eq!(roll.metadata.session_id.as_deref(), Some("session-1"));
+            assert_eq!(feed.recv().await.unwrap().unwrap(), *roll);
+        }
+        assert!(rolls[0].id < rolls[1].id && rolls[1].id < rolls[2].id);
+
+        let listed = sut
+            .list_dice_rolls(&Caller::Trusted, &ListDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.rolls, rolls);
+    }
+
+    #[tokio::test]
+    async fn cannot_roll_invalid_dice_batches() {
+        let mut repo = MockDiceHistorySaver::new();
+        repo.expect_save_rolls().never();
+        let sut = Service::new(repo, NoopMeter);
+        let item = |notation: &str| RollDicesBatchItem {
+            dice_set: DiceSet::from_str(notation).unwrap(),
+            label: None,
+        };
+
+        let req = RollDicesBatchRequest::default();
+        assert!(matches!(
+            sut.roll_dices_batch(&Caller::Trusted, &req).await,
+            Err(Error::InvalidBatch(_))
+        ));
+        let req = RollDicesBatchRequest {
+            items: vec![item("d20"); MAX_BATCH_LEN + 1],
+            ..Default::default()
+        };
+        assert!(matches!(
+            sut.roll_dices_batch(&Caller::Trusted, &req).await,
+            Err(Error::InvalidBatch(_))
+        ));
+
+        let req = RollDicesBatchRequest {
+            items: vec![item("d20"), item("1001d6"), item("d20")],
+            ..Default::default()
+        };
+        let error = sut.roll_dices_batch(&Caller::Trusted, &req).await;
+        assert!(matches!(
+            error,
+            Err(Error::InvalidBatchItem(1, ref e))
+                if matches!(**e, Error::FromModel(DiceError::WayTooManyDices))
+        ));
+
+        let req = RollDicesBatchRequest {
+            items: vec![RollDicesBatchItem {
+                label: Some("a".repeat(MAX_METADATA_LEN + 1)),
+                ..item("d20")
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            sut.roll_dices_batch(&Caller::Trusted, &req).await,
+            Err(Error::InvalidBatchItem(0, ref e)) if matches!(**e, Error::InvalidMetadata(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_resolve_ability_tests() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter)
+            .with_roller(ScriptedRoller::new([19, 4]));
+
+        let test = AbilityTest::new(DiceSet::from_str("2d20kh1").unwrap(), 2, 25)
+            .unwrap()
+            .with_critical_threshold(19)
+            .unwrap();
+        let resp = sut
+            .resolve_test(
+                &Caller::Trusted,
+                &ResolveTestRequest {
+                    test,
+                    metadata: RollMetadata::default(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.outcome.natural(), 19);
+        assert_eq!(resp.outcome.total(), 21);
+        assert!(resp.outcome.is_critical());
+        assert!(resp.outcome.is_success());
+
+        let saved = sut
+            .get_dice_roll(&Caller::Trusted, &resp.roll.id)
+            .await
+            .unwrap();
+        assert_eq!(saved, resp.roll);
+    }
+
+    #[tokio::test]
+    async fn can_roll_dices_with_metadata() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let metadata = RollMetadata {
+            label: Some("Attack with longsword".to_string()),
+            rolled_by: Some("Aldric".to_string()),
+            session_id: Some("session-12".to_string()),
+            visibility: Visibility::GameMaster,
+            tags: vec!["combat".to_string(), "attack".to_string()],
+            revealed_at: None,
+        };
+
+        let roll = sut
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("d20 + 5").unwrap(),
+                    fair_roll: None,
+                    metadata: metadata.clone(),
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(roll.metadata, metadata);
+        assert_eq!(
+            sut.get_dice_roll(&Caller::Trusted, &roll.id)
+                .await
+                .unwrap()
+                .metadata,
+            metadata
+        );
+
+        let invalid = sut
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("d20").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata {
+                        tags: vec![String::new()],
+                        ..metadata
+                    },
+                    idempotency_key: None,
+                },
+            )
+            .await;
+        assert!(matches!(invalid, Err(Error::InvalidMetadata(_))));
+    }
+
+    #[tokio::test]
+    async fn can_roll_dices_as_authenticated_callers() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let brunhild = Caller::Authenticated(Identity::player("brunhild"));
+        let gm = Caller::Authenticated(Identity::game_master("gm"));
+
+        let roll_as = async |caller: &Caller, visibility: Visibility| {
+            sut.roll_dices(
+                caller,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("d20").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata {
+                        rolled_by: Some("gm".to_string()),
+                        visibility,
+                        ..Default::default()
+                    },
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap()
+        };
+        let private = roll_as(&aldric, Visibility::Private).await;
+        assert_eq!(private.metadata.rolled_by.as_deref(), Some("aldric"));
+        let behind_screen = roll_as(&gm, Visibility::GameMaster).await;
+        let public = roll_as(&brunhild, Visibility::Public).await;
+
+        assert_eq!(
+            sut.get_dice_roll(&aldric, &private.id).await.unwrap(),
+            private
+        );
+        assert_eq!(
+            sut.get_dice_roll(&aldric, &public.id).await.unwrap(),
+            public
+        );
+        assert_eq!(
+            sut.get_dice_roll(&gm, &behind_screen.id).await.unwrap(),
+            behind_screen
+        );
+        for (caller, id) in [(&brunhild, &private.id), (&aldric, &behind_screen.id)] {
+            assert!(matches!(
+                sut.get_dice_roll(caller, id).await,
+                Err(Error::NonExistingDiceRoll)
+            ));
+        }
+    }
+
+    async fn roll_hidden(
+        sut: &impl DiceService,
+        caller: &Caller,
+        visibility: Visibility,
+    ) -> RollDicesResponse {
+        sut.roll_dices(
+            caller,
+            &RollDicesRequest {
+                dice_set: DiceSet::from_str("d20").unwrap(),
+                fair_roll: None,
+                metadata: RollMetadata {
+                    visibility,
+                    ..Default::default()
+                },
+                idempotency_key: None,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn can_hide_rolls_from_listings_and_feeds() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let gm = Caller::Authenticated(Identity::game_master("gm"));
+
+        let mut aldric_feed = sut
+            .watch_dice_rolls(&aldric, &WatchDiceRollsRequest::default())
+            .await
+            .unwrap();
+        let behind_screen = roll_hidden(&sut, &gm, Visibility::GameMaster).await;
+        let private = roll_hidden(&sut, &aldric, Visibility::Private).await;
+        let public = roll_hidden(&sut, &gm, Visibility::Public).await;
+
+        let req = ListDiceRollsRequest::default();
+        let listed = async |caller: &Caller| sut.list_dice_rolls(caller, &req).await.unwrap().rolls;
+        assert_eq!(listed(&aldric).await, [private.clone(), public.clone()]);
+        assert_eq!(listed(&gm).await, [behind_screen.clone(), public.clone()]);
+        assert_eq!(
+            listed(&Caller::Trusted).await,
+            [behind_screen, private.clone(), public.clone()]
+        );
+
+        assert_eq!(aldric_feed.recv().await.unwrap().unwrap(), private);
+        assert_eq!(aldric_feed.recv().await.unwrap().unwrap(), public);
+        assert!(aldric_feed.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn can_reveal_hidden_rolls() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let gm = Caller::Authenticated(Identity::game_master("gm"));
+
+        let behind_screen = roll_hidden(&sut, &gm, Visibility::GameMaster).await;
+        let mut aldric_feed = sut
+            .watch_dice_rolls(&aldric, &WatchDiceRollsRequest::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            sut.reveal_dice_roll(&aldric, &behind_screen.id).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+
+        let before = SystemTime::now() - Duration::from_millis(1);
+        let revealed = sut.reveal_dice_roll(&gm, &behind_screen.id).await.unwrap();
+        assert_eq!(revealed.metadata.visibility, Visibility::Public);
+        assert!(revealed.metadata.revealed_at.is_some_and(|at| at >= before));
+        assert_eq!(revealed.rolled_dice_set, behind_screen.rolled_dice_set);
+        assert_eq!(
+            sut.get_dice_roll(&aldric, &behind_screen.id).await.unwrap(),
+            revealed
+        );
+        assert_eq!(aldric_feed.recv().await.unwrap().unwrap(), revealed);
+
+        // revealing a public roll again leaves its audit timestamp untouched
+        assert_eq!(
+            sut.reveal_dice_roll(&aldric, &behind_screen.id)
+                .await
+                .unwrap(),
+            revealed
+        );
+        assert!(aldric_feed.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn cannot_reveal_rolls_of_others() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let aldric = Caller::Authenticated(Identity::player("aldric"));
+        let brunhild = Caller::Authenticated(Identity::player("brunhild"));
+        let gm = Caller::Authenticated(Identity::game_master("gm"));
+
+        let private = roll_hidden(&sut, &aldric, Visibility::Private).await;
+        for caller in [&brunhild, &gm] {
+            assert!(matches!(
+                sut.reveal_dice_roll(caller, &private.id).await,
+                Err(Error::NonExistingDiceRoll)
+            ));
+        }
+        let revealed = sut.reveal_dice_roll(&aldric, &private.id).await.unwrap();
+        assert_eq!(revealed.metadata.visibility, Visibility::Public);
+
+        // a player may reveal the roll they made behind the screen, though they do not see it
+        let behind_screen = roll_hidden(&sut, &aldric, Visibility::GameMaster).await;
+        assert!(matches!(
+            sut.reveal_dice_roll(&brunhild, &behind_screen.id).await,
+            Err(Error::NonExistingDiceRoll)
+        ));
+        let revealed = sut
+            .reveal_dice_roll(&aldric, &behind_screen.id)
+            .await
+            .unwrap();
+        assert_eq!(revealed.metadata.visibility, Visibility::Public);
+    }
+
+    #[tokio::test]
+    async fn cannot_roll_dices_already_revealed() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let roll = sut
+            .roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("d20").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata {
+                        visibility: Visibility::GameMaster,
+                        revealed_at: Some(SystemTime::now()),
+                        ..Default::default()
+                    },
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(roll.metadata.revealed_at, None);
+    }
+
+    #[tokio::test]
+    async fn can_list_dice_rolls() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let mut session_rolls = Vec::new();
+        for notation in ["d20", "2d6", "d20 + 4", "d8", "d20"] {
+            let roll = sut
+                .roll_dices(
+                    &Caller::Trusted,
+                    &RollDicesRequest {
+                        dice_set: DiceSet::from_str(notation).unwrap(),
+                        fair_roll: None,
+                        metadata: RollMetadata {
+                            session_id: Some("session-1".to_string()),
+                            ..Default::default()
+                        },
+                        idempotency_key: None,
+                    },
+                )
+                .await
+                .unwrap();
+            session_rolls.push(roll);
+            sut.roll_dices(
+                &Caller::Trusted,
+                &RollDicesRequest {
+                    dice_set: DiceSet::from_str("d20").unwrap(),
+                    fair_roll: None,
+                    metadata: RollMetadata::default(),
+                    idempotency_key: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let mut req = ListDiceRollsRequest {
+            filter: RollFilter {
+                dice: Some(Dice::D20),
+                session_id: Some("session-1".to_string()),
+                ..Default::default()
+            },
+            after: None,
+            page_size: 2,
+        };
+        let first_page = sut.list_dice_rolls(&Caller::Trusted, &req).await.unwrap();
+        assert_eq!(
+            first_page.rolls,
+            vec![session_rolls[0].clone(), session_rolls[2].clone()]
+        );
+        assert_eq!(first_page.next_cursor, Some(session_rolls[2].id.clone()));
+
+        req.after = first_page.next_cursor;
+        let last_page = sut.list_dice_rolls(&Caller::Trusted, &req).await.unwrap();
+        assert_eq!(last_page.rolls, vec![session_rolls[4].clone()]);
+        assert_eq!(last_page.next_cursor, None);
+    }
+
+    async fn roll_in_session(
+        sut: &impl DiceService,
+        notation: &str,
+        session_id: &str,
+    ) -> RollDicesResponse {
+        sut.roll_dices(
+            &Caller::Trusted,
+            &RollDicesRequest {
+                dice_set: DiceSet::from_str(notation).unwrap(),
+                fair_roll: None,
+                metadata: RollMetadata {
+                    session_id: Some(session_id.to_string()),
+                    ..Default::default()
+                },
+                idempotency_key: None,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn can_watch_dice_rolls() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let mut feed = sut
+            .watch_dice_rolls(
+                &Caller::Trusted,
+                &WatchDiceRollsRequest {
+                    session_id: Some("table-1".to_string()),
+                    after: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        roll_in_session(&sut, "d20", "table-2").await;
+        let roll = roll_in_session(&sut, "2d6", "table-1").await;
+        assert_eq!(feed.recv().await.unwrap().unwrap(), roll);
+        assert!(feed.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn can_resume_watching_dice_rolls() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let last_seen = roll_in_session(&sut, "d20", "table-1").await;
+        let missed = roll_in_session(&sut, "d6", "table-1").await;
+
+        let mut feed = sut
+            .watch_dice_rolls(
+                &Caller::Trusted,
+                &WatchDiceRollsRequest {
+                    session_id: Some("table-1".to_string()),
+                    after: Some(last_seen.id),
+                },
+            )
+            .await
+            .unwrap();
+        let live = roll_in_session(&sut, "d8", "table-1").await;
+
+        assert_eq!(feed.recv().await.unwrap().unwrap(), missed);
+        assert_eq!(feed.recv().await.unwrap().unwrap(), live);
+        assert!(feed.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn cannot_watch_dice_rolls_without_keeping_up() {
+        let sut = Service::new(InMemoryDiceHistorySaver::default(), NoopMeter);
+        let mut feed = sut
+            .watch_dice_rolls(&Caller::Trusted, &WatchDiceRollsRequest::default())
+            .await
+            .unwrap();
+        for _ in 0..2 * (FEED_CAPACITY + WATCHER_CAPACITY) {
+            roll_in_session(&sut, "d4", "table-1").await;
+        }
+
+        let mut last = feed.recv().await;
+        while let Some(Ok(_)) = last {
+            last = feed.recv().await;
+        }
+        assert!(matches!(last, Some(Err(Error::RollFeedLagged))));
+    }
 }
@@ -0,0 +1,69 @@
+//! Tests the fairness of the dices over an export of the history of the rolls, such as the one
+//! of the Postgres storage:
+//!
+//! ```sh
+//! psql "$COF_DATABASE_URL" -c "\copy (SELECT dice, result FROM dice_rolls) TO 'rolls.csv' WITH CSV HEADER"
+//! fairness_report rolls.csv
+//! ```
+
+use std::fs::File;
+use std::io::{BufReader, stdin};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use cof::services::dice::{DEFAULT_SIGNIFICANCE, FairnessReport};
+
+/// The command line of the fairness report.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Tests the fairness of the dices over a CSV export of the history of the rolls"
+)]
+struct Cli {
+    /// The CSV export to read, each line holding a dice and the face rolled, the standard
+    /// input being read when none is given
+    export: Option<PathBuf>,
+
+    /// The p-value below which a dice is reported as biased
+    #[arg(short, long, default_value_t = DEFAULT_SIGNIFICANCE)]
+    significance: f64,
+}
+
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let report = match &cli.export {
+        Some(path) => {
+            FairnessReport::from_export(BufReader::new(File::open(path)?), cli.significance)?
+        }
+        None => FairnessReport::from_export(stdin().lock(), cli.significance)?,
+    };
+
+    println!(
+        "{:<6} {:>10} {:>12} {:>12}  verdict",
+        "dice", "rolls", "chi-squared", "p-value"
+    );
+    for fairness in &report.dices {
+        let verdict = if fairness.biased {
+            "biased"
+        } else if fairness.p_value < report.significance {
+            "not rolled enough"
+        } else {
+            "fair"
+        };
+        println!(
+            "{:<6} {:>10} {:>12.2} {:>12.2e}  {verdict}",
+            fairness.dice.to_string(),
+            fairness.roll_count(),
+            fairness.chi_squared,
+            fairness.p_value,
+        );
+    }
+
+    // the exit code lets the report be used as a check in scripts
+    Ok(if report.biased().next().is_some() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
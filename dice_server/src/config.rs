@@ -0,0 +1,659 @@
+//! Module that contains the configuration of the dice server, read from the command line, the
+//! `COF_*` environment variables and an optional TOML file, in this order of precedence.
+
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Args, Parser, ValueEnum};
+use cof::services::dice::implem::auth::{ApiKeys, AuthInterceptor, JwtAuthenticator};
+use cof::services::dice::implem::journal::{Durability, JournalConfig};
+use cof::services::dice::{DEFAULT_IDEMPOTENCY_RETENTION, DEFAULT_SIGNIFICANCE, Identity, Role};
+use serde::Deserialize;
+
+/// The address the gRPC server listens on when none is configured.
+const DEFAULT_LISTEN_ADDRESS: &str = "0.0.0.0:50052";
+
+/// The time left to the pending requests to complete once a shutdown is requested.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The interval between two checks of the fairness of the dices.
+const DEFAULT_FAIRNESS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cannot read the configuration file {0}: {1}")]
+    ReadFile(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot parse the configuration file {0}: {1}")]
+    ParseFile(PathBuf, #[source] toml::de::Error),
+
+    #[error("the {0} storage requires a database URL")]
+    MissingDatabaseUrl(StorageBackend),
+
+    #[error("the database URL {1} cannot be used by the {0} storage")]
+    IncompatibleDatabaseUrl(StorageBackend, String),
+
+    #[error("the journal storage requires a journal directory")]
+    MissingJournalDir,
+
+    #[error("the fairness significance {0} is not between 0 and 1")]
+    InvalidFairnessSignificance(f64),
+
+    #[error("the fairness check interval must be at least one second")]
+    InvalidFairnessCheckInterval,
+
+    #[error("cannot parse the API keys file {0}: {1}")]
+    ParseApiKeys(PathBuf, #[source] toml::de::Error),
+
+    #[error("cannot load the JWKS file {0}: {1:#}")]
+    LoadJwks(PathBuf, #[source] anyhow::Error),
+
+    #[error("the JWT issuer and audience require a JWKS file")]
+    MissingJwksFile,
+}
+
+/// The command line of the dice server.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "gRPC server rolling the dices of Chroniques Oubliées Fantasy"
+)]
+pub struct Cli {
+    /// The TOML file to read the settings from, the command line and the environment taking
+    /// precedence over it.
+    #[arg(short, long, env = "COF_CONFIG")]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    settings: Settings,
+}
+
+impl Cli {
+    /// Merges the settings of the command line with the ones of the configuration file and
+    /// checks them.
+    ///
+    /// # Errors
+    ///
+    /// [`Error`] if the configuration file cannot be read or if the settings are inconsistent.
+    pub fn into_config(self) -> Result<Config, Error> {
+        let settings = match &self.config {
+            Some(path) => self.settings.or(Settings::from_file(path)?),
+            None => self.settings,
+        };
+        Config::try_from(settings)
+    }
+}
+
+/// The settings of the dice server, every one of them being optional in each of its sources.
+#[derive(Debug, Default, Args, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Settings {
+    /// The address the gRPC server listens on [default: 0.0.0.0:50052]
+    #[arg(long, env = "COF_LISTEN_ADDRESS")]
+    listen_address: Option<SocketAddr>,
+
+    /// The seconds left to the pending requests to complete once a shutdown is requested
+    /// [default: 10]
+    #[arg(long, env = "COF_DRAIN_TIMEOUT_SECS")]
+    drain_timeout_secs: Option<u64>,
+
+    /// Where the history of the rolls is saved [default: inferred from the other storage
+    /// settings, memory when there are none]
+    #[arg(long, env = "COF_STORAGE", value_enum)]
+    storage: Option<StorageBackend>,
+
+    /// The URL of the database of the postgres and sqlite storages
+    #[arg(long, env = "COF_DATABASE_URL")]
+    database_url: Option<String>,
+
+    /// The directory of the journal storage
+    #[arg(long, env = "COF_JOURNAL_DIR")]
+    journal_dir: Option<PathBuf>,
+
+    /// When the journal is synced to the disk: `always`, `os-managed` or an interval in
+    /// milliseconds (e.g. `200ms`) [default: always]
+    #[arg(long, env = "COF_JOURNAL_DURABILITY")]
+    journal_durability: Option<JournalDurability>,
+
+    /// How the rolls are measured [default: opentelemetry]
+    #[arg(long, env = "COF_METER", value_enum)]
+    meter: Option<MeterBackend>,
+
+    /// The seconds between two checks of the fairness of the dices [default: 60]
+    #[arg(long, env = "COF_FAIRNESS_CHECK_INTERVAL_SECS")]
+    fairness_check_interval_secs: Option<u64>,
+
+    /// The p-value below which a dice is reported as biased [default: 0.001]
+    #[arg(long, env = "COF_FAIRNESS_SIGNIFICANCE")]
+    fairness_significance: Option<f64>,
+
+    /// The seconds the idempotency keys of the roll requests are remembered, a retried request
+    /// returning the roll it has made during this time [default: 86400]
+    #[arg(long, env = "COF_IDEMPOTENCY_RETENTION_SECS")]
+    idempotency_retention_secs: Option<u64>,
+
+    /// The TOML file of the API keys the players and Game Masters authenticate with, e.g.
+    /// `[[keys]]` tables of a `key`, a `subject` and a `role` (`player` or `gm`)
+    #[arg(long, env = "COF_API_KEYS_FILE")]
+    api_keys_file: Option<PathBuf>,
+
+    /// The JWKS file of the keys the JSON Web Tokens of the players and Game Masters are
+    /// signed with
+    #[arg(long, env = "COF_JWKS_FILE")]
+    jwks_file: Option<PathBuf>,
+
+    /// The issuer the JSON Web Tokens must be issued by, their `iss` claim
+    #[arg(long, env = "COF_JWT_ISSUER")]
+    jwt_issuer: Option<String>,
+
+    /// The audience the JSON Web Tokens must be issued for, their `aud` claim
+    #[arg(long, env = "COF_JWT_AUDIENCE")]
+    jwt_audience: Option<String>,
+
+    /// Where the logs are exported [default: stdout]
+    #[arg(long, env = "COF_LOG_EXPORTER", value_enum)]
+    log_exporter: Option<Exporter>,
+
+    /// Where the metrics are exported [default: stdout]
+    #[arg(long, env = "COF_METRIC_EXPORTER", value_enum)]
+    metric_exporter: Option<Exporter>,
+
+    /// Where the traces are exported [default: none]
+    #[arg(long, env = "COF_TRACE_EXPORTER", value_enum)]
+    trace_exporter: Option<Exporter>,
+
+    /// The URL of the OTLP collector the OTLP exporters send to, e.g. `http://collector:4317`
+    /// for gRPC or `http://collector:4318` for HTTP [default: the `OTEL_EXPORTER_OTLP_*`
+    /// variables, then the local collector]
+    #[arg(long, env = "COF_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+}
+
+impl Settings {
+    fn from_file(path: &Path) -> Result<Self, Error> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| Error::ReadFile(path.to_path_buf(), e))?;
+        toml::from_str(&content).map_err(|e| Error::ParseFile(path.to_path_buf(), e))
+    }
+
+    /// Returns the settings completed with the ones of `other` that are not set.
+    fn or(self, other: Self) -> Self {
+        Self {
+            listen_address: self.listen_address.or(other.listen_address),
+            drain_timeout_secs: self.drain_timeout_secs.or(other.drain_timeout_secs),
+            storage: self.storage.or(other.storage),
+            database_url: self.database_url.or(other.database_url),
+            journal_dir: self.journal_dir.or(other.journal_dir),
+            journal_durability: self.journal_durability.or(other.journal_durability),
+            meter: self.meter.or(other.meter),
+            fairness_check_interval_secs: self
+                .fairness_check_interval_secs
+                .or(other.fairness_check_interval_secs),
+            fairness_significance: self.fairness_significance.or(other.fairness_significance),
+            idempotency_retention_secs: self
+                .idempotency_retention_secs
+                .or(other.idempotency_retention_secs),
+            api_keys_file: self.api_keys_file.or(other.api_keys_file),
+            jwks_file: self.jwks_file.or(other.jwks_file),
+            jwt_issuer: self.jwt_issuer.or(other.jwt_issuer),
+            jwt_audience: self.jwt_audience.or(other.jwt_audience),
+            log_exporter: self.log_exporter.or(other.log_exporter),
+            metric_exporter: self.metric_exporter.or(other.metric_exporter),
+            trace_exporter: self.trace_exporter.or(other.trace_exporter),
+            otlp_endpoint: self.otlp_endpoint.or(other.otlp_endpoint),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageBackend {
+    Postgres,
+    Sqlite,
+    Journal,
+    Memory,
+}
+
+impl Display for StorageBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageBackend::Postgres => write!(f, "postgres"),
+            StorageBackend::Sqlite => write!(f, "sqlite"),
+            StorageBackend::Journal => write!(f, "journal"),
+            StorageBackend::Memory => write!(f, "memory"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MeterBackend {
+    #[default]
+    Opentelemetry,
+    Noop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Exporter {
+    #[default]
+    Stdout,
+    OtlpGrpc,
+    OtlpHttp,
+    None,
+}
+
+/// Where the logs, the metrics and the traces of the dice server are exported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryConfig {
+    pub log_exporter: Exporter,
+    pub metric_exporter: Exporter,
+    pub trace_exporter: Exporter,
+    pub otlp_endpoint: Option<String>,
+}
+
+/// How the fairness of the dices is checked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FairnessConfig {
+    pub check_interval: Duration,
+    pub significance: f64,
+}
+
+/// The API keys file, each key being issued to a player or a Game Master.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKeysFile {
+    keys: Vec<ApiKey>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKey {
+    key: String,
+    subject: String,
+    #[serde(default, deserialize_with = "deserialize_role")]
+    role: Role,
+}
+
+fn deserialize_role<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Role, D::Error> {
+    let role = String::deserialize(deserializer)?;
+    role.parse().map_err(serde::de::Error::custom)
+}
+
+/// Reads the API keys file.
+fn read_api_keys(path: &Path) -> Result<ApiKeys, Error> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| Error::ReadFile(path.to_path_buf(), e))?;
+    let ApiKeysFile { keys } =
+        toml::from_str(&content).map_err(|e| Error::ParseApiKeys(path.to_path_buf(), e))?;
+    Ok(ApiKeys::new(keys.into_iter().map(|key| {
+        let identity = Identity {
+            subject: key.subject,
+            role: key.role,
+        };
+        (key.key, identity)
+    })))
+}
+
+/// Reads the JWKS file.
+fn read_jwks(path: &Path) -> Result<JwtAuthenticator, Error> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| Error::ReadFile(path.to_path_buf(), e))?;
+    JwtAuthenticator::from_jwks(&content).map_err(|e| Error::LoadJwks(path.to_path_buf(), e))
+}
+
+/// The [`Durability`] of the journal, parsed from the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+struct JournalDurability(Durability);
+
+impl FromStr for JournalDurability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(Self(Durability::Always)),
+            "os-managed" => Ok(Self(Durability::OsManaged)),
+            _ => s
+                .strip_suffix("ms")
+                .and_then(|millis| millis.parse().ok())
+                .map(|millis| Self(Durability::Interval(Duration::from_millis(millis))))
+                .ok_or_else(|| format!("unknown journal durability {s}")),
+        }
+    }
+}
+
+impl TryFrom<String> for JournalDurability {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// The storage of the history of the rolls.
+#[derive(Debug, Clone)]
+pub enum Storage {
+    Postgres(String),
+    Sqlite(String),
+    Journal(JournalConfig),
+    Memory,
+}
+
+/// The checked configuration of the dice server.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub listen_address: SocketAddr,
+    pub drain_timeout: Duration,
+    pub storage: Storage,
+    pub meter: MeterBackend,
+    pub fairness: FairnessConfig,
+    pub idempotency_retention: Duration,
+    /// How the callers of the gRPC API are authenticated, anyone can call it when `None`.
+    pub auth: Option<AuthInterceptor>,
+    pub telemetry: TelemetryConfig,
+}
+
+impl TryFrom<Settings> for Config {
+    type Error = Error;
+
+    fn try_from(settings: Settings) -> Result<Self, Self::Error> {
+        let scheme = settings
+            .database_url
+            .as_deref()
+            .and_then(|url| url.split_once(':'))
+            .map(|(scheme, _)| scheme);
+        let backend = match (settings.storage, scheme) {
+            (Some(backend), _) => backend,
+            (None, Some("sqlite")) => StorageBackend::Sqlite,
+            (None, Some(_)) => StorageBackend::Postgres,
+            (None, None) if settings.journal_dir.is_some() => StorageBackend::Journal,
+            (None, None) => StorageBackend::Memory,
+        };
+
+        let database_url = |schemes: &[&str]| match (&settings.database_url, scheme) {
+            (Some(url), Some(scheme)) if schemes.contains(&scheme) => Ok(url.clone()),
+            (Some(url), _) => Err(Error::IncompatibleDatabaseUrl(backend, url.clone())),
+            (None, _) => Err(Error::MissingDatabaseUrl(backend)),
+        };
+        let storage = match backend {
+            StorageBackend::Postgres => {
+                Storage::Postgres(database_url(&["postgres", "postgresql"])?)
+            }
+            StorageBackend::Sqlite => Storage::Sqlite(database_url(&["sqlite"])?),
+            _ if settings.database_url.is_some() => {
+                let url = settings.database_url.unwrap_or_default();
+                return Err(Error::IncompatibleDatabaseUrl(backend, url));
+            }
+            StorageBackend::Journal => {
+                let dir = settings.journal_dir.ok_or(Error::MissingJournalDir)?;
+                let durability = settings.journal_durability.map(|d| d.0).unwrap_or_default();
+                Storage::Journal(JournalConfig::new(dir).with_durability(durability))
+            }
+            StorageBackend::Memory => Storage::Memory,
+        };
+
+        let significance = settings
+            .fairness_significance
+            .unwrap_or(DEFAULT_SIGNIFICANCE);
+        if !(significance > 0.0 && significance < 1.0) {
+            return Err(Error::InvalidFairnessSignificance(significance));
+        }
+        let check_interval = settings
+            .fairness_check_interval_secs
+            .map_or(DEFAULT_FAIRNESS_CHECK_INTERVAL, Duration::from_secs);
+        if check_interval.is_zero() {
+            return Err(Error::InvalidFairnessCheckInterval);
+        }
+
+        let api_keys = settings
+            .api_keys_file
+            .as_deref()
+            .map(read_api_keys)
+            .transpose()?;
+        let jwt = match settings.jwks_file.as_deref() {
+            Some(path) => {
+                let mut jwt = read_jwks(path)?;
+                if let Some(issuer) = settings.jwt_issuer {
+                    jwt = jwt.with_issuer(issuer);
+                }
+                if let Some(audience) = settings.jwt_audience {
+                    jwt = jwt.with_audience(audience);
+                }
+                Some(jwt)
+            }
+            None if settings.jwt_issuer.is_some() || settings.jwt_audience.is_some() => {
+                return Err(Error::MissingJwksFile);
+            }
+            None => None,
+        };
+        let auth = match (api_keys, jwt) {
+            (Some(api_keys), Some(jwt)) => Some(AuthInterceptor::new(api_keys).or(jwt)),
+            (Some(api_keys), None) => Some(AuthInterceptor::new(api_keys)),
+            (None, Some(jwt)) => Some(AuthInterceptor::new(jwt)),
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            listen_address: settings
+                .listen_address
+                .unwrap_or_else(|| DEFAULT_LISTEN_ADDRESS.parse().unwrap()),
+            drain_timeout: settings
+                .drain_timeout_secs
+                .map_or(DEFAULT_DRAIN_TIMEOUT, Duration::from_secs),
+            storage,
+            meter: settings.meter.unwrap_or_default(),
+            fairness: FairnessConfig {
+                check_interval,
+                significance,
+            },
+            idempotency_retention: settings
+                .idempotency_retention_secs
+                .map_or(DEFAULT_IDEMPOTENCY_RETENTION, Duration::from_secs),
+            auth,
+            telemetry: TelemetryConfig {
+                log_exporter: settings.log_exporter.unwrap_or_default(),
+                metric_exporter: settings.metric_exporter.unwrap_or_default(),
+                trace_exporter: settings.trace_exporter.unwrap_or(Exporter::None),
+                otlp_endpoint: settings.otlp_endpoint,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config, Error> {
+        let args = std::iter::once("dice_server").chain(args.iter().copied());
+        Cli::try_parse_from(args).unwrap().into_config()
+    }
+
+    #[test]
+    fn can_configure_from_command_line() {
+        let config = parse(&[]).unwrap();
+        assert_eq!(config.listen_address, "0.0.0.0:50052".parse().unwrap());
+        assert_eq!(config.drain_timeout, DEFAULT_DRAIN_TIMEOUT);
+        assert!(matches!(config.storage, Storage::Memory));
+        assert_eq!(config.meter, MeterBackend::Opentelemetry);
+        assert_eq!(
+            config.fairness,
+            FairnessConfig {
+                check_interval: DEFAULT_FAIRNESS_CHECK_INTERVAL,
+                significance: DEFAULT_SIGNIFICANCE,
+            }
+        );
+        assert_eq!(config.idempotency_retention, DEFAULT_IDEMPOTENCY_RETENTION);
+        assert!(config.auth.is_none());
+        assert_eq!(config.telemetry.log_exporter, Exporter::Stdout);
+        assert_eq!(config.telemetry.trace_exporter, Exporter::None);
+
+        let config = parse(&[
+            "--listen-address",
+            "127.0.0.1:6000",
+            "--drain-timeout-secs",
+            "30",
+            "--database-url",
+            "sqlite://rolls.db",
+            "--meter",
+            "noop",
+            "--fairness-check-interval-secs",
+            "300",
+            "--fairness-significance",
+            "0.01",
+            "--idempotency-retention-secs",
+            "3600",
+            "--metric-exporter",
+            "none",
+            "--trace-exporter",
+            "otlp-grpc",
+            "--otlp-endpoint",
+            "http://collector:4317",
+        ])
+        .unwrap();
+        assert_eq!(config.listen_address, "127.0.0.1:6000".parse().unwrap());
+        assert_eq!(config.drain_timeout, Duration::from_secs(30));
+        assert!(matches!(config.storage, Storage::Sqlite(url) if url == "sqlite://rolls.db"));
+        assert_eq!(config.meter, MeterBackend::Noop);
+        assert_eq!(
+            config.fairness,
+            FairnessConfig {
+                check_interval: Duration::from_secs(300),
+                significance: 0.01,
+            }
+        );
+        assert_eq!(config.idempotency_retention, Duration::from_secs(3600));
+        assert_eq!(
+            config.telemetry,
+            TelemetryConfig {
+                log_exporter: Exporter::Stdout,
+                metric_exporter: Exporter::None,
+                trace_exporter: Exporter::OtlpGrpc,
+                otlp_endpoint: Some("http://collector:4317".to_string()),
+            }
+        );
+
+        let config = parse(&["--journal-dir", "rolls", "--journal-durability", "200ms"]).unwrap();
+        assert!(matches!(config.storage, Storage::Journal(_)));
+    }
+
+    #[test]
+    fn can_merge_configuration_file() {
+        let file: Settings = toml::from_str(
+            r#"
+            listen-address = "127.0.0.1:6000"
+            storage = "postgres"
+            database-url = "postgres://cof@db/rolls"
+            meter = "noop"
+            "#,
+        )
+        .unwrap();
+        let cli = Settings {
+            meter: Some(MeterBackend::Opentelemetry),
+            ..Default::default()
+        };
+
+        let config = Config::try_from(cli.or(file)).unwrap();
+        assert_eq!(config.listen_address, "127.0.0.1:6000".parse().unwrap());
+        assert!(
+            matches!(config.storage, Storage::Postgres(url) if url == "postgres://cof@db/rolls")
+        );
+        assert_eq!(config.meter, MeterBackend::Opentelemetry);
+
+        assert!(toml::from_str::<Settings>("listen-port = 6000").is_err());
+    }
+
+    #[test]
+    fn can_configure_authentication() {
+        let dir = tempfile::tempdir().unwrap();
+        let api_keys = dir.path().join("api_keys.toml");
+        std::fs::write(
+            &api_keys,
+            r#"
+            [[keys]]
+            key = "aldric-key"
+            subject = "aldric"
+
+            [[keys]]
+            key = "gm-key"
+            subject = "gm"
+            role = "gm"
+            "#,
+        )
+        .unwrap();
+        let jwks = dir.path().join("jwks.json");
+        std::fs::write(&jwks, r#"{"keys": [{"kty": "oct", "k": "c2VjcmV0"}]}"#).unwrap();
+
+        let config = parse(&[
+            "--api-keys-file",
+            api_keys.to_str().unwrap(),
+            "--jwks-file",
+            jwks.to_str().unwrap(),
+            "--jwt-audience",
+            "dice_server",
+        ])
+        .unwrap();
+        let mut metadata = tonic::metadata::MetadataMap::new();
+        metadata.insert("authorization", "Bearer gm-key".parse().unwrap());
+        assert_eq!(
+            config.auth.unwrap().authenticate(&metadata).unwrap(),
+            Identity::game_master("gm")
+        );
+
+        assert!(matches!(
+            parse(&["--jwt-issuer", "https://auth.example.org"]),
+            Err(Error::MissingJwksFile)
+        ));
+        std::fs::write(
+            &api_keys,
+            "[[keys]]\nkey = \"key\"\nsubject = \"gm\"\nrole = \"dm\"",
+        )
+        .unwrap();
+        assert!(matches!(
+            parse(&["--api-keys-file", api_keys.to_str().unwrap()]),
+            Err(Error::ParseApiKeys(..))
+        ));
+        std::fs::write(&jwks, r#"{"keys": []}"#).unwrap();
+        assert!(matches!(
+            parse(&["--jwks-file", jwks.to_str().unwrap()]),
+            Err(Error::LoadJwks(..))
+        ));
+    }
+
+    #[test]
+    fn cannot_configure_inconsistent_storage() {
+        assert!(matches!(
+            parse(&["--storage", "postgres"]),
+            Err(Error::MissingDatabaseUrl(StorageBackend::Postgres))
+        ));
+        assert!(matches!(
+            parse(&["--storage", "postgres", "--database-url", "sqlite::memory:"]),
+            Err(Error::IncompatibleDatabaseUrl(StorageBackend::Postgres, _))
+        ));
+        assert!(matches!(
+            parse(&["--storage", "memory", "--database-url", "sqlite::memory:"]),
+            Err(Error::IncompatibleDatabaseUrl(StorageBackend::Memory, _))
+        ));
+        assert!(matches!(
+            parse(&["--storage", "journal"]),
+            Err(Error::MissingJournalDir)
+        ));
+        assert!(matches!(
+            parse(&["--fairness-significance", "1.5"]),
+            Err(Error::InvalidFairnessSignificance(_))
+        ));
+        assert!(matches!(
+            parse(&["--fairness-check-interval-secs", "0"]),
+            Err(Error::InvalidFairnessCheckInterval)
+        ));
+        assert!(matches!(
+            parse(&["--config", "/nonexistent/dice_server.toml"]),
+            Err(Error::ReadFile(..))
+        ));
+        assert!(Cli::try_parse_from(["dice_server", "--journal-durability", "sometimes"]).is_err());
+    }
+}
@@ -1,35 +1,47 @@
-use opentelemetry::{logs::LoggerProvider, metrics::MeterProvider};
+use opentelemetry::{logs::LoggerProvider, metrics::MeterProvider, trace::TracerProvider};
 use opentelemetry_appender_tracing::layer;
+use opentelemetry_otlp::{ExporterBuildError, WithExportConfig};
 use opentelemetry_sdk::{
-    Resource,
-    logs::{LogExporter, SdkLoggerProvider},
-    metrics::{SdkMeterProvider, exporter::PushMetricExporter},
+    Resource, logs::SdkLoggerProvider, metrics::SdkMeterProvider, trace::SdkTracerProvider,
 };
 use tracing_subscriber::{EnvFilter, prelude::*};
 
+use crate::config::{Exporter, TelemetryConfig};
+
 const SERVICE_NAME: &str = env!("CARGO_PKG_NAME");
 
 #[derive(Debug)]
-pub struct OpenTelemetryMonitor<L: LoggerProvider, M: MeterProvider> {
+#[allow(clippy::struct_field_names)]
+pub struct OpenTelemetryMonitor<L: LoggerProvider, M: MeterProvider, T: TracerProvider> {
     pub logger_provider: L,
     pub meter_provider: M,
+    pub tracer_provider: T,
 }
 
-impl<L, M> OpenTelemetryMonitor<L, M>
+impl<L, M, T> OpenTelemetryMonitor<L, M, T>
 where
     L: LoggerProvider,
     M: MeterProvider,
+    T: TracerProvider,
 {
+    /// Sets up the providers of the SDK with the exporters of the configuration and routes the
+    /// logs of the server to the logger provider.
+    ///
+    /// # Errors
+    ///
+    /// [`ExporterBuildError`] if an OTLP exporter cannot be built, e.g. because of an invalid
+    /// endpoint.
     pub fn new_with_sdk(
-        log_exporter: impl LogExporter + 'static,
-        metric_exporter: impl PushMetricExporter,
-    ) -> OpenTelemetryMonitor<SdkLoggerProvider, SdkMeterProvider> {
+        config: &TelemetryConfig,
+    ) -> Result<
+        OpenTelemetryMonitor<SdkLoggerProvider, SdkMeterProvider, SdkTracerProvider>,
+        ExporterBuildError,
+    > {
         let resource = Resource::builder().with_service_name(SERVICE_NAME).build();
 
-        let logger_provider = SdkLoggerProvider::builder()
-            .with_resource(resource.clone())
-            .with_simple_exporter(log_exporter)
-            .build();
+        let logger_provider = build_logger_provider(config, resource.clone())?;
+        let meter_provider = build_meter_provider(config, resource.clone())?;
+        let tracer_provider = build_tracer_provider(config, resource)?;
 
         // Remove all dependency logs from data send via Opentelemetry
         let filter_otel_tracing_bridge = EnvFilter::from_default_env()
@@ -40,6 +52,15 @@ where
             .add_directive("reqwest=off".parse().unwrap())
             .add_directive("sqlx=off".parse().unwrap());
 
+        // Only trace the calls of the server, the spans of the dependencies being too verbose
+        let filter_otel_tracing_layer = EnvFilter::from_default_env()
+            .add_directive("opentelemetry=off".parse().unwrap())
+            .add_directive("hyper=off".parse().unwrap())
+            .add_directive("tonic=off".parse().unwrap())
+            .add_directive("h2=off".parse().unwrap())
+            .add_directive("reqwest=off".parse().unwrap())
+            .add_directive("sqlx=off".parse().unwrap());
+
         // Force INFO level on dependencies
         let filter_fmt = EnvFilter::from_default_env()
             .add_directive("opentelemetry=info".parse().unwrap())
@@ -54,17 +75,258 @@ where
                 layer::OpenTelemetryTracingBridge::new(&logger_provider)
                     .with_filter(filter_otel_tracing_bridge),
             )
+            .with(
+                tracing_opentelemetry::layer()
+                    .with_tracer(tracer_provider.tracer(SERVICE_NAME))
+                    .with_filter(filter_otel_tracing_layer),
+            )
             .with(tracing_subscriber::fmt::layer().with_filter(filter_fmt))
             .init();
 
-        let meter_provider = SdkMeterProvider::builder()
-            .with_periodic_exporter(metric_exporter)
-            .with_resource(resource.clone())
-            .build();
+        Ok(
+            OpenTelemetryMonitor::<SdkLoggerProvider, SdkMeterProvider, SdkTracerProvider> {
+                logger_provider,
+                meter_provider,
+                tracer_provider,
+            },
+        )
+    }
+}
+
+/// Returns the URL an OTLP exporter sends the given signal to, the HTTP exporters expecting the
+/// full URL of the signal.
+fn otlp_endpoint(config: &TelemetryConfig, exporter: Exporter, signal: &str) -> Option<String> {
+    let endpoint = config.otlp_endpoint.as_deref()?;
+    match exporter {
+        Exporter::OtlpHttp => Some(format!("{}/v1/{signal}", endpoint.trim_end_matches('/'))),
+        _ => Some(endpoint.to_string()),
+    }
+}
+
+fn build_logger_provider(
+    config: &TelemetryConfig,
+    resource: Resource,
+) -> Result<SdkLoggerProvider, ExporterBuildError> {
+    let builder = SdkLoggerProvider::builder().with_resource(resource);
+    let endpoint = otlp_endpoint(config, config.log_exporter, "logs");
+    let builder = match config.log_exporter {
+        Exporter::Stdout => {
+            builder.with_simple_exporter(opentelemetry_stdout::LogExporter::default())
+        }
+        Exporter::OtlpGrpc => {
+            let mut exporter = opentelemetry_otlp::LogExporter::builder().with_tonic();
+            if let Some(endpoint) = endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            builder.with_batch_exporter(exporter.build()?)
+        }
+        Exporter::OtlpHttp => {
+            let mut exporter = opentelemetry_otlp::LogExporter::builder().with_http();
+            if let Some(endpoint) = endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            builder.with_batch_exporter(exporter.build()?)
+        }
+        Exporter::None => builder,
+    };
+    Ok(builder.build())
+}
+
+fn build_meter_provider(
+    config: &TelemetryConfig,
+    resource: Resource,
+) -> Result<SdkMeterProvider, ExporterBuildError> {
+    let builder = SdkMeterProvider::builder().with_resource(resource);
+    let endpoint = otlp_endpoint(config, config.metric_exporter, "metrics");
+    let builder = match config.metric_exporter {
+        Exporter::Stdout => builder
+            .with_periodic_exporter(opentelemetry_stdout::MetricExporterBuilder::default().build()),
+        Exporter::OtlpGrpc => {
+            let mut exporter = opentelemetry_otlp::MetricExporter::builder().with_tonic();
+            if let Some(endpoint) = endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            builder.with_periodic_exporter(exporter.build()?)
+        }
+        Exporter::OtlpHttp => {
+            let mut exporter = opentelemetry_otlp::MetricExporter::builder().with_http();
+            if let Some(endpoint) = endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            builder.with_periodic_exporter(exporter.build()?)
+        }
+        Exporter::None => builder,
+    };
+    Ok(builder.build())
+}
+
+fn build_tracer_provider(
+    config: &TelemetryConfig,
+    resource: Resource,
+) -> Result<SdkTracerProvider, ExporterBuildError> {
+    let builder = SdkTracerProvider::builder().with_resource(resource);
+    let endpoint = otlp_endpoint(config, config.trace_exporter, "traces");
+    let builder = match config.trace_exporter {
+        Exporter::Stdout => {
+            builder.with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+        }
+        Exporter::OtlpGrpc => {
+            let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_tonic();
+            if let Some(endpoint) = endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            builder.with_batch_exporter(exporter.build()?)
+        }
+        Exporter::OtlpHttp => {
+            let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_http();
+            if let Some(endpoint) = endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            builder.with_batch_exporter(exporter.build()?)
+        }
+        Exporter::None => builder,
+    };
+    Ok(builder.build())
+}
 
-        OpenTelemetryMonitor::<SdkLoggerProvider, SdkMeterProvider> {
-            logger_provider,
-            meter_provider,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use opentelemetry::logs::{AnyValue, LogRecord, Logger};
+    use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+    use opentelemetry_proto::tonic::collector::metrics::v1::metrics_service_server::{
+        MetricsService, MetricsServiceServer,
+    };
+    use opentelemetry_proto::tonic::collector::metrics::v1::{
+        ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+    };
+    use prost::Message;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tonic::transport::Server;
+    use tonic::transport::server::TcpIncoming;
+    use tonic::{Request, Response, Status};
+
+    fn otlp_config(exporter: Exporter, endpoint: String) -> TelemetryConfig {
+        TelemetryConfig {
+            log_exporter: exporter,
+            metric_exporter: exporter,
+            trace_exporter: exporter,
+            otlp_endpoint: Some(endpoint),
         }
     }
+
+    /// A stand-in for the metrics service of an OTLP collector.
+    struct MetricsReceiver(mpsc::UnboundedSender<ExportMetricsServiceRequest>);
+
+    #[tonic::async_trait]
+    impl MetricsService for MetricsReceiver {
+        async fn export(
+            &self,
+            request: Request<ExportMetricsServiceRequest>,
+        ) -> Result<Response<ExportMetricsServiceResponse>, Status> {
+            self.0.send(request.into_inner()).ok();
+            Ok(Response::new(ExportMetricsServiceResponse::default()))
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn can_export_metrics_over_otlp_grpc() {
+        let incoming = TcpIncoming::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = incoming.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(
+            Server::builder()
+                .add_service(MetricsServiceServer::new(MetricsReceiver(tx)))
+                .serve_with_incoming(incoming),
+        );
+
+        let config = otlp_config(Exporter::OtlpGrpc, format!("http://{addr}"));
+        let resource = Resource::builder().with_service_name(SERVICE_NAME).build();
+        let meter_provider = build_meter_provider(&config, resource).unwrap();
+        meter_provider
+            .meter("dice_service")
+            .u64_counter("dice_rolls")
+            .build()
+            .add(1, &[]);
+        tokio::task::spawn_blocking(move || meter_provider.force_flush())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let request = rx.recv().await.unwrap();
+        let metric_names = request
+            .resource_metrics
+            .iter()
+            .flat_map(|rm| &rm.scope_metrics)
+            .flat_map(|sm| &sm.metrics)
+            .map(|m| m.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(metric_names, ["dice_rolls"]);
+    }
+
+    /// Accepts a single HTTP request as a stand-in for an OTLP collector, and returns its path
+    /// along with its body.
+    async fn receive_http_request(listener: TcpListener) -> (String, Vec<u8>) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        stream.read_line(&mut request_line).await.unwrap();
+        let mut content_len = 0;
+        loop {
+            let mut header = String::new();
+            stream.read_line(&mut header).await.unwrap();
+            let header = header.trim_end().to_ascii_lowercase();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(len) = header.strip_prefix("content-length:") {
+                content_len = len.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_len];
+        stream.read_exact(&mut body).await.unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: application/x-protobuf\r\ncontent-length: 0\r\n\r\n")
+            .await
+            .unwrap();
+
+        let path = request_line.split(' ').nth(1).unwrap().to_string();
+        (path, body)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn can_export_logs_over_otlp_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let received = tokio::spawn(receive_http_request(listener));
+
+        let config = otlp_config(Exporter::OtlpHttp, format!("http://{addr}/"));
+        let resource = Resource::builder().with_service_name(SERVICE_NAME).build();
+        let logger_provider = build_logger_provider(&config, resource).unwrap();
+        let logger = logger_provider.logger("dice_service");
+        let mut record = logger.create_log_record();
+        record.set_body(AnyValue::from("Rolled 2d6"));
+        logger.emit(record);
+        tokio::task::spawn_blocking(move || logger_provider.force_flush())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let (path, body) = received.await.unwrap();
+        assert_eq!(path, "/v1/logs");
+        let request = ExportLogsServiceRequest::decode(body.as_slice()).unwrap();
+        let bodies = request
+            .resource_logs
+            .iter()
+            .flat_map(|rl| &rl.scope_logs)
+            .flat_map(|sl| &sl.log_records)
+            .filter_map(|lr| lr.body.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies.len(), 1);
+        assert!(format!("{:?}", bodies[0]).contains("Rolled 2d6"));
+    }
 }